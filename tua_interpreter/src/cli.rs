@@ -0,0 +1,408 @@
+use crate::prelude::*;
+use std::process::ExitCode;
+
+
+
+pub const EXIT_SUCCESS: u8 = 0;
+pub const EXIT_COMPILE_ERRORS: u8 = 1;
+pub const EXIT_USAGE_ERROR: u8 = 2;
+pub const EXIT_FATAL_ERROR: u8 = 3;
+pub const EXIT_RUNTIME_ERROR: u8 = 4;
+
+pub const HELP_TEXT: &str = "\
+Usage: tua_interpreter <command> [path] [options] [-- <program args>]
+
+Commands:
+    check     Compile the project and report any errors
+    run       Compile the project and run its entry function
+    tokens    Print the tokens of every file in the project
+    ast       Print the parsed definitions of every file in the project
+    test      Compile the project and run its tests
+    help      Print this message
+
+Arguments:
+    [path]            A project directory or a single `.tua` file (defaults to the current directory)
+    <program args>    Arguments given to the entry function when using `run`
+
+Options:
+    --entry <name>        Function to start at when using `run` (defaults to `main`)
+    --log-level <level>   How much to print: `quiet`, `normal`, or `verbose` (defaults to `normal`)
+    -q, --quiet           Same as `--log-level quiet`
+    -v, --verbose         Same as `--log-level verbose`
+    --format <format>     How `tokens` and `ast` are printed: `pretty` or `compact` (defaults to `pretty`)
+    --error-format <format>
+                          How errors are printed: `pretty` or `json` (defaults to `pretty`)
+                          `json` prints one object per line with the fields severity, variant,
+                          message, path, start_line, start_column, end_line, end_column,
+                          expected, found, and note
+    --debug               Show where in the compiler each error was created
+    -h, --help            Print this message
+
+Exit codes:
+    0    Success
+    1    The project has compile errors
+    2    Invalid command-line arguments
+    3    Fatal error (for example, the project could not be loaded)
+    4    The program crashed or a test failed
+
+`run` and `test` don't run anything when the project has compile errors, even if the
+errors are in code that the entry function doesn't use.
+";
+
+
+
+
+
+#[derive(Debug)]
+pub struct CliArgs {
+    pub command: CliCommand,
+    pub path: PathBuf,
+    pub entry: String,
+    pub log_level: LogLevel,
+    pub output_format: OutputFormat,
+    pub error_format: ErrorFormat,
+    pub program_args: Vec<String>,
+    pub debug: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CliCommand {
+    Check,
+    Run,
+    Tokens,
+    Ast,
+    Test,
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum LogLevel {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Pretty,
+    Compact,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorFormat {
+    Pretty,
+    Json,
+}
+
+
+
+
+
+pub fn parse_args (args: impl IntoIterator<Item = String>) -> Result<CliArgs, String> {
+    let mut args = args.into_iter();
+
+    // command
+    let command = match args.next().as_deref() {
+        Some("check") => CliCommand::Check,
+        Some("run") => CliCommand::Run,
+        Some("tokens") => CliCommand::Tokens,
+        Some("ast") => CliCommand::Ast,
+        Some("test") => CliCommand::Test,
+        Some("help" | "-h" | "--help") => CliCommand::Help,
+        Some(other) => return Err(format!("unknown command `{other}`")),
+        None => return Err(String::from("no command given")),
+    };
+
+    let mut output = CliArgs {
+        command,
+        path: PathBuf::from("."),
+        entry: String::from("main"),
+        log_level: LogLevel::Normal,
+        output_format: OutputFormat::Pretty,
+        error_format: ErrorFormat::Pretty,
+        program_args: vec!(),
+        debug: false,
+    };
+
+    // options
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+        let mut get_value = || inline_value.clone().or_else(|| args.next()).ok_or_else(|| format!("expected a value after `{name}`"));
+        match &*name {
+            "--entry" => output.entry = get_value()?,
+            "--log-level" => {
+                output.log_level = match &*get_value()? {
+                    "quiet" => LogLevel::Quiet,
+                    "normal" => LogLevel::Normal,
+                    "verbose" => LogLevel::Verbose,
+                    other => return Err(format!("unknown log level `{other}`, expected `quiet`, `normal`, or `verbose`")),
+                };
+            }
+            "-q" | "--quiet" => output.log_level = LogLevel::Quiet,
+            "-v" | "--verbose" => output.log_level = LogLevel::Verbose,
+            "--format" => {
+                output.output_format = match &*get_value()? {
+                    "pretty" => OutputFormat::Pretty,
+                    "compact" => OutputFormat::Compact,
+                    other => return Err(format!("unknown format `{other}`, expected `pretty` or `compact`")),
+                };
+            }
+            "--error-format" => {
+                output.error_format = match &*get_value()? {
+                    "pretty" => ErrorFormat::Pretty,
+                    "json" => ErrorFormat::Json,
+                    other => return Err(format!("unknown error format `{other}`, expected `pretty` or `json`")),
+                };
+            }
+            "--debug" => output.debug = true,
+            "-h" | "--help" => output.command = CliCommand::Help,
+            "--" => {
+                output.program_args = args.by_ref().collect();
+                break;
+            }
+            _ if name.starts_with('-') => return Err(format!("unknown option `{name}`")),
+            _ => {
+                if path.is_some() {return Err(format!("unexpected argument `{arg}`, only one path can be given"));}
+                path = Some(PathBuf::from(arg));
+            }
+        }
+    }
+
+    if let Some(path) = path {
+        output.path = path;
+    }
+    Ok(output)
+}
+
+
+
+
+
+pub fn run_cli (args: CliArgs) -> ExitCode {
+    if args.command == CliCommand::Help {
+        println!("{HELP_TEXT}");
+        return ExitCode::from(EXIT_SUCCESS);
+    }
+
+    if !args.path.is_dir() {
+        if !args.path.exists() {
+            eprintln!("error: could not find {:?}", args.path);
+            return ExitCode::from(EXIT_FATAL_ERROR);
+        }
+        if args.path.extension().and_then(|v| v.to_str()) != Some("tua") {
+            eprintln!("error: {:?} is not a directory or a `.tua` file", args.path);
+            return ExitCode::from(EXIT_USAGE_ERROR);
+        }
+    }
+
+    // backtraces are only captured when they'll be shown
+    if args.debug {
+        force_backtraces();
+    }
+
+    let mut logger = Logger::new("Tua compiler");
+    let exit_code = match args.command {
+        CliCommand::Check => run_check_command(&args, &mut logger),
+        CliCommand::Run => run_run_command(&args, &mut logger),
+        CliCommand::Tokens => run_tokens_command(&args, &mut logger),
+        CliCommand::Ast => run_ast_command(&args, &mut logger),
+        CliCommand::Test => run_test_command(&args, &mut logger),
+        CliCommand::Help => unreachable!(),
+    };
+    if args.log_level == LogLevel::Verbose {
+        logger.print_all();
+    }
+    ExitCode::from(exit_code)
+}
+
+
+
+pub fn run_check_command (args: &CliArgs, logger: &mut Logger) -> u8 {
+    let (_, errors) = match compiler::compile_from_path(&args.path, &EntryPoints::None, logger) {
+        Ok(v) => v,
+        Err(error) => return report_fatal_error(error, args),
+    };
+    let exit_code = report_errors(&errors, args);
+    if exit_code == EXIT_SUCCESS && args.log_level >= LogLevel::Normal {
+        println!("No errors found");
+    }
+    exit_code
+}
+
+
+
+pub fn run_run_command (args: &CliArgs, logger: &mut Logger) -> u8 {
+    let (program, errors) = match compiler::compile_from_path(&args.path, &EntryPoints::Function(args.entry.clone()), logger) {
+        Ok(v) => v,
+        Err(error) => return report_fatal_error(error, args),
+    };
+    let exit_code = report_errors(&errors, args);
+    if exit_code != EXIT_SUCCESS {return exit_code;}
+    let Some(program) = program else {return exit_code;};
+
+    // program args
+    let entry = &program.functions[program.entries[0]];
+    if args.program_args.len() != entry.arg_types.len() {
+        eprintln!("error: `{}` takes {} argument(s) but {} were given", entry.name, entry.arg_types.len(), args.program_args.len());
+        return EXIT_USAGE_ERROR;
+    }
+    let mut entry_args = vec!();
+    for (arg, arg_type) in args.program_args.iter().zip(entry.arg_types.iter()) {
+        match interpreter::parse_arg_value(arg, arg_type) {
+            Ok(value) => entry_args.push(value),
+            Err(message) => {
+                eprintln!("error: {message}");
+                return EXIT_USAGE_ERROR;
+            }
+        }
+    }
+
+    match interpreter::run_function(&program, program.entries[0], entry_args) {
+        Ok(Value::None) => EXIT_SUCCESS,
+        Ok(return_value) => {
+            println!("{}", interpreter::format_value(&program, &return_value));
+            EXIT_SUCCESS
+        }
+        Err(error) => {
+            eprintln!("runtime error: {error}");
+            EXIT_RUNTIME_ERROR
+        }
+    }
+}
+
+
+
+pub fn run_test_command (args: &CliArgs, logger: &mut Logger) -> u8 {
+    let (program, errors) = match compiler::compile_from_path(&args.path, &EntryPoints::Tests, logger) {
+        Ok(v) => v,
+        Err(error) => return report_fatal_error(error, args),
+    };
+    let exit_code = report_errors(&errors, args);
+    if exit_code != EXIT_SUCCESS {return exit_code;}
+    let Some(program) = program else {return exit_code;};
+
+    let mut failed_count = 0;
+    for entry in &program.entries {
+        let name = &program.functions[*entry].name;
+        match interpreter::run_function(&program, *entry, vec!()) {
+            Ok(_) => if args.log_level >= LogLevel::Normal {println!("test {name} ... ok");},
+            Err(error) => {
+                eprintln!("test {name} ... FAILED: {}", error.raw_error);
+                failed_count += 1;
+            }
+        }
+    }
+    if args.log_level >= LogLevel::Normal {
+        println!("{} passed, {failed_count} failed", program.entries.len() - failed_count);
+    }
+
+    if failed_count > 0 {
+        EXIT_RUNTIME_ERROR
+    } else {
+        EXIT_SUCCESS
+    }
+}
+
+
+
+pub fn run_tokens_command (args: &CliArgs, logger: &mut Logger) -> u8 {
+    let mut errors = vec!();
+    let raw_tua_files = match compiler::load_files(args.path.clone(), logger) {
+        Ok(v) => v,
+        Err(error) => return report_fatal_error(error, args),
+    };
+    let preprocessed_tua_files = compiler::preprocess_files(raw_tua_files, &mut errors, logger);
+    let mut lexed_files = compiler::lex_files(preprocessed_tua_files, &mut errors, logger);
+    modules::load_imported_files(&mut lexed_files, &mut errors, logger);
+
+    for (file, path) in lexed_files.iter().filter(|(_, path)| is_project_file(path)) {
+        println!("{}:", path.display());
+        for token in &file.contents {
+            match args.output_format {
+                OutputFormat::Pretty => println!("{token:#?}"),
+                OutputFormat::Compact => println!("    {}:{}  {:?}", token.line_num + 1, token.char_num + 1, token.token),
+            }
+        }
+    }
+
+    report_errors(&errors, args)
+}
+
+
+
+pub fn run_ast_command (args: &CliArgs, logger: &mut Logger) -> u8 {
+    let mut errors = vec!();
+    let raw_tua_files = match compiler::load_files(args.path.clone(), logger) {
+        Ok(v) => v,
+        Err(error) => return report_fatal_error(error, args),
+    };
+    let (raw_macro_files, raw_tua_files): (Vec<_>, Vec<_>) = raw_tua_files.into_iter().partition(|(_, path)| init::is_macro_file(path));
+    let preprocessed_tua_files = compiler::preprocess_files(raw_tua_files, &mut errors, logger);
+    let preprocessed_macro_files = compiler::preprocess_files(raw_macro_files, &mut errors, logger);
+    let mut lexed_files = compiler::lex_files(preprocessed_tua_files, &mut errors, logger);
+    modules::load_imported_files(&mut lexed_files, &mut errors, logger);
+    let lexed_macro_files = compiler::lex_files(preprocessed_macro_files, &mut errors, logger);
+    let parsed_files = compiler::parse_files(&lexed_files, &mut errors, logger);
+    let macro_files = compiler::parse_macro_files(&lexed_macro_files, &mut errors, logger);
+
+    for (file, path) in parsed_files.iter().filter(|(_, path)| is_project_file(path)) {
+        println!("{}:", path.display());
+        for definition in &file.definitions {
+            match args.output_format {
+                OutputFormat::Pretty => println!("{definition:#?}"),
+                OutputFormat::Compact => println!("    {definition:?}"),
+            }
+        }
+    }
+    for (file, path) in macro_files.iter().filter(|(_, path)| is_project_file(path)) {
+        println!("{}:", path.display());
+        for ast_macro in &file.macros {
+            match args.output_format {
+                OutputFormat::Pretty => println!("{ast_macro:#?}"),
+                OutputFormat::Compact => println!("    {ast_macro:?}"),
+            }
+        }
+    }
+
+    report_errors(&errors, args)
+}
+
+// the std files bundled with the interpreter are added to every project, so `tokens` and `ast` leave them out
+fn is_project_file (path: &Path) -> bool {
+    init::get_bundled_std_file(path).is_none()
+}
+
+
+
+
+
+pub fn report_errors (errors: &[CompileError], args: &CliArgs) -> u8 {
+    let mut source_cache = diagnostics::SourceCache::new();
+    for error in errors {
+        match args.error_format {
+            ErrorFormat::Pretty => eprintln!("{}", diagnostics::render_error(error, "error", &mut source_cache, args.debug)),
+            ErrorFormat::Json => eprintln!("{}", diagnostics::render_error_json(error, "error")),
+        }
+    }
+    if errors.is_empty() {
+        return EXIT_SUCCESS;
+    }
+    if args.log_level >= LogLevel::Normal && args.error_format == ErrorFormat::Pretty {
+        eprintln!("{} error{} found", errors.len(), if errors.len() == 1 {""} else {"s"});
+    }
+    EXIT_COMPILE_ERRORS
+}
+
+
+
+pub fn report_fatal_error (error: CompileError, args: &CliArgs) -> u8 {
+    match args.error_format {
+        ErrorFormat::Pretty => eprintln!("{}", diagnostics::render_error(&error, "fatal error", &mut diagnostics::SourceCache::new(), args.debug)),
+        ErrorFormat::Json => eprintln!("{}", diagnostics::render_error_json(&error, "fatal")),
+    }
+    EXIT_FATAL_ERROR
+}
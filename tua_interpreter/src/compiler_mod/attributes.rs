@@ -0,0 +1,117 @@
+use crate::prelude::*;
+
+
+
+pub struct AttributeData {
+	pub name: &'static str,
+	pub arg_count: AttributeArgCount,
+	pub allowed_targets: &'static [&'static str],
+}
+
+pub enum AttributeArgCount {
+	Exactly (usize),
+	AtLeast (usize),
+}
+
+pub const BUILTIN_ATTRIBUTES: &[AttributeData] = &[
+	AttributeData {name: "makeDefaultConstructor", arg_count: AttributeArgCount::Exactly(0), allowed_targets: &["object"]},
+	AttributeData {name: "notInConstructor",       arg_count: AttributeArgCount::Exactly(0), allowed_targets: &["feild"]},
+	AttributeData {name: "must_equal",             arg_count: AttributeArgCount::AtLeast(1), allowed_targets: &["feild"]},
+	AttributeData {name: "inline",                 arg_count: AttributeArgCount::Exactly(0), allowed_targets: &["function"]},
+];
+
+
+
+pub fn get_attribute<'a, 'b> (attributes: &'b [ASTAttribute<'a>], name: &str) -> Option<&'b ASTAttribute<'a>> {
+	attributes.iter().find(|attribute| attribute.name == name)
+}
+
+pub fn has_attribute (attributes: &[ASTAttribute], name: &str) -> bool {
+	get_attribute(attributes, name).is_some()
+}
+
+
+
+
+
+// attributes are either built in (listed above) or macros from `.tuam` files
+pub fn check_attributes (parsed_files: &[(ParsedTuaFile, &PathBuf)], macro_files: &[(ParsedMacroFile, &PathBuf)], errors: &mut Vec<CompileError>, logger: &mut Logger) {
+	logger.logln("");
+	logger.logln("Checking attributes ...");
+	for (file, path) in parsed_files {
+		let errors_start = errors.len();
+		for definition in &file.definitions {
+			check_attribute_list(definition.attributes(), definition.type_name(), macro_files, &file.locations, errors);
+			if let ASTDefinition::Object {feilds, ..} = definition {
+				for feild in feilds {
+					check_attribute_list(&feild.attributes, "feild", macro_files, &file.locations, errors);
+				}
+			}
+		}
+		for error in &mut errors[errors_start..] {
+			error.set_path(path);
+		}
+	}
+	logger.logln("done");
+}
+
+
+
+pub fn check_attribute_list (attributes: &[ASTAttribute], target: &str, macro_files: &[(ParsedMacroFile, &PathBuf)], locations: &TokenLocations, errors: &mut Vec<CompileError>) {
+	for attribute in attributes {
+		let location = locations.get_token(attribute.name);
+
+		// macros
+		if let Some(ast_macro) = macros::get_macro(macro_files, attribute.name) {
+			if !macros::is_allowed_target(ast_macro, target) {
+				errors.push(RawCompileError::InvalidAttributeTarget {
+					location: location.clone(),
+					name: attribute.name.to_string(),
+					target: target.to_string(),
+				}.into());
+			}
+			if attribute.args.len() != ast_macro.args.len() {
+				errors.push(RawCompileError::InvalidAttributeArgCount {
+					location,
+					name: attribute.name.to_string(),
+					expected: ast_macro.args.len().to_string(),
+					found: attribute.args.len(),
+				}.into());
+			}
+			continue;
+		}
+
+		// name
+		let Some(attribute_data) = BUILTIN_ATTRIBUTES.iter().find(|attribute_data| attribute_data.name == attribute.name) else {
+			errors.push(RawCompileError::UnknownAttribute {
+				location,
+				name: attribute.name.to_string(),
+			}.into());
+			continue;
+		};
+
+		// target
+		if !attribute_data.allowed_targets.contains(&target) {
+			errors.push(RawCompileError::InvalidAttributeTarget {
+				location: location.clone(),
+				name: attribute.name.to_string(),
+				target: target.to_string(),
+			}.into());
+		}
+
+		// args
+		let (arg_count_is_valid, expected) = match attribute_data.arg_count {
+			AttributeArgCount::Exactly(count) => (attribute.args.len() == count, count.to_string()),
+			AttributeArgCount::AtLeast(count) => (attribute.args.len() >= count, format!("at least {count}")),
+		};
+		if !arg_count_is_valid {
+			errors.push(RawCompileError::InvalidAttributeArgCount {
+				location,
+				name: attribute.name.to_string(),
+				expected,
+				found: attribute.args.len(),
+			}.into());
+		}
+
+	}
+}
@@ -0,0 +1,2775 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+use super::patterns::SimplePattern;
+use super::resolver::SymbolTable;
+
+
+
+// built-in types that work like choices, their type ids come before every object and choice
+pub const OPTIONAL_TYPE_ID: usize = 0;
+pub const MAYBE_ERROR_TYPE_ID: usize = 1;
+pub const CONTROL_FLOW_TYPE_ID: usize = 2;
+pub const BUILT_IN_TYPE_COUNT: usize = 3;
+
+pub const NOTHING_VARIANT: usize = 0;
+pub const FILLED_VARIANT: usize = 1;
+pub const VALUE_VARIANT: usize = 0;
+pub const ERROR_VARIANT: usize = 1;
+pub const PASS_VARIANT: usize = 0;
+pub const RETURN_VARIANT: usize = 1;
+
+
+
+pub struct ProgramData<'a> {
+	pub parsed_files: &'a [(ParsedTuaFile<'a>, &'a PathBuf)],
+	pub module_graph: &'a ModuleGraph<'a>,
+	pub symbol_table: &'a SymbolTable<'a>,
+	pub functions: Vec<FunctionData<'a>>,
+	pub objects: Vec<ObjectData<'a>>,
+	pub choices: Vec<ChoiceData<'a>>,
+	pub type_aliases: Vec<(usize, &'a str, &'a ASTType<'a>)>,
+	pub consts: Vec<(usize, &'a str, &'a ASTFormula<'a>)>,
+}
+
+#[derive(Clone, Copy)]
+pub struct FunctionData<'a> {
+	pub file_index: usize,
+	pub name: &'a str,
+	pub associated_type: Option<&'a ASTType<'a>>,
+	pub has_self: bool,
+	pub args: &'a [ASTFunctionArg<'a>],
+	pub return_type: &'a ASTType<'a>,
+	pub statements: &'a ASTBlock<'a>,
+	pub properties: &'a DefinitionProperties<'a>,
+	// functions with syntax errors aren't lowered, since the statements that couldn't be parsed are missing from them
+	pub is_poisoned: bool,
+}
+
+impl<'a> FunctionData<'a> {
+	// generic types in the function's signature, each distinct set of types they're called with gets its own copy of the function
+	pub fn get_generic_names (&self) -> Vec<&'a str> {
+		let mut output = vec!();
+		for arg in self.args {
+			arg.ast_type.add_generic_names(&mut output);
+		}
+		self.return_type.add_generic_names(&mut output);
+		output
+	}
+}
+
+#[derive(Clone, Copy)]
+pub struct ObjectData<'a> {
+	pub file_index: usize,
+	pub name: &'a str,
+	pub feilds: &'a [ASTObjectFeild<'a>],
+	pub properties: &'a DefinitionProperties<'a>,
+}
+
+#[derive(Clone, Copy)]
+pub struct ChoiceData<'a> {
+	pub file_index: usize,
+	pub name: &'a str,
+	pub choices: &'a [ASTChoiceVariant<'a>],
+	pub properties: &'a DefinitionProperties<'a>,
+}
+
+// a resolved choice variant, tuple payloads use their indices as feild names
+pub struct VariantData {
+	pub full_name: String,
+	pub feilds: Vec<(String, TuaType)>,
+}
+
+
+
+impl<'a> ProgramData<'a> {
+
+	pub fn new (parsed_files: &'a [(ParsedTuaFile<'a>, &'a PathBuf)], module_graph: &'a ModuleGraph<'a>, symbol_table: &'a SymbolTable<'a>) -> Self {
+		let mut output = Self {
+			parsed_files,
+			module_graph,
+			symbol_table,
+			functions: vec!(),
+			objects: vec!(),
+			choices: vec!(),
+			type_aliases: vec!(),
+			consts: vec!(),
+		};
+		for (file_index, (file, _)) in parsed_files.iter().enumerate() {
+			for ((definition, properties), is_poisoned) in file.definitions.iter().zip(&file.properties).zip(&file.poisoned) {
+				match definition {
+					ASTDefinition::Function {name, associated_type, args, return_type, statements, ..} => {
+						let has_self = args.first().map(|arg| arg.name == "self").unwrap_or(false);
+						let associated_type = associated_type.as_ref().or_else(|| fns::some_if(has_self, || &args[0].ast_type));
+						output.functions.push(FunctionData {file_index, name, associated_type, has_self, args, return_type, statements, properties, is_poisoned: *is_poisoned});
+					}
+					ASTDefinition::Object {name, feilds, ..} => output.objects.push(ObjectData {file_index, name, feilds, properties}),
+					ASTDefinition::Choice {name, choices, ..} => output.choices.push(ChoiceData {file_index, name, choices, properties}),
+					ASTDefinition::Type {name, ast_type, ..} => output.type_aliases.push((file_index, name, ast_type)),
+					ASTDefinition::Const {name, value, ..} => output.consts.push((file_index, name, value)),
+					ASTDefinition::Static {..} | ASTDefinition::Use {..} | ASTDefinition::GlobalUse {..} => {}
+				}
+			}
+		}
+		output
+	}
+
+
+
+	pub fn resolve_type (&self, ast_type: &ASTType, file_index: usize, location: &Token) -> Result<TuaType, CompileError> {
+		self.resolve_generic_type(ast_type, file_index, &[], location)
+	}
+
+	// `bindings` are the types that generic types like `any_item` stand for
+	pub fn resolve_generic_type (&self, ast_type: &ASTType, file_index: usize, bindings: &[(&str, TuaType)], location: &Token) -> Result<TuaType, CompileError> {
+		if ASTType::is_generic_name(ast_type.name) {
+			let Some((_, bound_type)) = bindings.iter().find(|(name, _)| *name == ast_type.name) else {
+				return Err(RawCompileError::UnboundGenericType {location: location.clone(), name: ast_type.name.to_string()}.into());
+			};
+			return Ok(bound_type.clone());
+		}
+		Ok(match ast_type.name {
+			"int_8" => TuaType::Int8,
+			"int_16" => TuaType::Int16,
+			"int_32" => TuaType::Int32,
+			"int" | "int_64" => TuaType::Int64,
+			"uint_8" => TuaType::UInt8,
+			"uint_16" => TuaType::UInt16,
+			"uint_32" | "char" => TuaType::UInt32,
+			"uint" | "uint_64" => TuaType::UInt64,
+			"float" | "float_32" => TuaType::Float32,
+			"float_64" => TuaType::Float64,
+			"bool" => TuaType::Bool,
+			"String" => TuaType::String,
+			"none" => TuaType::None,
+			"Array" | "Optional" => {
+				let Some(inner_type) = &ast_type.unnamed_type_arg else {
+					return Err(RawCompileError::UnknownType {location: location.clone(), name: ast_type.name.to_string()}.into());
+				};
+				let inner_type = box self.resolve_generic_type(inner_type, file_index, bindings, location)?;
+				if ast_type.name == "Array" {TuaType::Array(inner_type)} else {TuaType::Optional(inner_type)}
+			}
+			"MaybeError" | "ControlFlow" => {
+				let (first_type, second_type) = match ast_type.name {
+					"MaybeError" => (ast_type.unnamed_type_arg.as_deref(), get_named_type_arg(ast_type, "error")),
+					_ => (get_named_type_arg(ast_type, "pass"), get_named_type_arg(ast_type, "return")),
+				};
+				let (Some(first_type), Some(second_type)) = (first_type, second_type) else {
+					return Err(RawCompileError::UnknownType {location: location.clone(), name: ast_type.name.to_string()}.into());
+				};
+				let first_type = box self.resolve_generic_type(first_type, file_index, bindings, location)?;
+				let second_type = box self.resolve_generic_type(second_type, file_index, bindings, location)?;
+				if ast_type.name == "MaybeError" {TuaType::MaybeError(first_type, second_type)} else {TuaType::ControlFlow(first_type, second_type)}
+			}
+			"Function" => {
+				let arg_types = get_function_arg_types(ast_type).into_iter()
+					.map(|arg_type| self.resolve_generic_type(arg_type, file_index, bindings, location))
+					.collect::<Result<Vec<_>, _>>()?;
+				let return_type = match get_named_type_arg(ast_type, "returns") {
+					Some(return_type) => self.resolve_generic_type(return_type, file_index, bindings, location)?,
+					None => TuaType::None,
+				};
+				TuaType::Function(arg_types, box return_type)
+			}
+			"Tuple" => TuaType::Tuple(
+				ast_type.tuple_items.iter()
+					.map(|item_type| self.resolve_generic_type(item_type, file_index, bindings, location))
+					.collect::<Result<Vec<_>, _>>()?
+			),
+			name => {
+				if self.objects.iter().any(|object| object.name == name) {
+					TuaType::Object(name.to_string())
+				} else if self.choices.iter().any(|choice| choice.name == name) {
+					TuaType::Choice(name.to_string())
+				} else if let Some((alias_file_index, _, alias_type)) = self.type_aliases.iter().find(|(_, alias_name, _)| *alias_name == name) {
+					// aliases are checked where they're defined, so their errors were already reported there
+					if self.uses_alias(alias_type, name, &mut vec!()) {
+						return Err(CompileError::from(RawCompileError::CircularTypeAlias {location: location.clone(), name: name.to_string()}).into_reported());
+					}
+					self.resolve_type(alias_type, *alias_file_index, location).map_err(CompileError::into_reported)?
+				} else {
+					return Err(RawCompileError::UnknownType {location: location.clone(), name: name.to_string()}.into());
+				}
+			}
+		})
+	}
+
+
+
+	// any type stands in for the generic types, since those are only checked once it's known what they are
+	pub fn check_type (&self, ast_type: &ASTType, file_index: usize, location: &Token) -> Result<(), CompileError> {
+		let mut generic_names = vec!();
+		ast_type.add_generic_names(&mut generic_names);
+		let bindings: Vec<(&str, TuaType)> = generic_names.into_iter().map(|name| (name, TuaType::None)).collect();
+		self.resolve_generic_type(ast_type, file_index, &bindings, location).map(|_| ())
+	}
+
+	// the types of object feilds and choice payloads are checked where they're defined, so their errors were already reported there
+	pub fn resolve_feild_type (&self, ast_type: &ASTType, file_index: usize, location: &Token) -> Result<TuaType, CompileError> {
+		self.resolve_type(ast_type, file_index, location).map_err(CompileError::into_reported)
+	}
+
+
+
+	// whether a type mentions an alias, including through other aliases, so `type A = B[]` and `type B = A` can be found before they're resolved forever
+	fn uses_alias (&self, ast_type: &ASTType, alias_name: &str, checked_aliases: &mut Vec<String>) -> bool {
+		if ast_type.name == alias_name {return true;}
+		if let Some((_, name, inner_alias)) = self.type_aliases.iter().find(|(_, name, _)| *name == ast_type.name) {
+			if !checked_aliases.iter().any(|checked| checked == name) {
+				checked_aliases.push(name.to_string());
+				if self.uses_alias(inner_alias, alias_name, checked_aliases) {return true;}
+			}
+		}
+		ast_type.unnamed_type_arg.iter().map(|inner_type| &**inner_type)
+			.chain(ast_type.named_type_args.iter().map(|(_, inner_type)| inner_type))
+			.chain(ast_type.tuple_items.iter())
+			.any(|inner_type| self.uses_alias(inner_type, alias_name, checked_aliases))
+	}
+
+
+
+	pub fn get_type_id (&self, tua_type: &TuaType) -> usize {
+		match tua_type {
+			TuaType::Optional(_) => OPTIONAL_TYPE_ID,
+			TuaType::MaybeError(..) => MAYBE_ERROR_TYPE_ID,
+			TuaType::ControlFlow(..) => CONTROL_FLOW_TYPE_ID,
+			TuaType::Object(name) => BUILT_IN_TYPE_COUNT + self.objects.iter().position(|object| object.name == name).unwrap(),
+			TuaType::Choice(name) => BUILT_IN_TYPE_COUNT + self.objects.len() + self.choices.iter().position(|choice| choice.name == name).unwrap(),
+			_ => unreachable!(),
+		}
+	}
+
+	pub fn get_runtime_types (&self) -> Vec<RuntimeTypeData> {
+		let mut output = vec!(
+			RuntimeTypeData {
+				name: String::from("Optional"),
+				feild_names: vec!(String::from("value")),
+				variant_names: vec!(String::from("Nothing"), String::from("Filled")),
+				variant_feild_names: vec!(vec!(), vec!(String::from("0"))),
+			},
+			RuntimeTypeData {
+				name: String::from("MaybeError"),
+				feild_names: vec!(),
+				variant_names: vec!(String::from("Value"), String::from("Error")),
+				variant_feild_names: vec!(vec!(String::from("0")), vec!(String::from("0"))),
+			},
+			RuntimeTypeData {
+				name: String::from("ControlFlow"),
+				feild_names: vec!(),
+				variant_names: vec!(String::from("Pass"), String::from("Return")),
+				variant_feild_names: vec!(vec!(String::from("0")), vec!(String::from("0"))),
+			},
+		);
+		for object in &self.objects {
+			output.push(RuntimeTypeData {
+				name: object.name.to_string(),
+				feild_names: object.feilds.iter().map(|feild| feild.name.to_string()).collect(),
+				variant_names: vec!(),
+				variant_feild_names: vec!(),
+			});
+		}
+		for choice in &self.choices {
+			output.push(RuntimeTypeData {
+				name: choice.name.to_string(),
+				feild_names: vec!(),
+				variant_names: choice.choices.iter().map(|variant| variant.name.to_string()).collect(),
+				variant_feild_names: choice.choices.iter().map(|variant| get_payload_feild_names(&variant.payload)).collect(),
+			});
+		}
+		output
+	}
+
+
+
+	// the variants of an `Optional` or a choice, or None for any other type
+	pub fn get_variants (&self, tua_type: &TuaType, location: &Token) -> Result<Option<Vec<VariantData>>, CompileError> {
+		Ok(Some(match tua_type {
+			TuaType::Optional(inner_type) => vec!(
+				VariantData {full_name: String::from("Nothing"), feilds: vec!()},
+				VariantData {full_name: String::from("Filled"), feilds: vec!((String::from("0"), *inner_type.clone()))},
+			),
+			TuaType::MaybeError(value_type, error_type) => vec!(
+				VariantData {full_name: String::from("Value"), feilds: vec!((String::from("0"), *value_type.clone()))},
+				VariantData {full_name: String::from("Error"), feilds: vec!((String::from("0"), *error_type.clone()))},
+			),
+			TuaType::ControlFlow(pass_type, return_type) => vec!(
+				VariantData {full_name: String::from("Pass"), feilds: vec!((String::from("0"), *pass_type.clone()))},
+				VariantData {full_name: String::from("Return"), feilds: vec!((String::from("0"), *return_type.clone()))},
+			),
+			TuaType::Choice(name) => {
+				let choice = self.choices.iter().find(|choice| choice.name == name).unwrap();
+				let mut variants = vec!();
+				for variant in choice.choices {
+					let types = match &variant.payload {
+						ASTChoicePayload::None => vec!(),
+						ASTChoicePayload::Tuple (types) => types.iter().collect(),
+						ASTChoicePayload::Feilds (feilds) => feilds.iter().map(|(_, ast_type)| ast_type).collect(),
+					};
+					let mut feilds = vec!();
+					for (feild_name, ast_type) in get_payload_feild_names(&variant.payload).into_iter().zip(types) {
+						feilds.push((feild_name, self.resolve_feild_type(ast_type, choice.file_index, location)?));
+					}
+					variants.push(VariantData {full_name: format!("{name}.{}", variant.name), feilds});
+				}
+				variants
+			}
+			_ => return Ok(None),
+		}))
+	}
+
+
+
+	// functions can be called from the file they're defined in, or from anywhere if they're `#global`
+	pub fn is_function_visible (&self, function: &FunctionData, file_index: usize) -> bool {
+		function.file_index == file_index || function.properties.get_bool("is_global")
+	}
+
+	pub fn get_module_file_index (&self, name: &str, file_index: usize) -> Option<usize> {
+		self.module_graph.imports.get(file_index)?.iter()
+			.find(|import| import.name == name)
+			.map(|import| import.file_index)
+	}
+
+}
+
+
+
+
+
+pub fn lower_program<'a> (parsed_files: &'a [(ParsedTuaFile<'a>, &'a PathBuf)], module_graph: &'a ModuleGraph<'a>, symbol_table: &'a SymbolTable<'a>, entry_points: &EntryPoints, errors: &mut Vec<CompileError>, logger: &mut Logger) -> Option<Program> {
+	logger.logln("");
+	logger.logln("Lowering to bytecode ...");
+	let mut lowerer = ProgramLowerer {
+		data: ProgramData::new(parsed_files, module_graph, symbol_table),
+		functions: vec!(),
+		function_ids: HashMap::new(),
+		queue: vec!(),
+		inferred_error_types: HashMap::new(),
+		inferring_functions: vec!(),
+		failed_error_types: vec!(),
+		lowering_defaults: vec!(),
+	};
+
+	// entry points
+	let entry_indexes = match entry_points {
+		EntryPoints::None => vec!(),
+		EntryPoints::Function (name) => {
+			let entry_index = lowerer.data.functions.iter().position(|function| function.name == name && function.associated_type.is_none());
+			let Some(entry_index) = entry_index else {
+				errors.push(RawCompileError::EntryFunctionNotFound {name: name.to_string()}.into());
+				return None;
+			};
+			vec!(entry_index)
+		}
+		EntryPoints::Tests => (0..lowerer.data.functions.len())
+			.filter(|i| lowerer.data.functions[*i].properties.get_bool("is_test"))
+			.collect(),
+	};
+	let entries = entry_indexes.into_iter().map(|i| lowerer.get_function_id(i, vec!())).collect();
+
+	// types are checked where they're written once, instead of everywhere the definitions with them are used
+	let mut has_errors = lowerer.check_definition_types(errors);
+
+	// every function reachable from the entry points
+	has_errors |= lowerer.lower_queue(errors);
+	let reachable_count = lowerer.functions.len();
+
+	// the rest of the project's own functions are still checked, so `check` finds the same errors that `run` would
+	// generic functions can only be checked once it's known what types they're used with
+	for function_index in 0..lowerer.data.functions.len() {
+		let function = lowerer.data.functions[function_index];
+		let is_lowered = lowerer.function_ids.keys().any(|(lowered_index, _)| *lowered_index == function_index);
+		if is_lowered || !function.get_generic_names().is_empty() || init::get_bundled_std_file(parsed_files[function.file_index].1).is_some() {continue;}
+		lowerer.get_function_id(function_index, vec!());
+		has_errors |= lowerer.lower_queue(errors);
+	}
+	has_errors |= lowerer.check_default_values(errors);
+	has_errors |= lowerer.lower_queue(errors);
+
+	logger.logln("done");
+	if has_errors {return None;}
+	lowerer.functions.truncate(reachable_count);
+	Some(Program {
+		functions: lowerer.functions.into_iter().map(Option::unwrap).collect(),
+		types: lowerer.data.get_runtime_types(),
+		entries,
+	})
+}
+
+
+
+
+
+struct ProgramLowerer<'a> {
+	data: ProgramData<'a>,
+	functions: Vec<Option<BytecodeFunction>>,
+	// (function index, generic type args) -> function id
+	function_ids: HashMap<(usize, Vec<TuaType>), usize>,
+	queue: Vec<(usize, Vec<TuaType>, usize)>,
+	// the error types of `MaybeError<T>` return types, which are inferred from the function's body
+	inferred_error_types: HashMap<(usize, Vec<TuaType>), TuaType>,
+	inferring_functions: Vec<(usize, Vec<TuaType>)>,
+	// the error types that couldn't be inferred, whose error is only reported by whatever found it first
+	failed_error_types: Vec<(usize, Vec<TuaType>)>,
+	// the objects whose default feild values are being lowered, since they're lowered inline wherever the object is created
+	lowering_defaults: Vec<&'a str>,
+}
+
+struct FunctionBuilder<'a> {
+	file_index: usize,
+	locations: &'a TokenLocations<'a>,
+	location: Token,
+	return_type: TuaType,
+	frame_sizes: [usize; StackType::COUNT],
+	instructions: Vec<Instruction>,
+	scopes: Vec<Vec<(&'a str, Slot, TuaType)>>,
+	loops: Vec<LoopData>,
+	// errors are collected per statement so one function can report several
+	errors: Vec<CompileError>,
+	// variables whose `var` statement failed, so using them later doesn't cause another error
+	poisoned_vars: Vec<&'a str>,
+	// the variables an anonymous function copied from where it was made, which can't be assigned to since the change would be lost
+	captured_slots: Vec<Slot>,
+	type_bindings: Vec<(&'a str, TuaType)>,
+	// the error types found so far, when the function's error type is being inferred instead of lowered
+	inferred_errors: Option<Vec<TuaType>>,
+	// anonymous functions without a return type take it from their first `return`
+	infers_return_type: bool,
+	// from `#get_permission(...)`, which lets the function create and change types that have `#set_permission(...)`
+	permissions: Vec<&'a str>,
+}
+
+// everything that lowering a call can add to, from before the call
+struct LoweringSnapshot {
+	instructions_len: usize,
+	frame_sizes: [usize; StackType::COUNT],
+	errors_len: usize,
+	functions_len: usize,
+	queue_len: usize,
+	failed_error_types_len: usize,
+}
+
+#[derive(Default)]
+struct LoopData {
+	break_jumps: Vec<usize>,
+	continue_jumps: Vec<usize>,
+}
+
+enum CallBase<'b, 'a> {
+	Formula (&'b ASTFormula<'a>),
+	Var (&'a str, &'b [VarQuery<'a>]),
+}
+
+type LoweredValue = (Option<Slot>, TuaType);
+
+
+
+impl<'a> FunctionBuilder<'a> {
+
+	fn new_slot (&mut self, stack: StackType) -> Slot {
+		let index = self.frame_sizes[stack as usize];
+		self.frame_sizes[stack as usize] += 1;
+		Slot {stack, index}
+	}
+
+	fn new_typed_slot (&mut self, tua_type: &TuaType, location: &Token) -> Result<Slot, CompileError> {
+		let Some(stack) = tua_type.get_stack_type() else {
+			return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: String::from("a value"), found: tua_type.to_string()}.into());
+		};
+		Ok(self.new_slot(stack))
+	}
+
+	fn push (&mut self, instruction: Instruction) {
+		self.instructions.push(instruction);
+	}
+
+	// pushes a jump with a placeholder target and returns its index so it can be patched later
+	fn push_jump (&mut self, instruction: Instruction) -> usize {
+		self.instructions.push(instruction);
+		self.instructions.len() - 1
+	}
+
+	fn patch_jump (&mut self, jump_index: usize, new_target: usize) {
+		match &mut self.instructions[jump_index] {
+			Instruction::Jump {target} | Instruction::JumpIfFalse {target, ..} | Instruction::JumpIfTrue {target, ..} => *target = new_target,
+			_ => unreachable!(),
+		}
+	}
+
+	fn get_var (&self, name: &str) -> Option<(Slot, TuaType)> {
+		self.scopes.iter().rev()
+			.flat_map(|scope| scope.iter().rev())
+			.find(|(var_name, _, _)| *var_name == name)
+			.map(|(_, slot, tua_type)| (*slot, tua_type.clone()))
+	}
+
+	fn add_var (&mut self, name: &'a str, tua_type: TuaType) -> Result<Slot, CompileError> {
+		let location = self.get_location(name);
+		let slot = self.new_typed_slot(&tua_type, &location)?;
+		self.scopes.last_mut().unwrap().push((name, slot, tua_type));
+		Ok(slot)
+	}
+
+	// the `Value` of a `MaybeError<none>`, which has nothing in it
+	fn new_empty_value (&mut self) -> Slot {
+		let output = self.new_slot(StackType::Object);
+		self.push(Instruction::NewObject {type_id: MAYBE_ERROR_TYPE_ID, variant: VALUE_VARIANT, feilds: vec!(), output});
+		output
+	}
+
+	// functions that return nothing can reach their end, but any other function has to return
+	fn finish_body (&mut self, statements: &[ASTStatement], name: &str) {
+		if self.return_type == TuaType::None {
+			self.push(Instruction::Return {value: None});
+		} else if matches!(&self.return_type, TuaType::MaybeError(value_type, _) if **value_type == TuaType::None) {
+			let value = self.new_empty_value();
+			self.push(Instruction::Return {value: Some(value)});
+		} else if !block_always_returns(statements) {
+			self.errors.push(RawCompileError::MissingReturn {location: self.location.clone(), function: name.to_string(), return_type: self.return_type.to_string()}.into());
+		}
+	}
+
+	fn get_location (&self, text: &str) -> Token {
+		self.locations.get(text).cloned().unwrap_or_else(|| self.location.clone())
+	}
+
+	fn get_formula_location (&self, formula: &ASTFormula) -> Token {
+		self.find_formula_location(formula).cloned().unwrap_or_else(|| self.location.clone())
+	}
+
+	fn find_formula_location (&self, formula: &ASTFormula) -> Option<&'a Token> {
+		match formula {
+			ASTFormula::Name (text) | ASTFormula::String (text) => self.locations.get(text),
+			ASTFormula::Int (_) | ASTFormula::UInt (_) | ASTFormula::Float (_) | ASTFormula::TypedNumber {..} | ASTFormula::Bool (_) | ASTFormula::Char (_) => None,
+			ASTFormula::Tuple (items) => items.iter().find_map(|item| self.find_formula_location(item)),
+			ASTFormula::Operation {left, right, ..} => self.find_formula_location(left).or_else(|| self.find_formula_location(right)),
+			ASTFormula::New {name, ..} => self.locations.get(name),
+			ASTFormula::ChoiceVariant {choice, variant, ..} => choice.and_then(|choice| self.locations.get(choice)).or_else(|| self.locations.get(variant)),
+			ASTFormula::Prefix {base, ..} | ASTFormula::As {base, ..} | ASTFormula::ReturnTest {base} => self.find_formula_location(base),
+			ASTFormula::IndexQuery {base, key} => self.find_formula_location(base).or_else(|| self.find_formula_location(key)),
+			ASTFormula::PropertyQuery {base, key} => self.find_formula_location(base).or_else(|| self.locations.get(key)),
+			ASTFormula::FunctionCall {base, args, ..} => self.find_formula_location(base).or_else(|| args.iter().find_map(|arg| self.find_formula_location(arg))),
+			ASTFormula::Function {args, ..} => args.iter().find_map(|(name, _)| self.locations.get(name)),
+		}
+	}
+
+	fn find_pattern_location (&self, pattern: &ASTPattern) -> Option<&'a Token> {
+		match pattern {
+			ASTPattern::Wildcard => None,
+			ASTPattern::Binding (name) => self.locations.get(name),
+			ASTPattern::Literal (literal) | ASTPattern::Range {start: literal, ..} => self.find_formula_location(literal),
+			ASTPattern::Variant {choice, variant, ..} => choice.and_then(|choice| self.locations.get(choice)).or_else(|| self.locations.get(variant)),
+			ASTPattern::Tuple (items) => items.iter().find_map(|item| self.find_pattern_location(item)),
+		}
+	}
+
+}
+
+
+
+
+
+impl<'a> ProgramLowerer<'a> {
+
+	// returns whether any errors were found
+	fn lower_queue (&mut self, errors: &mut Vec<CompileError>) -> bool {
+		let mut has_errors = false;
+		while let Some((function_index, type_args, function_id)) = self.queue.pop() {
+			match self.lower_function(function_index, &type_args) {
+				Ok(function) => self.functions[function_id] = Some(function),
+				Err(function_errors) => {
+					has_errors = true;
+					let path = self.data.parsed_files[self.data.functions[function_index].file_index].1;
+					for error in function_errors.into_iter().filter(|error| !error.is_reported()) {
+						errors.push(error.with_path(path));
+					}
+				}
+			}
+		}
+		has_errors
+	}
+
+	fn get_function_id (&mut self, function_index: usize, type_args: Vec<TuaType>) -> usize {
+		let key = (function_index, type_args);
+		if let Some(function_id) = self.function_ids.get(&key) {
+			return *function_id;
+		}
+		let function_id = self.functions.len();
+		self.functions.push(None);
+		self.queue.push((function_index, key.1.clone(), function_id));
+		self.function_ids.insert(key, function_id);
+		function_id
+	}
+
+	fn get_function_signature (&mut self, function_index: usize, bindings: &[(&'a str, TuaType)]) -> Result<(Vec<TuaType>, TuaType), CompileError> {
+		let function = self.data.functions[function_index];
+		let location = self.data.parsed_files[function.file_index].0.locations.get_token(function.name);
+		let arg_types = self.get_arg_types(function_index, bindings)?;
+		let return_type = self.get_return_type(function_index, bindings, &location)?;
+		Ok((arg_types, return_type))
+	}
+
+	// unknown types in a signature are reported once, pointing at the arg or the function's name like when it's lowered
+	fn check_signature (&self, function_index: usize) -> Result<(), CompileError> {
+		let function = self.data.functions[function_index];
+		let locations = &self.data.parsed_files[function.file_index].0.locations;
+		let location = locations.get_token(function.name);
+		for arg in function.args {
+			self.data.check_type(&arg.ast_type, function.file_index, &locations.get(arg.name).cloned().unwrap_or_else(|| location.clone()))?;
+		}
+		// the error type of a `MaybeError<T>` is inferred, and only checked once the function is used
+		let return_type = match (function.return_type.name, get_named_type_arg(function.return_type, "error")) {
+			("MaybeError", None) => match &function.return_type.unnamed_type_arg {
+				Some(value_type) => value_type,
+				None => return Err(RawCompileError::UnknownType {location, name: function.return_type.name.to_string()}.into()),
+			},
+			_ => function.return_type,
+		};
+		self.data.check_type(return_type, function.file_index, &location)
+	}
+
+	fn check_definition_types (&self, errors: &mut Vec<CompileError>) -> bool {
+		let mut results = vec!();
+		for (file_index, name, alias_type) in &self.data.type_aliases {
+			let location = self.data.parsed_files[*file_index].0.locations.get_token(name);
+			if self.data.uses_alias(alias_type, name, &mut vec!()) {
+				results.push((*file_index, Err(RawCompileError::CircularTypeAlias {location, name: name.to_string()}.into())));
+			} else {
+				results.push((*file_index, self.data.check_type(alias_type, *file_index, &location)));
+			}
+		}
+		for object in &self.data.objects {
+			let locations = &self.data.parsed_files[object.file_index].0.locations;
+			for feild in object.feilds {
+				results.push((object.file_index, self.data.check_type(&feild.ast_type, object.file_index, &locations.get_token(feild.name))));
+			}
+		}
+		for choice in &self.data.choices {
+			let locations = &self.data.parsed_files[choice.file_index].0.locations;
+			for variant in choice.choices {
+				match &variant.payload {
+					ASTChoicePayload::None => {}
+					ASTChoicePayload::Tuple (types) => for ast_type in types {
+						results.push((choice.file_index, self.data.check_type(ast_type, choice.file_index, &locations.get_token(variant.name))));
+					}
+					ASTChoicePayload::Feilds (feilds) => for (feild_name, ast_type) in feilds {
+						results.push((choice.file_index, self.data.check_type(ast_type, choice.file_index, &locations.get_token(feild_name))));
+					}
+				}
+			}
+		}
+		for function_index in 0..self.data.functions.len() {
+			results.push((self.data.functions[function_index].file_index, self.check_signature(function_index)));
+		}
+
+		// types that use a bad alias fail too, but only the alias's error is reported
+		let mut has_errors = false;
+		for (file_index, result) in results {
+			let Err(error) = result else {continue;};
+			has_errors = true;
+			if !error.is_reported() {
+				errors.push(error.with_path(self.data.parsed_files[file_index].1));
+			}
+		}
+		has_errors
+	}
+
+	// default feild values are lowered wherever the object is created, so they're checked once here in the file they're written in
+	fn check_default_values (&mut self, errors: &mut Vec<CompileError>) -> bool {
+		let mut has_errors = false;
+		for object in self.data.objects.clone() {
+			for feild in object.feilds {
+				let Some(default_value) = &feild.default_value else {continue;};
+				let mut builder = self.new_file_builder(object.file_index, feild.name, TuaType::None);
+				let Ok(feild_type) = self.data.resolve_type(&feild.ast_type, object.file_index, &builder.location) else {continue;};
+				self.lowering_defaults.push(object.name);
+				if let Err(error) = self.lower_value_as(default_value, &feild_type, &mut builder) {
+					builder.errors.push(error);
+				}
+				self.lowering_defaults.pop();
+				for error in builder.errors {
+					if matches!(error.raw_error(), RawCompileError::MissingPermission {..}) {continue;}
+					has_errors = true;
+					if !error.is_reported() {
+						errors.push(error.with_path(self.data.parsed_files[object.file_index].1));
+					}
+				}
+			}
+		}
+		has_errors
+	}
+
+	fn get_arg_types (&self, function_index: usize, bindings: &[(&str, TuaType)]) -> Result<Vec<TuaType>, CompileError> {
+		let function = self.data.functions[function_index];
+		let locations = &self.data.parsed_files[function.file_index].0.locations;
+		let location = locations.get_token(function.name);
+		function.args.iter()
+			.map(|arg| self.data.resolve_generic_type(&arg.ast_type, function.file_index, bindings, &locations.get(arg.name).cloned().unwrap_or_else(|| location.clone())))
+			.collect()
+	}
+
+	// `MaybeError<T>` return types without an error type get the errors that the function can return
+	fn get_return_type (&mut self, function_index: usize, bindings: &[(&'a str, TuaType)], location: &Token) -> Result<TuaType, CompileError> {
+		let function = self.data.functions[function_index];
+		let return_type = function.return_type;
+		if return_type.name != "MaybeError" || get_named_type_arg(return_type, "error").is_some() {
+			return self.data.resolve_generic_type(return_type, function.file_index, bindings, location);
+		}
+		let Some(value_type) = &return_type.unnamed_type_arg else {
+			return Err(RawCompileError::UnknownType {location: location.clone(), name: return_type.name.to_string()}.into());
+		};
+		let value_type = self.data.resolve_generic_type(value_type, function.file_index, bindings, location)?;
+		let error_type = self.infer_error_type(function_index, bindings, value_type.clone())?;
+		Ok(TuaType::MaybeError(box value_type, box error_type))
+	}
+
+	// the body is lowered without keeping anything except the types of the errors it returns, which becomes a union if there's more than one
+	// errors in the body are ignored here since they're reported when the function is actually lowered
+	fn infer_error_type (&mut self, function_index: usize, bindings: &[(&'a str, TuaType)], value_type: TuaType) -> Result<TuaType, CompileError> {
+		let function = self.data.functions[function_index];
+		let type_args = function.get_generic_names().into_iter()
+			.filter_map(|name| bindings.iter().find(|(bound_name, _)| *bound_name == name).map(|(_, bound_type)| bound_type.clone()))
+			.collect();
+		let key = (function_index, type_args);
+		if let Some(error_type) = self.inferred_error_types.get(&key) {
+			return Ok(error_type.clone());
+		}
+		// recursive calls can't return any errors that the rest of the function doesn't
+		if self.inferring_functions.contains(&key) {
+			return Ok(TuaType::Union(vec!()));
+		}
+
+		let arg_types = self.get_arg_types(function_index, bindings)?;
+		let mut builder = self.new_builder(function_index, bindings.to_vec(), TuaType::MaybeError(box value_type, box TuaType::Union(vec!())));
+		builder.inferred_errors = Some(vec!());
+		for (arg, arg_type) in function.args.iter().zip(arg_types) {
+			builder.add_var(arg.name, arg_type)?;
+		}
+		self.inferring_functions.push(key.clone());
+		self.lower_block(function.statements, &mut builder);
+		self.inferring_functions.pop();
+
+		let mut error_types = builder.inferred_errors.unwrap();
+		let error_type = match error_types.len() {
+			1 => error_types.remove(0),
+			_ => {
+				if let Some(invalid_type) = error_types.iter().find(|error_type| error_type.get_stack_type() != Some(StackType::Object)) {
+					let other_type = error_types.iter().find(|error_type| *error_type != invalid_type).unwrap();
+					let error = CompileError::from(RawCompileError::IncompatibleErrorTypes {
+						location: builder.location,
+						function: function.name.to_string(),
+						first: invalid_type.to_string(),
+						second: other_type.to_string(),
+					}).with_path(self.data.parsed_files[function.file_index].1);
+					// every call to the function and the function itself find this, but it's only reported once
+					if self.failed_error_types.contains(&key) {
+						return Err(error.into_reported());
+					}
+					self.failed_error_types.push(key);
+					return Err(error);
+				}
+				TuaType::Union(error_types)
+			}
+		};
+		self.inferred_error_types.insert(key, error_type.clone());
+		Ok(error_type)
+	}
+
+
+
+	fn new_builder (&self, function_index: usize, type_bindings: Vec<(&'a str, TuaType)>, return_type: TuaType) -> FunctionBuilder<'a> {
+		let function = self.data.functions[function_index];
+		let mut builder = self.new_file_builder(function.file_index, function.name, return_type);
+		builder.type_bindings = type_bindings;
+		builder.permissions = function.properties.get_names("unlock_keys");
+		builder
+	}
+
+	// code that isn't in a function, like default values, is lowered on its own to check it
+	fn new_file_builder (&self, file_index: usize, name: &str, return_type: TuaType) -> FunctionBuilder<'a> {
+		let locations = &self.data.parsed_files[file_index].0.locations;
+		FunctionBuilder {
+			file_index,
+			locations,
+			location: locations.get_token(name),
+			return_type,
+			frame_sizes: [0; StackType::COUNT],
+			instructions: vec!(),
+			scopes: vec!(vec!()),
+			loops: vec!(),
+			errors: vec!(),
+			poisoned_vars: vec!(),
+			captured_slots: vec!(),
+			type_bindings: vec!(),
+			inferred_errors: None,
+			infers_return_type: false,
+			permissions: vec!(),
+		}
+	}
+
+	fn lower_function (&mut self, function_index: usize, type_args: &[TuaType]) -> Result<BytecodeFunction, Vec<CompileError>> {
+		let function = self.data.functions[function_index];
+		// its syntax errors, or the unknown types in its signature, were already reported
+		if function.is_poisoned || self.check_signature(function_index).is_err() {
+			return Err(vec!());
+		}
+		let type_bindings: Vec<(&'a str, TuaType)> = function.get_generic_names().into_iter().zip(type_args.iter().cloned()).collect();
+		let (arg_types, return_type) = self.get_function_signature(function_index, &type_bindings).map_err(|error| vec!(error))?;
+		let mut builder = self.new_builder(function_index, type_bindings, return_type.clone());
+
+		// args
+		let mut arg_slots = vec!();
+		for (arg, arg_type) in function.args.iter().zip(arg_types.iter()) {
+			arg_slots.push(builder.add_var(arg.name, arg_type.clone()).map_err(|error| vec!(error))?);
+		}
+
+		// defaults are checked here once instead of at every call that uses them
+		if type_args.is_empty() {
+			for (arg, arg_type) in function.args.iter().zip(arg_types.iter()) {
+				let Some(default) = arg.default else {continue;};
+				let mut default_builder = self.new_file_builder(function.file_index, arg.name, TuaType::None);
+				if let Err(error) = self.lower_value_as(&get_default_formula(default), arg_type, &mut default_builder) {
+					builder.errors.push(error);
+				}
+			}
+		}
+
+		// body
+		let mut name = match function.associated_type {
+			Some(associated_type) => format!("{}.{}", associated_type.name, function.name),
+			None => function.name.to_string(),
+		};
+		if !type_args.is_empty() {
+			let type_args: Vec<String> = type_args.iter().map(TuaType::to_string).collect();
+			name += &format!("<{}>", type_args.join(", "));
+		}
+		self.lower_block(function.statements, &mut builder);
+		builder.finish_body(function.statements, &name);
+		if !builder.errors.is_empty() {
+			return Err(builder.errors);
+		}
+
+		Ok(BytecodeFunction {
+			name,
+			arg_types,
+			arg_slots,
+			return_type,
+			frame_sizes: builder.frame_sizes,
+			instructions: builder.instructions,
+		})
+	}
+
+
+
+
+
+	// errors are recorded in the builder and lowering moves on to the next statement, so every mismatch in a function is reported
+	fn lower_block (&mut self, block: &[ASTStatement<'a>], builder: &mut FunctionBuilder<'a>) {
+		builder.scopes.push(vec!());
+		let scope_count = builder.scopes.len();
+		let loop_count = builder.loops.len();
+		for statement in block {
+			// statements with names the resolver couldn't find are already reported, so they're skipped like ones that failed to lower
+			if self.data.symbol_table.is_statement_rejected(statement) {
+				if let ASTStatement::VarInit {var_names, ..} = statement {
+					builder.poisoned_vars.extend(var_names.iter().copied());
+				}
+				continue;
+			}
+			// values without a location of their own, like literals, point at the statement's first name instead of the function
+			let function_location = builder.location.clone();
+			match statement {
+				ASTStatement::VarInit {var_names, ..} | ASTStatement::For {var_names, ..} => builder.location = builder.get_location(var_names[0]),
+				ASTStatement::VarAssignment {start_name, ..} | ASTStatement::FunctionCall {start_name, ..} => builder.location = builder.get_location(start_name),
+				ASTStatement::Switch {switch_value, ..} => builder.location = builder.get_formula_location(switch_value),
+				_ => {}
+			}
+			let result = self.lower_statement(statement, builder);
+			builder.location = function_location;
+			let Err(error) = result else {continue;};
+			builder.scopes.truncate(scope_count);
+			builder.loops.truncate(loop_count);
+			if let ASTStatement::VarInit {var_names, ..} = statement {
+				builder.poisoned_vars.extend(var_names.iter().copied());
+			}
+			let uses_poisoned_var = match error.raw_error() {
+				RawCompileError::UndefinedName {name, ..} => builder.poisoned_vars.contains(&&**name),
+				_ => false,
+			};
+			if !uses_poisoned_var && !error.is_reported() {
+				builder.errors.push(error);
+			}
+		}
+		builder.scopes.pop();
+	}
+
+
+
+	fn lower_statement (&mut self, statement: &ASTStatement<'a>, builder: &mut FunctionBuilder<'a>) -> Result<(), CompileError> {
+		match statement {
+
+			ASTStatement::Print {value} => {
+				let (value, _) = self.lower_value(value, None, builder)?;
+				builder.push(Instruction::Print {value});
+			}
+
+			// `throw error` is the same as `return Error(error)`
+			ASTStatement::Throw {value} => {
+				let location = builder.get_formula_location(value);
+				let return_type = builder.return_type.clone();
+				let TuaType::MaybeError(_, error_type) = &return_type else {
+					return Err(RawCompileError::ThrowOutsideMaybeError {location, return_type: return_type.to_string()}.into());
+				};
+				let error = if builder.inferred_errors.is_some() {
+					let (error, found) = self.lower_value(value, None, builder)?;
+					self.check_error_type(&found, &location, builder)?;
+					error
+				} else {
+					self.lower_value_as(value, error_type, builder)?
+				};
+				let output = builder.new_slot(StackType::Object);
+				builder.push(Instruction::NewObject {type_id: MAYBE_ERROR_TYPE_ID, variant: ERROR_VARIANT, feilds: vec!(error), output});
+				builder.push(Instruction::Return {value: Some(output)});
+			}
+
+			ASTStatement::Crash {message} => {
+				let message = self.lower_string(message, builder)?;
+				builder.push(Instruction::Crash {message});
+			}
+
+			ASTStatement::Assert {condition} => {
+				let condition = self.lower_value_as(condition, &TuaType::Bool, builder)?;
+				builder.push(Instruction::Assert {condition});
+			}
+
+			ASTStatement::Todo {message} => {
+				let message = self.lower_string(message, builder)?;
+				builder.push(Instruction::Todo {message});
+			}
+
+			// with more than one name (`var a, b = pair`), the type is the type of the whole tuple
+			ASTStatement::VarInit {var_names, var_type, value: formula} => {
+				let (value, value_type) = match var_type {
+					Some(var_type) => {
+						let var_type = self.data.resolve_generic_type(var_type, builder.file_index, &builder.type_bindings, &builder.get_location(var_names[0]))?;
+						(self.lower_value_as(formula, &var_type, builder)?, var_type)
+					}
+					None => self.lower_value(formula, None, builder)?,
+				};
+				if var_names.len() == 1 {
+					let var_slot = builder.add_var(var_names[0], value_type)?;
+					builder.push(Instruction::Copy {from: value, to: var_slot});
+				} else {
+					self.lower_destructuring(var_names, value, value_type, &builder.get_formula_location(formula), builder)?;
+				}
+			}
+
+			ASTStatement::VarAssignment {start_name, var_queries, operator, value} => self.lower_var_assignment(start_name, var_queries, operator, value, builder)?,
+
+			ASTStatement::If {condition, true_block, false_block} => {
+				let condition = self.lower_value_as(condition, &TuaType::Bool, builder)?;
+				let false_jump = builder.push_jump(Instruction::JumpIfFalse {condition, target: 0});
+				self.lower_block(true_block, builder);
+				if false_block.is_empty() {
+					builder.patch_jump(false_jump, builder.instructions.len());
+				} else {
+					let end_jump = builder.push_jump(Instruction::Jump {target: 0});
+					builder.patch_jump(false_jump, builder.instructions.len());
+					self.lower_block(false_block, builder);
+					builder.patch_jump(end_jump, builder.instructions.len());
+				}
+			}
+
+			ASTStatement::Switch {switch_value, cases} => self.lower_switch(switch_value, cases, builder)?,
+
+			ASTStatement::For {var_names, iter, block} => self.lower_for_loop(var_names, iter, block, builder)?,
+
+			ASTStatement::While {condition, block} => {
+				let loop_start = builder.instructions.len();
+				let condition = self.lower_value_as(condition, &TuaType::Bool, builder)?;
+				let end_jump = builder.push_jump(Instruction::JumpIfFalse {condition, target: 0});
+				builder.loops.push(LoopData::default());
+				self.lower_block(block, builder);
+				builder.push(Instruction::Jump {target: loop_start});
+				builder.patch_jump(end_jump, builder.instructions.len());
+				self.finish_loop(loop_start, builder);
+			}
+
+			ASTStatement::Loop {block} => {
+				let loop_start = builder.instructions.len();
+				builder.loops.push(LoopData::default());
+				self.lower_block(block, builder);
+				builder.push(Instruction::Jump {target: loop_start});
+				self.finish_loop(loop_start, builder);
+			}
+
+			ASTStatement::Break | ASTStatement::Continue => {
+				let jump = builder.push_jump(Instruction::Jump {target: 0});
+				let is_break = matches!(statement, ASTStatement::Break);
+				let Some(loop_data) = builder.loops.last_mut() else {
+					return Err(RawCompileError::BreakOutsideLoop {location: builder.location.clone()}.into());
+				};
+				if is_break {
+					loop_data.break_jumps.push(jump);
+				} else {
+					loop_data.continue_jumps.push(jump);
+				}
+			}
+
+			ASTStatement::FunctionCall {start_name, var_queries, args} => {
+				self.lower_call(CallBase::Var(start_name, var_queries), args, None, builder)?;
+			}
+
+			ASTStatement::Return {value} if builder.infers_return_type => {
+				let value = match value {
+					Some(value) => {
+						let (value, value_type) = self.lower_value(value, None, builder)?;
+						builder.return_type = value_type;
+						Some(value)
+					}
+					None => {
+						builder.return_type = TuaType::None;
+						None
+					}
+				};
+				builder.infers_return_type = false;
+				builder.push(Instruction::Return {value});
+			}
+
+			ASTStatement::Return {value} => {
+				let return_type = builder.return_type.clone();
+				let value = match (value, &return_type) {
+					(None, TuaType::None) => None,
+					(Some(value), TuaType::None) => {
+						let (_, found) = self.lower_formula(value, None, builder)?;
+						return Err(RawCompileError::MismatchedTypes {location: builder.get_formula_location(value), expected: return_type.to_string(), found: found.to_string()}.into());
+					}
+					(None, TuaType::MaybeError(value_type, _)) if **value_type == TuaType::None => Some(builder.new_empty_value()),
+					(None, _) => return Err(RawCompileError::MismatchedTypes {location: builder.location.clone(), expected: return_type.to_string(), found: String::from("none")}.into()),
+					(Some(value), _) => Some(self.lower_return_value(value, builder)?),
+				};
+				builder.push(Instruction::Return {value});
+			}
+
+		}
+		Ok(())
+	}
+
+
+
+	fn finish_loop (&mut self, continue_target: usize, builder: &mut FunctionBuilder<'a>) {
+		let loop_data = builder.loops.pop().unwrap();
+		for jump in loop_data.break_jumps {
+			builder.patch_jump(jump, builder.instructions.len());
+		}
+		for jump in loop_data.continue_jumps {
+			builder.patch_jump(jump, continue_target);
+		}
+	}
+
+
+
+	fn lower_return_value (&mut self, value: &ASTFormula<'a>, builder: &mut FunctionBuilder<'a>) -> Result<Slot, CompileError> {
+		let return_type = builder.return_type.clone();
+		let (slot, found) = self.lower_value(value, Some(&return_type), builder)?;
+		self.convert_return_slot(slot, &found, &builder.get_formula_location(value), builder)
+	}
+
+	// in functions whose error type is being inferred, returning a `MaybeError` adds its errors instead of having to match them
+	fn convert_return_slot (&mut self, slot: Slot, found: &TuaType, location: &Token, builder: &mut FunctionBuilder<'a>) -> Result<Slot, CompileError> {
+		let return_type = builder.return_type.clone();
+		if builder.inferred_errors.is_none() {
+			return self.convert_slot(slot, found, &return_type, location, builder);
+		}
+		if let (TuaType::MaybeError(found_value_type, error_type), TuaType::MaybeError(value_type, _)) = (&found, &return_type) {
+			if found_value_type == value_type {
+				self.check_error_type(error_type, location, builder)?;
+				return Ok(slot);
+			}
+		}
+		self.convert_slot(slot, found, &return_type, location, builder)
+	}
+
+	// errors that a function returns have to fit its error type, or are added to it while it's being inferred
+	fn check_error_type (&self, error_type: &TuaType, location: &Token, builder: &mut FunctionBuilder<'a>) -> Result<(), CompileError> {
+		if let Some(inferred_errors) = &mut builder.inferred_errors {
+			let error_types = match error_type {
+				TuaType::Union(members) => members.clone(),
+				_ => vec!(error_type.clone()),
+			};
+			for error_type in error_types {
+				if !inferred_errors.contains(&error_type) {
+					inferred_errors.push(error_type);
+				}
+			}
+			return Ok(());
+		}
+		let TuaType::MaybeError(_, expected) = &builder.return_type else {unreachable!()};
+		if !is_error_subtype(error_type, expected) {
+			return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: expected.to_string(), found: error_type.to_string()}.into());
+		}
+		Ok(())
+	}
+
+
+
+	fn lower_for_loop (&mut self, var_names: &[&'a str], iter: &ASTFormula<'a>, block: &[ASTStatement<'a>], builder: &mut FunctionBuilder<'a>) -> Result<(), CompileError> {
+		let location = builder.get_formula_location(iter);
+		let (array, array_type) = self.lower_value(iter, None, builder)?;
+		if array_type.is_user_type() {
+			return self.lower_iterator_loop(var_names, array, array_type, block, &location, builder);
+		}
+		let TuaType::Array(item_type) = array_type else {
+			return Err(RawCompileError::UnfinishedFeature {location, details: format!("`for` loops can only go over arrays and types with a `to_iter` function for now, found `{array_type}`")}.into());
+		};
+
+		// counter
+		let counter = builder.new_slot(StackType::UInt64);
+		let one = builder.new_slot(StackType::UInt64);
+		let length = builder.new_slot(StackType::UInt64);
+		let is_in_bounds = builder.new_slot(StackType::Bool);
+		builder.push(Instruction::LoadConst {value: Value::UInt64(0), output: counter});
+		builder.push(Instruction::LoadConst {value: Value::UInt64(1), output: one});
+
+		// condition
+		let loop_start = builder.instructions.len();
+		builder.push(Instruction::CallNative {native: NativeFunction::ArrayLength, args: vec!(array), output: Some(length)});
+		builder.push(Instruction::Operation {operator: Operator::LessThan, left: counter, right: length, output: is_in_bounds});
+		let end_jump = builder.push_jump(Instruction::JumpIfFalse {condition: is_in_bounds, target: 0});
+
+		// body
+		builder.scopes.push(vec!());
+		self.lower_loop_item(var_names, *item_type, |item| Instruction::GetIndex {base: array, key: counter, output: item}, &location, builder)?;
+		builder.loops.push(LoopData::default());
+		self.lower_block(block, builder);
+		builder.scopes.pop();
+
+		// increment
+		let increment_start = builder.instructions.len();
+		builder.push(Instruction::Operation {operator: Operator::Plus, left: counter, right: one, output: counter});
+		builder.push(Instruction::Jump {target: loop_start});
+		builder.patch_jump(end_jump, builder.instructions.len());
+		self.finish_loop(increment_start, builder);
+		Ok(())
+	}
+
+	// objects and choices are looped over with `value.to_iter()`, then `next_item()` on what that returns until it gives `Nothing`
+	fn lower_iterator_loop (&mut self, var_names: &[&'a str], value: Slot, value_type: TuaType, block: &[ASTStatement<'a>], location: &Token, builder: &mut FunctionBuilder<'a>) -> Result<(), CompileError> {
+		let (iterator, iterator_type) = self.lower_iterator_call(value, &value_type, "to_iter", location, builder)?;
+
+		// condition
+		let loop_start = builder.instructions.len();
+		let (next_item, next_item_type) = self.lower_iterator_call(iterator, &iterator_type, "next_item", location, builder)?;
+		let TuaType::Optional(item_type) = next_item_type else {
+			return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: String::from("an `Optional` from `next_item`"), found: next_item_type.to_string()}.into());
+		};
+		let is_filled = builder.new_slot(StackType::Bool);
+		builder.push(Instruction::IsVariant {base: next_item, variant: FILLED_VARIANT, output: is_filled});
+		let end_jump = builder.push_jump(Instruction::JumpIfFalse {condition: is_filled, target: 0});
+
+		// body
+		builder.scopes.push(vec!());
+		self.lower_loop_item(var_names, *item_type, |item| Instruction::GetFeild {base: next_item, feild: 0, output: item}, location, builder)?;
+		builder.loops.push(LoopData::default());
+		self.lower_block(block, builder);
+		builder.scopes.pop();
+
+		builder.push(Instruction::Jump {target: loop_start});
+		builder.patch_jump(end_jump, builder.instructions.len());
+		self.finish_loop(loop_start, builder);
+		Ok(())
+	}
+
+	fn lower_iterator_call (&mut self, base: Slot, base_type: &TuaType, name: &str, location: &Token, builder: &mut FunctionBuilder<'a>) -> Result<(Slot, TuaType), CompileError> {
+		let candidates = if base_type.is_user_type() {self.get_method_candidates(&base_type.get_type_name(), name)} else {vec!()};
+		if candidates.is_empty() {
+			return Err(RawCompileError::MissingOperatorFunction {location: location.clone(), operator: String::from("for"), type_name: base_type.to_string(), function: name.to_string()}.into());
+		}
+		let (output, output_type) = self.lower_user_function_call(&candidates, Some((base, base_type.clone())), &[], location, builder)?;
+		let Some(output) = output else {
+			return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: String::from("a value"), found: output_type.to_string()}.into());
+		};
+		Ok((output, output_type))
+	}
+
+	// with more than one name (`for key, value in pairs do`), each item is taken apart like `var key, value = pair`
+	fn lower_loop_item (&mut self, var_names: &[&'a str], item_type: TuaType, get_item: impl FnOnce(Slot) -> Instruction, location: &Token, builder: &mut FunctionBuilder<'a>) -> Result<(), CompileError> {
+		let [var_name] = var_names else {
+			let item = builder.new_typed_slot(&item_type, location)?;
+			builder.push(get_item(item));
+			return self.lower_destructuring(var_names, item, item_type, location, builder);
+		};
+		let item = builder.add_var(var_name, item_type)?;
+		builder.push(get_item(item));
+		Ok(())
+	}
+
+	// `var a, b = pair` gives each item of the tuple its own variable
+	fn lower_destructuring (&mut self, var_names: &[&'a str], value: Slot, value_type: TuaType, location: &Token, builder: &mut FunctionBuilder<'a>) -> Result<(), CompileError> {
+		let item_types = match value_type {
+			TuaType::Tuple (item_types) if item_types.len() == var_names.len() => item_types,
+			_ => return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: format!("a tuple with {} items", var_names.len()), found: value_type.to_string()}.into()),
+		};
+		for (item, (var_name, item_type)) in var_names.iter().zip(item_types).enumerate() {
+			let var_slot = builder.add_var(var_name, item_type)?;
+			builder.push(Instruction::GetFeild {base: value, feild: item, output: var_slot});
+		}
+		Ok(())
+	}
+
+
+
+	fn lower_switch (&mut self, switch_value: &ASTFormula<'a>, cases: &[ASTSwitchCase<'a>], builder: &mut FunctionBuilder<'a>) -> Result<(), CompileError> {
+		let location = builder.get_formula_location(switch_value);
+		let (switch_slot, switch_type) = self.lower_value(switch_value, None, builder)?;
+		let mut rows = vec!();
+		let mut end_jumps = vec!();
+		for case in cases {
+			builder.scopes.push(vec!());
+
+			// each pattern jumps to the next one when it fails, and the last one jumps to the next case
+			let mut body_jumps = vec!();
+			let mut fail_jumps = vec!();
+			let mut case_bindings: Option<Vec<&'a str>> = None;
+			for (i, pattern) in case.patterns.iter().enumerate() {
+				for fail_jump in fail_jumps.drain(..) {
+					builder.patch_jump(fail_jump, builder.instructions.len());
+				}
+				let mut bindings = vec!();
+				let simple_pattern = self.lower_pattern(pattern, switch_slot, &switch_type, &mut fail_jumps, &mut bindings, builder)?;
+				rows.push(vec!(simple_pattern));
+				if i < case.patterns.len() - 1 {
+					body_jumps.push(builder.push_jump(Instruction::Jump {target: 0}));
+				}
+
+				// every pattern has to bind the same variables
+				bindings.sort();
+				if let Some(case_bindings) = &case_bindings {
+					if let Some(name) = case_bindings.iter().chain(bindings.iter()).find(|name| !case_bindings.contains(name) || !bindings.contains(name)) {
+						return Err(RawCompileError::InconsistentPatternBindings {location: builder.get_location(name), name: name.to_string()}.into());
+					}
+				}
+				case_bindings = Some(bindings);
+			}
+			for body_jump in body_jumps {
+				builder.patch_jump(body_jump, builder.instructions.len());
+			}
+
+			self.lower_block(&case.block, builder);
+			builder.scopes.pop();
+			end_jumps.push(builder.push_jump(Instruction::Jump {target: 0}));
+			for fail_jump in fail_jumps {
+				builder.patch_jump(fail_jump, builder.instructions.len());
+			}
+		}
+		for end_jump in end_jumps {
+			builder.patch_jump(end_jump, builder.instructions.len());
+		}
+
+		if let Some(missing) = patterns::find_missing_pattern(&rows, &[switch_type], &self.data, &location)? {
+			return Err(RawCompileError::NonExhaustiveSwitch {location, missing: missing[0].clone()}.into());
+		}
+		Ok(())
+	}
+
+
+
+	// pushes the code to test a pattern against a value, which jumps using `fail_jumps` if it doesn't match and otherwise sets the pattern's variables
+	fn lower_pattern (&mut self, pattern: &ASTPattern<'a>, value: Slot, value_type: &TuaType, fail_jumps: &mut Vec<usize>, bindings: &mut Vec<&'a str>, builder: &mut FunctionBuilder<'a>) -> Result<SimplePattern, CompileError> {
+		match pattern {
+
+			ASTPattern::Wildcard => Ok(SimplePattern::Any),
+
+			ASTPattern::Binding (name) => {
+				// later patterns in the same case reuse the variable
+				let var_slot = match builder.scopes.last().unwrap().iter().find(|(var_name, _, _)| var_name == name) {
+					Some((_, slot, var_type)) if var_type == value_type => *slot,
+					Some((_, _, var_type)) => return Err(RawCompileError::MismatchedTypes {location: builder.get_location(name), expected: var_type.to_string(), found: value_type.to_string()}.into()),
+					None => builder.add_var(name, value_type.clone())?,
+				};
+				builder.push(Instruction::Copy {from: value, to: var_slot});
+				bindings.push(name);
+				Ok(SimplePattern::Any)
+			}
+
+			ASTPattern::Literal (literal) => {
+				let literal_slot = self.lower_value_as(literal, value_type, builder)?;
+				let is_match = builder.new_slot(StackType::Bool);
+				builder.push(Instruction::Operation {operator: Operator::Equal, left: value, right: literal_slot, output: is_match});
+				fail_jumps.push(builder.push_jump(Instruction::JumpIfFalse {condition: is_match, target: 0}));
+				Ok(match literal {
+					ASTFormula::Bool (true) => SimplePattern::Constructor {index: 0, feilds: vec!()},
+					ASTFormula::Bool (false) => SimplePattern::Constructor {index: 1, feilds: vec!()},
+					_ => SimplePattern::Opaque,
+				})
+			}
+
+			ASTPattern::Range {start, end, is_inclusive} => {
+				if !value_type.is_number() {
+					return Err(RawCompileError::MismatchedTypes {location: builder.get_formula_location(start), expected: value_type.to_string(), found: String::from("a range")}.into());
+				}
+				let end_operator = if *is_inclusive {Operator::LessOrEqual} else {Operator::LessThan};
+				for (bound, operator) in [(start, Operator::GreaterOrEqual), (end, end_operator)] {
+					let bound_slot = self.lower_value_as(bound, value_type, builder)?;
+					let is_match = builder.new_slot(StackType::Bool);
+					builder.push(Instruction::Operation {operator, left: value, right: bound_slot, output: is_match});
+					fail_jumps.push(builder.push_jump(Instruction::JumpIfFalse {condition: is_match, target: 0}));
+				}
+				Ok(SimplePattern::Opaque)
+			}
+
+			ASTPattern::Variant {choice, variant, payload} => {
+				let location = builder.get_location(variant);
+				let Some(variants) = self.data.get_variants(value_type, &location)? else {
+					return Err(RawCompileError::MismatchedTypes {location, expected: value_type.to_string(), found: choice.unwrap_or(variant).to_string()}.into());
+				};
+				let type_name = value_type.get_type_name();
+				if let Some(choice) = choice {
+					if *choice != type_name {
+						return Err(RawCompileError::MismatchedTypes {location: builder.get_location(choice), expected: value_type.to_string(), found: choice.to_string()}.into());
+					}
+				}
+				let Some(variant_index) = variants.iter().position(|variant_data| variant_data.full_name.rsplit('.').next() == Some(*variant)) else {
+					return Err(RawCompileError::UnknownFeild {location, type_name: type_name.to_string(), feild: variant.to_string()}.into());
+				};
+				let variant_data = &variants[variant_index];
+
+				let is_match = builder.new_slot(StackType::Bool);
+				builder.push(Instruction::IsVariant {base: value, variant: variant_index, output: is_match});
+				fail_jumps.push(builder.push_jump(Instruction::JumpIfFalse {condition: is_match, target: 0}));
+
+				// which payload feild each sub-pattern is for
+				let feild_patterns: Vec<(usize, &ASTPattern<'a>)> = match payload {
+					ASTPatternPayload::None => vec!(),
+					ASTPatternPayload::Tuple (items) if items.len() == variant_data.feilds.len() => items.iter().enumerate().collect(),
+					ASTPatternPayload::Feilds (feilds) if variant_data.feilds.first().map(|(name, _)| name != "0").unwrap_or(false) => {
+						let mut output = vec!();
+						for (feild_name, feild_pattern) in feilds {
+							let Some(feild_index) = variant_data.feilds.iter().position(|(name, _)| name == feild_name) else {
+								return Err(RawCompileError::UnknownFeild {location: builder.get_location(feild_name), type_name: variant_data.full_name.clone(), feild: feild_name.to_string()}.into());
+							};
+							output.push((feild_index, feild_pattern));
+						}
+						output
+					}
+					_ => {
+						let feild_names: Vec<&str> = variant_data.feilds.iter().map(|(name, _)| &**name).collect();
+						return Err(RawCompileError::InvalidVariantPayload {location, variant: variant_data.full_name.clone(), expected: format!("{} feild(s): {}", feild_names.len(), feild_names.join(", "))}.into());
+					}
+				};
+
+				let mut feilds = vec!(SimplePattern::Any; variant_data.feilds.len());
+				for (feild_index, feild_pattern) in feild_patterns {
+					let feild_type = &variant_data.feilds[feild_index].1;
+					let feild_slot = builder.new_typed_slot(feild_type, &location)?;
+					builder.push(Instruction::GetFeild {base: value, feild: feild_index, output: feild_slot});
+					feilds[feild_index] = self.lower_pattern(feild_pattern, feild_slot, feild_type, fail_jumps, bindings, builder)?;
+				}
+				Ok(SimplePattern::Constructor {index: variant_index, feilds})
+			}
+
+			ASTPattern::Tuple (items) => {
+				let location = builder.find_pattern_location(pattern).cloned().unwrap_or_else(|| builder.location.clone());
+				let item_types = match value_type {
+					TuaType::Tuple (item_types) if item_types.len() == items.len() => item_types,
+					_ => return Err(RawCompileError::MismatchedTypes {location, expected: format!("a tuple with {} items", items.len()), found: value_type.to_string()}.into()),
+				};
+				let mut feilds = vec!();
+				for (i, (item, item_type)) in items.iter().zip(item_types).enumerate() {
+					let item_slot = builder.new_typed_slot(item_type, &location)?;
+					builder.push(Instruction::GetFeild {base: value, feild: i, output: item_slot});
+					feilds.push(self.lower_pattern(item, item_slot, item_type, fail_jumps, bindings, builder)?);
+				}
+				Ok(SimplePattern::Constructor {index: 0, feilds})
+			}
+
+		}
+	}
+
+
+
+	fn lower_var_assignment (&mut self, start_name: &'a str, var_queries: &[VarQuery<'a>], operator: &AssignmentOperator, value: &ASTFormula<'a>, builder: &mut FunctionBuilder<'a>) -> Result<(), CompileError> {
+		let location = builder.get_location(start_name);
+		let operator = match operator {
+			AssignmentOperator::Equals => None,
+			AssignmentOperator::Plus | AssignmentOperator::PlusPlus => Some(Operator::Plus),
+			AssignmentOperator::Minus | AssignmentOperator::MinusMinus => Some(Operator::Minus),
+			AssignmentOperator::Times => Some(Operator::Times),
+			AssignmentOperator::Divide => Some(Operator::Divide),
+			AssignmentOperator::Modulo => Some(Operator::Modulo),
+			AssignmentOperator::Concat => Some(Operator::Concat),
+			AssignmentOperator::ShiftLeft => Some(Operator::ShiftLeft),
+			AssignmentOperator::ShiftRight => Some(Operator::ShiftRight),
+			AssignmentOperator::Call => return Err(RawCompileError::UnfinishedFeature {location: location.clone(), details: String::from("`.=` assignments cannot be compiled yet")}.into()),
+		};
+
+		// plain variable
+		let Some((last_query, base_queries)) = var_queries.split_last() else {
+			let Some((var_slot, var_type)) = builder.get_var(start_name) else {
+				return Err(RawCompileError::UndefinedName {location, name: start_name.to_string(), suggestion: None}.into());
+			};
+			if builder.captured_slots.contains(&var_slot) {
+				return Err(RawCompileError::AssignedCapture {location, name: start_name.to_string()}.into());
+			}
+			let new_value = self.lower_assigned_value(Some(var_slot), &var_type, operator, value, builder)?;
+			builder.push(Instruction::Copy {from: new_value, to: var_slot});
+			return Ok(());
+		};
+
+		// feild or index
+		let (base, base_type) = self.lower_var_queries(start_name, base_queries, builder)?;
+		match last_query {
+			VarQuery::Feild (feild_name) => {
+				if let TuaType::Tuple(_) = base_type {
+					return Err(RawCompileError::ChangedTupleItem {location: builder.get_location(feild_name), tuple_type: base_type.to_string()}.into());
+				}
+				let (feild, feild_type) = self.get_feild(&base_type, feild_name, builder)?;
+				self.check_permission(&base_type.get_type_name(), "change the feilds of", &builder.get_location(feild_name), builder)?;
+				let current_value = match operator {
+					Some(_) => {
+						let current_value = builder.new_typed_slot(&feild_type, &location)?;
+						builder.push(Instruction::GetFeild {base, feild, output: current_value});
+						Some(current_value)
+					}
+					None => None,
+				};
+				let value = self.lower_assigned_value(current_value, &feild_type, operator, value, builder)?;
+				builder.push(Instruction::SetFeild {base, feild, value});
+			}
+			VarQuery::Index (key) => {
+				let TuaType::Array(item_type) = base_type else {
+					return Err(RawCompileError::MismatchedTypes {location, expected: String::from("an array"), found: base_type.to_string()}.into());
+				};
+				let key = self.lower_index_key(key, builder)?;
+				let current_value = match operator {
+					Some(_) => {
+						let current_value = builder.new_typed_slot(&item_type, &location)?;
+						builder.push(Instruction::GetIndex {base, key, output: current_value});
+						Some(current_value)
+					}
+					None => None,
+				};
+				let value = self.lower_assigned_value(current_value, &item_type, operator, value, builder)?;
+				builder.push(Instruction::SetIndex {base, key, value});
+			}
+		}
+		Ok(())
+	}
+
+	// the value that's stored by an assignment, `current_value` is only used for operators like `+=`
+	fn lower_assigned_value (&mut self, current_value: Option<Slot>, target_type: &TuaType, operator: Option<Operator>, value: &ASTFormula<'a>, builder: &mut FunctionBuilder<'a>) -> Result<Slot, CompileError> {
+		let (Some(operator), Some(current_value)) = (operator, current_value) else {
+			return self.lower_value_as(value, target_type, builder);
+		};
+		let location = builder.get_formula_location(value);
+		if let Some((output, output_type)) = self.lower_operator_call(&operator, (current_value, target_type.clone()), value, &location, builder)? {
+			return match output.filter(|_| output_type == *target_type) {
+				Some(output) => Ok(output),
+				None => Err(RawCompileError::MismatchedTypes {location, expected: target_type.to_string(), found: output_type.to_string()}.into()),
+			};
+		}
+		if target_type.is_user_type() {
+			return Err(RawCompileError::MissingOperatorFunction {location, operator: operator.to_string(), type_name: target_type.to_string(), function: operator.get_function_name().unwrap_or("").to_string()}.into());
+		}
+		let value = match operator {
+			Operator::Concat => self.lower_string(value, builder)?,
+			_ => self.lower_value_as(value, target_type, builder)?,
+		};
+		let is_valid = match operator {
+			Operator::Concat => *target_type == TuaType::String,
+			Operator::ShiftLeft | Operator::ShiftRight => target_type.is_int(),
+			_ => target_type.is_number(),
+		};
+		if !is_valid {
+			return Err(RawCompileError::InvalidOperation {location, operator: operator.to_string(), left: target_type.to_string(), right: target_type.to_string()}.into());
+		}
+		let output = builder.new_typed_slot(target_type, &location)?;
+		builder.push(Instruction::Operation {operator, left: current_value, right: value, output});
+		Ok(output)
+	}
+
+
+
+	fn lower_var_queries (&mut self, start_name: &str, var_queries: &[VarQuery<'a>], builder: &mut FunctionBuilder<'a>) -> Result<(Slot, TuaType), CompileError> {
+		let Some((mut output, mut output_type)) = builder.get_var(start_name) else {
+			return Err(RawCompileError::UndefinedName {location: builder.get_location(start_name), name: start_name.to_string(), suggestion: None}.into());
+		};
+		for var_query in var_queries {
+			(output, output_type) = match var_query {
+				VarQuery::Feild (feild_name) => self.lower_get_feild(output, &output_type, feild_name, builder)?,
+				VarQuery::Index (key) => self.lower_get_index(output, &output_type, key, builder)?,
+			};
+		}
+		Ok((output, output_type))
+	}
+
+	fn get_feild (&self, base_type: &TuaType, feild_name: &str, builder: &FunctionBuilder<'a>) -> Result<(usize, TuaType), CompileError> {
+		let location = builder.get_location(feild_name);
+
+		// tuple items are named by their position (`pair.0`)
+		if let TuaType::Tuple(item_types) = base_type {
+			let Some(item) = feild_name.parse::<usize>().ok().filter(|item| *item < item_types.len()) else {
+				return Err(RawCompileError::UnknownFeild {location, type_name: base_type.to_string(), feild: feild_name.to_string()}.into());
+			};
+			return Ok((item, item_types[item].clone()));
+		}
+
+		let object = match base_type {
+			TuaType::Object (name) => self.data.objects.iter().find(|object| object.name == name).copied(),
+			_ => None,
+		};
+		let feild = object.and_then(|object| object.feilds.iter().position(|feild| feild.name == feild_name).map(|i| (object, i)));
+		let Some((object, feild)) = feild else {
+			return Err(RawCompileError::UnknownFeild {location, type_name: base_type.to_string(), feild: feild_name.to_string()}.into());
+		};
+		let feild_type = self.data.resolve_feild_type(&object.feilds[feild].ast_type, object.file_index, &location)?;
+		Ok((feild, feild_type))
+	}
+
+	fn lower_get_feild (&mut self, base: Slot, base_type: &TuaType, feild_name: &str, builder: &mut FunctionBuilder<'a>) -> Result<(Slot, TuaType), CompileError> {
+		let (feild, feild_type) = self.get_feild(base_type, feild_name, builder)?;
+		let output = builder.new_typed_slot(&feild_type, &builder.get_location(feild_name))?;
+		builder.push(Instruction::GetFeild {base, feild, output});
+		Ok((output, feild_type))
+	}
+
+	fn lower_get_index (&mut self, base: Slot, base_type: &TuaType, key: &ASTFormula<'a>, builder: &mut FunctionBuilder<'a>) -> Result<(Slot, TuaType), CompileError> {
+		let location = builder.get_formula_location(key);
+
+		// objects and choices can be indexed with `function Type.index (self, key)`
+		if base_type.is_user_type() {
+			let candidates = self.get_method_candidates(&base_type.get_type_name(), "index");
+			if candidates.is_empty() {
+				return Err(RawCompileError::MissingOperatorFunction {location, operator: String::from("[]"), type_name: base_type.to_string(), function: String::from("index")}.into());
+			}
+			let (output, output_type) = self.lower_user_function_call(&candidates, Some((base, base_type.clone())), std::slice::from_ref(key), &location, builder)?;
+			let Some(output) = output else {
+				return Err(RawCompileError::MismatchedTypes {location, expected: String::from("a value"), found: output_type.to_string()}.into());
+			};
+			return Ok((output, output_type));
+		}
+
+		let TuaType::Array(item_type) = base_type else {
+			return Err(RawCompileError::MismatchedTypes {location, expected: String::from("an array"), found: base_type.to_string()}.into());
+		};
+		let key = self.lower_index_key(key, builder)?;
+		let output = builder.new_typed_slot(item_type, &location)?;
+		builder.push(Instruction::GetIndex {base, key, output});
+		Ok((output, *item_type.clone()))
+	}
+
+	fn lower_index_key (&mut self, key: &ASTFormula<'a>, builder: &mut FunctionBuilder<'a>) -> Result<Slot, CompileError> {
+		let (key_slot, key_type) = self.lower_value(key, Some(&TuaType::UInt64), builder)?;
+		if !key_type.is_int() {
+			return Err(RawCompileError::MismatchedTypes {location: builder.get_formula_location(key), expected: String::from("uint"), found: key_type.to_string()}.into());
+		}
+		Ok(key_slot)
+	}
+
+
+
+
+
+	// lowers a formula that has to produce a value
+	fn lower_value (&mut self, formula: &ASTFormula<'a>, expected: Option<&TuaType>, builder: &mut FunctionBuilder<'a>) -> Result<(Slot, TuaType), CompileError> {
+		let (slot, tua_type) = self.lower_formula(formula, expected, builder)?;
+		let Some(slot) = slot else {
+			return Err(RawCompileError::MismatchedTypes {location: builder.get_formula_location(formula), expected: String::from("a value"), found: tua_type.to_string()}.into());
+		};
+		Ok((slot, tua_type))
+	}
+
+	// lowers a formula that has to produce a specific type
+	fn lower_value_as (&mut self, formula: &ASTFormula<'a>, expected: &TuaType, builder: &mut FunctionBuilder<'a>) -> Result<Slot, CompileError> {
+		let (slot, found) = self.lower_value(formula, Some(expected), builder)?;
+		self.convert_slot(slot, &found, expected, &builder.get_formula_location(formula), builder)
+	}
+
+	// values are implicitly wrapped when an `Optional` or `MaybeError` is expected, and errors can be used as any error type that includes them
+	fn convert_slot (&mut self, slot: Slot, found: &TuaType, expected: &TuaType, location: &Token, builder: &mut FunctionBuilder<'a>) -> Result<Slot, CompileError> {
+		if found == expected {return Ok(slot);}
+		let wrapped_variant = match expected {
+			TuaType::Optional(inner_type) if **inner_type == *found => Some(FILLED_VARIANT),
+			TuaType::MaybeError(value_type, _) if **value_type == *found => Some(VALUE_VARIANT),
+			_ => None,
+		};
+		if let Some(variant) = wrapped_variant {
+			let output = builder.new_slot(StackType::Object);
+			builder.push(Instruction::NewObject {type_id: self.data.get_type_id(expected), variant, feilds: vec!(slot), output});
+			return Ok(output);
+		}
+		let is_subtype = match (found, expected) {
+			(TuaType::MaybeError(found_value_type, found_error_type), TuaType::MaybeError(value_type, error_type)) => found_value_type == value_type && is_error_subtype(found_error_type, error_type),
+			_ => is_error_subtype(found, expected),
+		};
+		if is_subtype {return Ok(slot);}
+		Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: expected.to_string(), found: found.to_string()}.into())
+	}
+
+	// lowers any value and converts it to a string
+	fn lower_string (&mut self, formula: &ASTFormula<'a>, builder: &mut FunctionBuilder<'a>) -> Result<Slot, CompileError> {
+		let (slot, tua_type) = self.lower_value(formula, None, builder)?;
+		Ok(self.convert_to_string(slot, &tua_type, builder))
+	}
+
+	fn convert_to_string (&mut self, slot: Slot, tua_type: &TuaType, builder: &mut FunctionBuilder<'a>) -> Slot {
+		if *tua_type == TuaType::String {return slot;}
+		let output = builder.new_slot(StackType::String);
+		builder.push(Instruction::ToString {base: slot, output});
+		output
+	}
+
+	fn load_const (&mut self, value: Value, tua_type: TuaType, builder: &mut FunctionBuilder<'a>) -> LoweredValue {
+		let output = builder.new_slot(tua_type.get_stack_type().unwrap());
+		builder.push(Instruction::LoadConst {value, output});
+		(Some(output), tua_type)
+	}
+
+
+
+	fn lower_formula (&mut self, formula: &ASTFormula<'a>, expected: Option<&TuaType>, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+		let location = builder.get_formula_location(formula);
+		Ok(match formula {
+
+			ASTFormula::Name (name) => self.lower_name(name, expected, builder)?,
+
+			ASTFormula::Int (value) => self.lower_int_literal(*value as i128, TuaType::Int64, expected, &location, builder)?,
+			ASTFormula::UInt (value) => self.lower_int_literal(*value as i128, TuaType::UInt64, expected, &location, builder)?,
+			ASTFormula::TypedNumber {value, tua_type} => self.lower_formula(value, Some(tua_type), builder)?,
+			ASTFormula::Float (value) => {
+				match get_literal_type(expected, TuaType::Float32) {
+					TuaType::Float64 => self.load_const(Value::Float64(*value), TuaType::Float64, builder),
+					_ => self.load_const(Value::Float32(*value as f32), TuaType::Float32, builder),
+				}
+			}
+			ASTFormula::Bool (value) => self.load_const(Value::Bool(*value), TuaType::Bool, builder),
+			ASTFormula::String (value) => self.load_const(Value::new_string(*value), TuaType::String, builder),
+			ASTFormula::Char (value) => self.load_const(Value::UInt32(*value as u32), TuaType::UInt32, builder),
+
+			ASTFormula::Tuple (items) => self.lower_tuple(items, expected, builder)?,
+
+			ASTFormula::Operation {operator, left, right} => self.lower_operation(operator, left, right, expected, builder)?,
+
+			ASTFormula::New {name, feilds} => self.lower_new_object(name, feilds, builder)?,
+			ASTFormula::ChoiceVariant {choice, variant, payload} => self.lower_choice_variant(*choice, variant, payload, expected, builder)?,
+
+			ASTFormula::Prefix {operator: PrefixOperator::Negate, base} => self.lower_negate(base, expected, builder)?,
+
+			ASTFormula::Prefix {operator: PrefixOperator::Not, base} => {
+				let (base, base_type) = self.lower_value(base, expected, builder)?;
+				if base_type != TuaType::Bool && !base_type.is_int() {
+					return Err(RawCompileError::MismatchedTypes {location, expected: String::from("bool"), found: base_type.to_string()}.into());
+				}
+				let output = builder.new_typed_slot(&base_type, &location)?;
+				builder.push(Instruction::Not {base, output});
+				(Some(output), base_type)
+			}
+
+			ASTFormula::As {base, ast_type} => {
+				let target_type = self.data.resolve_generic_type(ast_type, builder.file_index, &builder.type_bindings, &location)?;
+				let (base, base_type) = self.lower_value(base, None, builder)?;
+				if base_type == target_type {
+					(Some(base), base_type)
+				} else if target_type == TuaType::String {
+					let output = builder.new_slot(StackType::String);
+					builder.push(Instruction::ToString {base, output});
+					(Some(output), target_type)
+				} else if (base_type.is_number() || base_type == TuaType::Bool) && target_type.is_number() {
+					let output = builder.new_typed_slot(&target_type, &location)?;
+					builder.push(Instruction::Cast {base, output});
+					(Some(output), target_type)
+				} else {
+					return Err(RawCompileError::InvalidCast {location, from: base_type.to_string(), to: target_type.to_string()}.into());
+				}
+			}
+
+			ASTFormula::IndexQuery {base, key} => {
+				let (base, base_type) = self.lower_value(base, None, builder)?;
+				let (output, output_type) = self.lower_get_index(base, &base_type, key, builder)?;
+				(Some(output), output_type)
+			}
+
+			ASTFormula::PropertyQuery {base, key} => self.lower_property_query(base, key, expected, builder)?,
+
+			ASTFormula::ReturnTest {base} => self.lower_return_test(base, builder)?,
+
+			ASTFormula::FunctionCall {base, args, ..} => self.lower_call(CallBase::Formula(base), args, expected, builder)?,
+
+			ASTFormula::Function {args, return_type, statements, captures} => {
+				let (expected_args, expected_return) = match expected {
+					Some(TuaType::Function(arg_types, return_type)) => (arg_types.iter().cloned().map(Some).collect(), Some(*return_type.clone())),
+					_ => (vec!(), None),
+				};
+				self.lower_anonymous_function(args, return_type, statements, captures, &expected_args, expected_return, builder)?
+			}
+
+		})
+	}
+
+
+
+	fn lower_name (&mut self, name: &str, expected: Option<&TuaType>, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+		let location = builder.get_location(name);
+
+		// variables
+		if let Some((slot, tua_type)) = builder.get_var(name) {
+			return Ok((Some(slot), tua_type));
+		}
+
+		// consts
+		let file_index = builder.file_index;
+		if let Some((_, _, value)) = self.data.consts.iter().find(|(const_file_index, const_name, _)| *const_file_index == file_index && *const_name == name) {
+			return self.lower_formula(value, expected, builder);
+		}
+
+		// unqualified choice variants without payloads, like `Nothing`
+		if name == "Nothing" || self.data.choices.iter().any(|choice| choice.choices.iter().any(|variant| variant.name == name)) {
+			return self.lower_choice_variant(None, name, &ASTVariantPayload::None, expected, builder);
+		}
+
+		Err(RawCompileError::UndefinedName {location, name: name.to_string(), suggestion: None}.into())
+	}
+
+
+
+	fn lower_int_literal (&mut self, value: i128, default_type: TuaType, expected: Option<&TuaType>, location: &Token, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+		let tua_type = get_literal_type(expected, default_type);
+		let output = match tua_type {
+			TuaType::Int8    => i8::try_from(value).ok().map(Value::Int8),
+			TuaType::Int16   => i16::try_from(value).ok().map(Value::Int16),
+			TuaType::Int32   => i32::try_from(value).ok().map(Value::Int32),
+			TuaType::Int64   => i64::try_from(value).ok().map(Value::Int64),
+			TuaType::UInt8   => u8::try_from(value).ok().map(Value::UInt8),
+			TuaType::UInt16  => u16::try_from(value).ok().map(Value::UInt16),
+			TuaType::UInt32  => u32::try_from(value).ok().map(Value::UInt32),
+			TuaType::UInt64  => u64::try_from(value).ok().map(Value::UInt64),
+			TuaType::Float32 => Some(Value::Float32(value as f32)),
+			TuaType::Float64 => Some(Value::Float64(value as f64)),
+			_ => unreachable!(),
+		};
+		let Some(output) = output else {
+			return Err(RawCompileError::LiteralOutOfRange {location: location.clone(), value: value.to_string(), tua_type: tua_type.to_string()}.into());
+		};
+		Ok(self.load_const(output, tua_type, builder))
+	}
+
+
+
+	fn lower_operation (&mut self, operator: &Operator, left: &ASTFormula<'a>, right: &ASTFormula<'a>, expected: Option<&TuaType>, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+		let location = builder.get_formula_location(left);
+		match operator {
+
+			Operator::And | Operator::Or => {
+				let output = builder.new_slot(StackType::Bool);
+				let left = self.lower_value_as(left, &TuaType::Bool, builder)?;
+				builder.push(Instruction::Copy {from: left, to: output});
+				let end_jump = match operator {
+					Operator::And => builder.push_jump(Instruction::JumpIfFalse {condition: output, target: 0}),
+					_ => builder.push_jump(Instruction::JumpIfTrue {condition: output, target: 0}),
+				};
+				let right = self.lower_value_as(right, &TuaType::Bool, builder)?;
+				builder.push(Instruction::Copy {from: right, to: output});
+				builder.patch_jump(end_jump, builder.instructions.len());
+				return Ok((Some(output), TuaType::Bool));
+			}
+
+			Operator::Concat => {
+				let (left_slot, left_type) = self.lower_value(left, None, builder)?;
+				if let Some(output) = self.lower_operator_call(operator, (left_slot, left_type.clone()), right, &location, builder)? {
+					return Ok(output);
+				}
+				if left_type.is_user_type() {
+					return Err(RawCompileError::MissingOperatorFunction {location, operator: operator.to_string(), type_name: left_type.to_string(), function: String::from("concat")}.into());
+				}
+				let left = self.convert_to_string(left_slot, &left_type, builder);
+				let right = self.lower_string(right, builder)?;
+				let output = builder.new_slot(StackType::String);
+				builder.push(Instruction::Operation {operator: Operator::Concat, left, right, output});
+				return Ok((Some(output), TuaType::String));
+			}
+
+			_ => {}
+		}
+
+		// literals take the type of the other side, so the non-literal side is lowered first
+		let operand_expected = match operator {
+			Operator::Plus | Operator::Minus | Operator::Times | Operator::Divide | Operator::Power | Operator::Modulo | Operator::ShiftLeft | Operator::ShiftRight => expected.filter(|tua_type| tua_type.is_number()),
+			_ => None,
+		};
+		let (left_slot, left_type, right_slot, right_type) = if is_literal(left) && !is_literal(right) {
+			let (right_slot, right_type) = self.lower_value(right, operand_expected, builder)?;
+			let (left_slot, left_type) = self.lower_value(left, Some(&right_type), builder)?;
+			(left_slot, left_type, right_slot, right_type)
+		} else {
+			let (left_slot, left_type) = self.lower_value(left, operand_expected, builder)?;
+			// objects and choices use associated functions, except for `==` and `!=` which fall back to comparing the values
+			if left_type.is_user_type() {
+				if let Some(output) = self.lower_operator_call(operator, (left_slot, left_type.clone()), right, &location, builder)? {
+					return Ok(output);
+				}
+				if !matches!(operator, Operator::Equal | Operator::NotEqual) {
+					return Err(RawCompileError::MissingOperatorFunction {
+						location,
+						operator: operator.to_string(),
+						type_name: left_type.to_string(),
+						function: operator.get_function_name().unwrap_or("").to_string(),
+					}.into());
+				}
+			}
+			let (right_slot, right_type) = self.lower_value(right, Some(&left_type), builder)?;
+			(left_slot, left_type, right_slot, right_type)
+		};
+
+		// types
+		let output_type = match operator {
+			Operator::Plus | Operator::Minus | Operator::Times | Operator::Divide | Operator::Power | Operator::Modulo if left_type.is_number() => left_type.clone(),
+			Operator::ShiftLeft | Operator::ShiftRight if left_type.is_int() => left_type.clone(),
+			Operator::GreaterThan | Operator::LessThan | Operator::GreaterOrEqual | Operator::LessOrEqual if left_type.is_number() || left_type == TuaType::String => TuaType::Bool,
+			Operator::Equal | Operator::NotEqual => TuaType::Bool,
+			Operator::Xor if left_type == TuaType::Bool => TuaType::Bool,
+			_ => return Err(RawCompileError::InvalidOperation {location, operator: operator.to_string(), left: left_type.to_string(), right: right_type.to_string()}.into()),
+		};
+		if left_type != right_type {
+			return Err(RawCompileError::MismatchedTypes {location: builder.get_formula_location(right), expected: left_type.to_string(), found: right_type.to_string()}.into());
+		}
+
+		let operator = match operator {
+			Operator::Xor => Operator::NotEqual,
+			_ => operator.clone(),
+		};
+		let output = builder.new_typed_slot(&output_type, &location)?;
+		builder.push(Instruction::Operation {operator, left: left_slot, right: right_slot, output});
+		Ok((Some(output), output_type))
+	}
+
+
+
+	// numbers have to be signed, and objects and choices use their `neg` function
+	fn lower_negate (&mut self, base: &ASTFormula<'a>, expected: Option<&TuaType>, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+		let location = builder.get_formula_location(base);
+		let (base, base_type) = self.lower_value(base, expected.filter(|tua_type| tua_type.is_number()), builder)?;
+		if base_type.is_user_type() {
+			let function_name = PrefixOperator::Negate.get_function_name().unwrap();
+			let candidates = self.get_method_candidates(&base_type.get_type_name(), function_name);
+			if candidates.is_empty() {
+				return Err(RawCompileError::MissingOperatorFunction {location, operator: String::from("-"), type_name: base_type.to_string(), function: function_name.to_string()}.into());
+			}
+			return self.lower_user_function_call(&candidates, Some((base, base_type)), &[], &location, builder);
+		}
+		if !base_type.is_signed() {
+			return Err(RawCompileError::InvalidPrefixOperation {location, operator: String::from("-"), found: base_type.to_string()}.into());
+		}
+		let output = builder.new_typed_slot(&base_type, &location)?;
+		builder.push(Instruction::Negate {base, output});
+		Ok((Some(output), base_type))
+	}
+
+
+
+	// calls the associated function for an operator, or returns None if the left side's type doesn't have one
+	fn lower_operator_call (&mut self, operator: &Operator, left: (Slot, TuaType), right: &ASTFormula<'a>, location: &Token, builder: &mut FunctionBuilder<'a>) -> Result<Option<LoweredValue>, CompileError> {
+		let Some(function_name) = operator.get_function_name() else {return Ok(None);};
+		if !left.1.is_user_type() {return Ok(None);}
+		let candidates = self.get_method_candidates(&left.1.get_type_name(), function_name);
+		if candidates.is_empty() {return Ok(None);}
+		let (output, output_type) = self.lower_user_function_call(&candidates, Some(left), std::slice::from_ref(right), location, builder)?;
+
+		// `equals` and `compare` give their result as a bool or int, which is turned into the operator's result
+		let required_type = match operator {
+			Operator::Equal | Operator::NotEqual => TuaType::Bool,
+			Operator::GreaterThan | Operator::LessThan | Operator::GreaterOrEqual | Operator::LessOrEqual => TuaType::Int64,
+			_ => return Ok(Some((output, output_type))),
+		};
+		let Some(output) = output.filter(|_| output_type == required_type) else {
+			return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: required_type.to_string(), found: output_type.to_string()}.into());
+		};
+		let result = builder.new_slot(StackType::Bool);
+		match operator {
+			Operator::Equal => return Ok(Some((Some(output), TuaType::Bool))),
+			Operator::NotEqual => builder.push(Instruction::Not {base: output, output: result}),
+			_ => {
+				let zero = builder.new_slot(StackType::Int64);
+				builder.push(Instruction::LoadConst {value: Value::Int64(0), output: zero});
+				builder.push(Instruction::Operation {operator: operator.clone(), left: output, right: zero, output: result});
+			}
+		}
+		Ok(Some((Some(result), TuaType::Bool)))
+	}
+
+
+
+	// `value?` gives what's inside the value, or returns early with the rest of it
+	// `Optional`s and `MaybeError`s return their `Nothing` or `Error` as is, and other types use their `test` function, which returns a `ControlFlow`
+	fn lower_return_test (&mut self, base: &ASTFormula<'a>, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+		let location = builder.get_formula_location(base);
+		let (base, base_type) = self.lower_value(base, None, builder)?;
+		let return_type = builder.return_type.clone();
+		let (tested, tested_type, pass_variant, pass_type) = match (&base_type, &return_type) {
+			(TuaType::Optional(inner_type), TuaType::Optional(_)) => (base, base_type.clone(), FILLED_VARIANT, *inner_type.clone()),
+			(TuaType::MaybeError(value_type, error_type), TuaType::MaybeError(..)) => {
+				self.check_error_type(error_type, &location, builder)?;
+				(base, base_type.clone(), VALUE_VARIANT, *value_type.clone())
+			}
+			(TuaType::Optional(_) | TuaType::MaybeError(..), _) => {
+				return Err(RawCompileError::InvalidReturnTest {location, found: base_type.to_string(), return_type: return_type.to_string()}.into());
+			}
+			_ => {
+				let candidates = if base_type.is_user_type() {self.get_method_candidates(&base_type.get_type_name(), "test")} else {vec!()};
+				if candidates.is_empty() {
+					return Err(RawCompileError::MissingOperatorFunction {location, operator: String::from("?"), type_name: base_type.to_string(), function: String::from("test")}.into());
+				}
+				let (output, output_type) = self.lower_user_function_call(&candidates, Some((base, base_type.clone())), &[], &location, builder)?;
+				let (Some(output), TuaType::ControlFlow(pass_type, _)) = (output, &output_type) else {
+					return Err(RawCompileError::MismatchedTypes {location, expected: String::from("a `ControlFlow`"), found: output_type.to_string()}.into());
+				};
+				let pass_type = *pass_type.clone();
+				(output, output_type, PASS_VARIANT, pass_type)
+			}
+		};
+
+		// return early unless it's the pass variant
+		let is_pass = builder.new_slot(StackType::Bool);
+		builder.push(Instruction::IsVariant {base: tested, variant: pass_variant, output: is_pass});
+		let pass_jump = builder.push_jump(Instruction::JumpIfTrue {condition: is_pass, target: 0});
+		let returned = match &tested_type {
+			TuaType::ControlFlow(_, returned_type) => {
+				let returned = builder.new_typed_slot(returned_type, &location)?;
+				builder.push(Instruction::GetFeild {base: tested, feild: 0, output: returned});
+				self.convert_return_slot(returned, returned_type, &location, builder).map_err(|_| RawCompileError::InvalidReturnTest {
+					location: location.clone(),
+					found: base_type.to_string(),
+					return_type: return_type.to_string(),
+				})?
+			}
+			_ => tested,
+		};
+		builder.push(Instruction::Return {value: Some(returned)});
+
+		// the passed value
+		builder.patch_jump(pass_jump, builder.instructions.len());
+		let Some(stack) = pass_type.get_stack_type() else {
+			return Ok((None, pass_type));
+		};
+		let output = builder.new_slot(stack);
+		builder.push(Instruction::GetFeild {base: tested, feild: 0, output});
+		Ok((Some(output), pass_type))
+	}
+
+
+
+	// objects and choices with `#set_permission(...)` (which sets `lock`) can only be created and changed by functions with the same `#get_permission(...)` (which adds to `unlock_keys`)
+	fn check_permission (&self, type_name: &str, action: &str, location: &Token, builder: &FunctionBuilder<'a>) -> Result<(), CompileError> {
+		let object_properties = self.data.objects.iter().find(|object| object.name == type_name).map(|object| object.properties);
+		let choice_properties = self.data.choices.iter().find(|choice| choice.name == type_name).map(|choice| choice.properties);
+		let Some(properties) = object_properties.or(choice_properties) else {return Ok(());};
+		for permission in properties.get_names("lock") {
+			if !builder.permissions.contains(&permission) {
+				return Err(RawCompileError::MissingPermission {
+					location: location.clone(),
+					permission: permission.to_string(),
+					action: action.to_string(),
+					type_name: type_name.to_string(),
+				}.into());
+			}
+		}
+		Ok(())
+	}
+
+
+
+	// the expected item types are only hints, so `(1, 2)` can be an `(int_8, int_8)`
+	fn lower_tuple (&mut self, items: &[ASTFormula<'a>], expected: Option<&TuaType>, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+		let expected_items = match expected {
+			Some(TuaType::Tuple(item_types)) if item_types.len() == items.len() => item_types.iter().map(Some).collect(),
+			_ => vec!(None; items.len()),
+		};
+		let mut item_slots = vec!();
+		let mut item_types = vec!();
+		for (item, expected_item) in items.iter().zip(expected_items) {
+			let (item_slot, item_type) = self.lower_value(item, expected_item, builder)?;
+			item_slots.push(item_slot);
+			item_types.push(item_type);
+		}
+		let output = builder.new_slot(StackType::Tuple);
+		builder.push(Instruction::NewTuple {items: item_slots, output});
+		Ok((Some(output), TuaType::Tuple(item_types)))
+	}
+
+	fn lower_new_object (&mut self, name: &str, feilds: &[(&'a str, ASTFormula<'a>)], builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+		let location = builder.get_location(name);
+		let Some(object) = self.data.objects.iter().find(|object| object.name == name).copied() else {
+			return Err(RawCompileError::UnknownType {location, name: name.to_string()}.into());
+		};
+		self.check_permission(name, "create", &location, builder)?;
+
+		// unknown feilds
+		for (feild_name, _) in feilds {
+			if !object.feilds.iter().any(|feild| feild.name == *feild_name) {
+				return Err(RawCompileError::UnknownFeild {location: builder.get_location(feild_name), type_name: name.to_string(), feild: feild_name.to_string()}.into());
+			}
+		}
+
+		// feild values, in the order they're defined in
+		let mut feild_slots = vec!();
+		for feild in object.feilds {
+			let feild_type = self.data.resolve_feild_type(&feild.ast_type, object.file_index, &location)?;
+			let value = feilds.iter().find(|(feild_name, _)| *feild_name == feild.name).map(|(_, value)| value);
+			let slot = match (value, &feild.default_value) {
+				(Some(value), _) => self.lower_value_as(value, &feild_type, builder)?,
+				(None, Some(default_value)) => {
+					if self.lowering_defaults.contains(&object.name) {
+						return Err(RawCompileError::CircularDefaultValue {location, object: name.to_string(), feild: feild.name.to_string()}.into());
+					}
+					self.lowering_defaults.push(object.name);
+					let slot = self.lower_value_as(default_value, &feild_type, builder);
+					self.lowering_defaults.pop();
+					// defaults are checked where they're defined, except for permissions which depend on where the object is created
+					slot.map_err(|error| match error.raw_error() {
+						RawCompileError::MissingPermission {..} => error,
+						_ => error.into_reported(),
+					})?
+				}
+				(None, None) => return Err(RawCompileError::MissingObjectFeild {location, object: name.to_string(), feild: feild.name.to_string()}.into()),
+			};
+			feild_slots.push(slot);
+		}
+
+		let object_type = TuaType::Object(name.to_string());
+		let output = builder.new_slot(StackType::Object);
+		builder.push(Instruction::NewObject {type_id: self.data.get_type_id(&object_type), variant: 0, feilds: feild_slots, output});
+		Ok((Some(output), object_type))
+	}
+
+
+
+	fn lower_choice_variant (&mut self, choice: Option<&str>, variant: &str, payload: &ASTVariantPayload<'a>, expected: Option<&TuaType>, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+		let location = builder.get_location(variant);
+
+		// other variants are the value of an expected `MaybeError`, like `Nothing` in `MaybeError<Optional<int>>`
+		let expected = match expected {
+			Some(TuaType::MaybeError(value_type, _)) if variant != "Value" && variant != "Error" => Some(&**value_type),
+			other => other,
+		};
+
+		// find which choice the variant belongs to, unqualified variants use the expected type or the only choice that has them
+		let choice_name = match choice {
+			Some(choice) => choice,
+			None => match expected {
+				Some(TuaType::Optional(_)) if variant == "Filled" || variant == "Nothing" => "Optional",
+				Some(TuaType::MaybeError(..)) if variant == "Value" || variant == "Error" => "MaybeError",
+				Some(TuaType::ControlFlow(..)) if variant == "Pass" || variant == "Return" => "ControlFlow",
+				Some(TuaType::Choice(name)) if self.data.choices.iter().any(|choice| choice.name == name && choice.choices.iter().any(|choice_variant| choice_variant.name == variant)) => name,
+				_ if variant == "Filled" || variant == "Nothing" => "Optional",
+				_ => {
+					let mut matching_choices = self.data.choices.iter().filter(|choice| choice.choices.iter().any(|choice_variant| choice_variant.name == variant));
+					match (matching_choices.next(), matching_choices.next()) {
+						(Some(choice), None) => choice.name,
+						(Some(_), Some(_)) => return Err(RawCompileError::CannotInferType {location}.into()),
+						(None, _) if variant == "Value" || variant == "Error" => "MaybeError",
+						(None, _) if variant == "Pass" || variant == "Return" => "ControlFlow",
+						(None, _) => return Err(RawCompileError::UndefinedName {location, name: variant.to_string(), suggestion: None}.into()),
+					}
+				}
+			},
+		};
+		let full_name = format!("{choice_name}.{variant}");
+
+		// `MaybeError` and `ControlFlow` are built in too, and both of their types come from the expected type
+		if choice_name == "MaybeError" || choice_name == "ControlFlow" {
+			let Some(expected_type) = expected.filter(|expected_type| expected_type.get_type_name() == choice_name) else {
+				return Err(RawCompileError::CannotInferType {location}.into());
+			};
+			let (TuaType::MaybeError(first_type, second_type) | TuaType::ControlFlow(first_type, second_type)) = expected_type else {unreachable!()};
+			let [first_variant, second_variant] = get_built_in_variants(choice_name).unwrap();
+			let (variant_index, feild_type) = match variant {
+				_ if variant == first_variant => (0, first_type),
+				_ if variant == second_variant => (1, second_type),
+				_ => return Err(RawCompileError::UnknownFeild {location, type_name: choice_name.to_string(), feild: variant.to_string()}.into()),
+			};
+			let values = match payload {
+				ASTVariantPayload::Tuple(values) if values.len() == 1 => values,
+				_ => return Err(RawCompileError::InvalidVariantPayload {location, variant: full_name, expected: String::from("`(value)`")}.into()),
+			};
+			// while a function's error type is being inferred, its errors can be anything
+			let (slot, output_type) = if choice_name == "MaybeError" && variant_index == ERROR_VARIANT && **second_type == TuaType::Union(vec!()) {
+				let (slot, error_type) = self.lower_value(&values[0], None, builder)?;
+				(slot, TuaType::MaybeError(first_type.clone(), box error_type))
+			} else {
+				(self.lower_value_as(&values[0], feild_type, builder)?, expected_type.clone())
+			};
+			let output = builder.new_slot(StackType::Object);
+			builder.push(Instruction::NewObject {type_id: self.data.get_type_id(&output_type), variant: variant_index, feilds: vec!(slot), output});
+			return Ok((Some(output), output_type));
+		}
+
+		// `Optional` is built in, and its inner type comes from the expected type or the payload
+		if choice_name == "Optional" {
+			let expected_inner_type = match expected {
+				Some(TuaType::Optional(inner_type)) => Some(&**inner_type),
+				_ => None,
+			};
+			return match (variant, payload) {
+				("Nothing", ASTVariantPayload::None) => {
+					let Some(inner_type) = expected_inner_type else {
+						return Err(RawCompileError::CannotInferType {location}.into());
+					};
+					let output = builder.new_slot(StackType::Object);
+					builder.push(Instruction::NewObject {type_id: OPTIONAL_TYPE_ID, variant: NOTHING_VARIANT, feilds: vec!(), output});
+					Ok((Some(output), TuaType::Optional(box inner_type.clone())))
+				}
+				("Filled", ASTVariantPayload::Tuple(values)) if values.len() == 1 => {
+					let (slot, inner_type) = match expected_inner_type {
+						Some(inner_type) => (self.lower_value_as(&values[0], inner_type, builder)?, inner_type.clone()),
+						None => self.lower_value(&values[0], None, builder)?,
+					};
+					let output = builder.new_slot(StackType::Object);
+					builder.push(Instruction::NewObject {type_id: OPTIONAL_TYPE_ID, variant: FILLED_VARIANT, feilds: vec!(slot), output});
+					Ok((Some(output), TuaType::Optional(box inner_type)))
+				}
+				("Nothing", _) => Err(RawCompileError::InvalidVariantPayload {location, variant: full_name, expected: String::from("no payload")}.into()),
+				("Filled", _) => Err(RawCompileError::InvalidVariantPayload {location, variant: full_name, expected: String::from("`(value)`")}.into()),
+				_ => Err(RawCompileError::UnknownFeild {location, type_name: String::from("Optional"), feild: variant.to_string()}.into()),
+			};
+		}
+
+		let Some(choice) = self.data.choices.iter().find(|choice| choice.name == choice_name).copied() else {
+			return Err(RawCompileError::UnknownType {location: builder.get_location(choice_name), name: choice_name.to_string()}.into());
+		};
+		let Some(variant_index) = choice.choices.iter().position(|choice_variant| choice_variant.name == variant) else {
+			return Err(RawCompileError::UnknownFeild {location, type_name: choice_name.to_string(), feild: variant.to_string()}.into());
+		};
+		let choice_variant = &choice.choices[variant_index];
+		self.check_permission(choice_name, "create", &location, builder)?;
+
+		// payload values, in the order they're defined in
+		let mut feild_slots = vec!();
+		match (&choice_variant.payload, payload) {
+			(ASTChoicePayload::None, ASTVariantPayload::None) => {}
+			(ASTChoicePayload::Tuple(types), ASTVariantPayload::Tuple(values)) if types.len() == values.len() => {
+				for (ast_type, value) in types.iter().zip(values) {
+					let feild_type = self.data.resolve_feild_type(ast_type, choice.file_index, &location)?;
+					feild_slots.push(self.lower_value_as(value, &feild_type, builder)?);
+				}
+			}
+			(ASTChoicePayload::Feilds(feild_types), ASTVariantPayload::Feilds(values)) => {
+				for (feild_name, _) in values {
+					if !feild_types.iter().any(|(name, _)| name == feild_name) {
+						return Err(RawCompileError::UnknownFeild {location: builder.get_location(feild_name), type_name: full_name, feild: feild_name.to_string()}.into());
+					}
+				}
+				for (feild_name, ast_type) in feild_types {
+					let Some((_, value)) = values.iter().find(|(name, _)| name == feild_name) else {
+						return Err(RawCompileError::MissingObjectFeild {location, object: full_name, feild: feild_name.to_string()}.into());
+					};
+					let feild_type = self.data.resolve_feild_type(ast_type, choice.file_index, &location)?;
+					feild_slots.push(self.lower_value_as(value, &feild_type, builder)?);
+				}
+			}
+			(expected_payload, _) => return Err(RawCompileError::InvalidVariantPayload {location, variant: full_name, expected: describe_payload(expected_payload)}.into()),
+		}
+
+		let choice_type = TuaType::Choice(choice_name.to_string());
+		let output = builder.new_slot(StackType::Object);
+		builder.push(Instruction::NewObject {type_id: self.data.get_type_id(&choice_type), variant: variant_index, feilds: feild_slots, output});
+		Ok((Some(output), choice_type))
+	}
+
+
+
+	fn lower_property_query (&mut self, base: &ASTFormula<'a>, key: &str, expected: Option<&TuaType>, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+
+		// module consts
+		if let ASTFormula::Name(name) = base {
+			if builder.get_var(name).is_none() {
+				if let Some(module_file_index) = self.data.get_module_file_index(name, builder.file_index) {
+					let Some((_, _, value)) = self.data.consts.iter().find(|(file_index, const_name, _)| *file_index == module_file_index && *const_name == key) else {
+						return Err(RawCompileError::UndefinedName {location: builder.get_location(key), name: format!("{name}.{key}"), suggestion: None}.into());
+					};
+					return self.lower_formula(value, expected, builder);
+				}
+			}
+		}
+
+		let (base, base_type) = self.lower_value(base, None, builder)?;
+		let (output, output_type) = self.lower_get_feild(base, &base_type, key, builder)?;
+		Ok((Some(output), output_type))
+	}
+
+
+
+
+
+	// anonymous functions become their own bytecode functions, which are given the values of the variables they capture before their args
+	// `expected_args` and `expected_return` are for args and return types that aren't written out, and come from the `Function<...>` type it's given as
+	fn lower_anonymous_function (&mut self, args: &[(&'a str, Option<ASTType<'a>>)], return_type: &Option<ASTType<'a>>, statements: &[ASTStatement<'a>], captures: &[&'a str], expected_args: &[Option<TuaType>], expected_return: Option<TuaType>, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+
+		// signature
+		let mut arg_types = vec!();
+		for (i, (name, arg_type)) in args.iter().enumerate() {
+			let location = builder.get_location(name);
+			arg_types.push(match (arg_type, expected_args.get(i).cloned().flatten()) {
+				(Some(arg_type), _) => self.data.resolve_generic_type(arg_type, builder.file_index, &builder.type_bindings, &location)?,
+				(None, Some(expected)) => expected,
+				(None, None) => return Err(RawCompileError::UntypedFunctionArg {location, name: name.to_string()}.into()),
+			});
+		}
+		let return_type = match return_type {
+			Some(return_type) => Some(self.data.resolve_generic_type(return_type, builder.file_index, &builder.type_bindings, &builder.location)?),
+			None => expected_return,
+		};
+
+		// only variables are captured, functions and consts are found the same way they are outside of it
+		let captures: Vec<(&'a str, Slot, TuaType)> = captures.iter()
+			.filter_map(|name| builder.get_var(name).map(|(slot, tua_type)| (*name, slot, tua_type)))
+			.collect();
+
+		// body
+		let mut function_builder = FunctionBuilder {
+			file_index: builder.file_index,
+			locations: builder.locations,
+			location: builder.location.clone(),
+			return_type: return_type.clone().unwrap_or(TuaType::None),
+			frame_sizes: [0; StackType::COUNT],
+			instructions: vec!(),
+			scopes: vec!(vec!()),
+			loops: vec!(),
+			errors: vec!(),
+			poisoned_vars: vec!(),
+			captured_slots: vec!(),
+			type_bindings: builder.type_bindings.clone(),
+			inferred_errors: None,
+			infers_return_type: return_type.is_none(),
+			permissions: builder.permissions.clone(),
+		};
+		let mut arg_slots = vec!();
+		let mut full_arg_types = vec!();
+		for (name, _, tua_type) in &captures {
+			arg_slots.push(function_builder.add_var(name, tua_type.clone())?);
+			full_arg_types.push(tua_type.clone());
+		}
+		function_builder.captured_slots = arg_slots.clone();
+		for ((name, _), arg_type) in args.iter().zip(&arg_types) {
+			arg_slots.push(function_builder.add_var(name, arg_type.clone())?);
+			full_arg_types.push(arg_type.clone());
+		}
+		let name = String::from("anonymous function");
+		self.lower_block(statements, &mut function_builder);
+		function_builder.finish_body(statements, &name);
+		if !function_builder.errors.is_empty() {
+			let mut errors = function_builder.errors;
+			let first_error = errors.remove(0);
+			builder.errors.extend(errors);
+			return Err(first_error);
+		}
+
+		let return_type = function_builder.return_type;
+		let function_id = self.functions.len();
+		self.functions.push(Some(BytecodeFunction {
+			name,
+			arg_types: full_arg_types,
+			arg_slots,
+			return_type: return_type.clone(),
+			frame_sizes: function_builder.frame_sizes,
+			instructions: function_builder.instructions,
+		}));
+		let output = builder.new_slot(StackType::Function);
+		builder.push(Instruction::NewClosure {function: function_id, captures: captures.iter().map(|(_, slot, _)| *slot).collect(), output});
+		Ok((Some(output), TuaType::Function(arg_types, box return_type)))
+	}
+
+
+
+	fn lower_call (&mut self, base: CallBase<'_, 'a>, args: &[ASTFormula<'a>], expected: Option<&TuaType>, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+
+		// split into `name(args)` or `receiver.name(args)`
+		let (receiver, name) = match base {
+			CallBase::Formula (ASTFormula::Name(name)) => (None, *name),
+			CallBase::Var (name, []) => (None, name),
+			CallBase::Formula (ASTFormula::PropertyQuery {base, key}) => (Some(CallBase::Formula(base)), *key),
+			CallBase::Var (start_name, [base_queries @ .., VarQuery::Feild(key)]) => (Some(CallBase::Var(start_name, base_queries)), *key),
+			CallBase::Formula (formula) => {
+				let location = builder.get_formula_location(formula);
+				let (closure, closure_type) = self.lower_value(formula, None, builder)?;
+				return self.lower_closure_call(closure, &closure_type, &closure_type.to_string(), args, &location, builder);
+			}
+			CallBase::Var (start_name, var_queries) => {
+				let location = builder.get_location(start_name);
+				let (closure, closure_type) = self.lower_var_queries(start_name, var_queries, builder)?;
+				return self.lower_closure_call(closure, &closure_type, &closure_type.to_string(), args, &location, builder);
+			}
+		};
+		let location = &builder.get_location(name);
+
+		// variables that hold functions
+		if let (None, Some((closure, closure_type))) = (&receiver, builder.get_var(name)) {
+			return self.lower_closure_call(closure, &closure_type, name, args, location, builder);
+		}
+
+		// plain functions
+		let Some(receiver) = receiver else {
+			let file_index = builder.file_index;
+			let candidates = (0..self.data.functions.len())
+				.filter(|i| {
+					let function = &self.data.functions[*i];
+					function.name == name && function.associated_type.is_none() && self.data.is_function_visible(function, file_index)
+				})
+				.collect::<Vec<_>>();
+			if !candidates.is_empty() {
+				return self.lower_user_function_call(&candidates, None, args, location, builder);
+			}
+			if name == "read_line" && args.is_empty() {
+				let output = builder.new_slot(StackType::String);
+				builder.push(Instruction::CallNative {native: NativeFunction::ReadLine, args: vec!(), output: Some(output)});
+				return Ok((Some(output), TuaType::String));
+			}
+			return Err(RawCompileError::UndefinedName {location: location.clone(), name: name.to_string(), suggestion: None}.into());
+		};
+
+		// module functions and static associated functions
+		let receiver_name = match receiver {
+			CallBase::Formula (ASTFormula::Name(receiver_name)) => Some(*receiver_name),
+			CallBase::Var (receiver_name, []) => Some(receiver_name),
+			_ => None,
+		};
+		if let Some(receiver_name) = receiver_name.filter(|receiver_name| builder.get_var(receiver_name).is_none()) {
+			let candidates = if let Some(module_file_index) = self.data.get_module_file_index(receiver_name, builder.file_index) {
+				(0..self.data.functions.len())
+					.filter(|i| {
+						let function = &self.data.functions[*i];
+						function.name == name && function.associated_type.is_none() && function.file_index == module_file_index
+					})
+					.collect::<Vec<_>>()
+			} else {
+				(0..self.data.functions.len())
+					.filter(|i| {
+						let function = &self.data.functions[*i];
+						function.name == name && !function.has_self && function.associated_type.map(|associated_type| associated_type.name) == Some(receiver_name)
+					})
+					.collect::<Vec<_>>()
+			};
+			if candidates.is_empty() {
+				return Err(RawCompileError::UndefinedName {location: location.clone(), name: format!("{receiver_name}.{name}"), suggestion: None}.into());
+			}
+			return self.lower_user_function_call(&candidates, None, args, location, builder);
+		}
+
+		// methods
+		let (receiver, receiver_type) = match receiver {
+			CallBase::Formula (formula) => self.lower_value(formula, None, builder)?,
+			CallBase::Var (start_name, var_queries) => self.lower_var_queries(start_name, var_queries, builder)?,
+		};
+		let type_name = receiver_type.get_type_name();
+		let candidates = self.get_method_candidates(&type_name, name);
+		if !candidates.is_empty() {
+			return self.lower_user_function_call(&candidates, Some((receiver, receiver_type)), args, location, builder);
+		}
+		if let Some((native, arg_types, return_type)) = get_native_method(&receiver_type, name) {
+			if args.len() != arg_types.len() {
+				return Err(RawCompileError::NoMatchingFunction {location: location.clone(), name: format!("{type_name}.{name}"), arg_count: args.len()}.into());
+			}
+			let mut arg_slots = vec!(receiver);
+			for (arg, arg_type) in args.iter().zip(arg_types.iter()) {
+				arg_slots.push(self.lower_value_as(arg, arg_type, builder)?);
+			}
+			let output = match return_type.get_stack_type() {
+				Some(stack) => Some(builder.new_slot(stack)),
+				None => None,
+			};
+			builder.push(Instruction::CallNative {native, args: arg_slots, output});
+			return Ok((output, return_type));
+		}
+		if name == "to_string" && args.is_empty() {
+			let output = builder.new_slot(StackType::String);
+			builder.push(Instruction::ToString {base: receiver, output});
+			return Ok((Some(output), TuaType::String));
+		}
+		Err(RawCompileError::UndefinedName {location: location.clone(), name: format!("{type_name}.{name}"), suggestion: None}.into())
+	}
+
+
+
+	fn lower_closure_call (&mut self, closure: Slot, closure_type: &TuaType, name: &str, args: &[ASTFormula<'a>], location: &Token, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+		let TuaType::Function(arg_types, return_type) = closure_type else {
+			return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: String::from("a function"), found: closure_type.to_string()}.into());
+		};
+		if args.len() != arg_types.len() {
+			return Err(RawCompileError::NoMatchingFunction {location: location.clone(), name: name.to_string(), arg_count: args.len()}.into());
+		}
+		let mut arg_slots = vec!();
+		for (arg, arg_type) in args.iter().zip(arg_types) {
+			arg_slots.push(self.lower_value_as(arg, arg_type, builder)?);
+		}
+		let output = match return_type.get_stack_type() {
+			Some(stack) => Some(builder.new_slot(stack)),
+			None => None,
+		};
+		builder.push(Instruction::CallClosure {closure, args: arg_slots, output});
+		Ok((output, *return_type.clone()))
+	}
+
+
+
+	// functions like `function Person.say_name (self)`
+	fn get_method_candidates (&self, type_name: &str, name: &str) -> Vec<usize> {
+		(0..self.data.functions.len())
+			.filter(|i| {
+				let function = &self.data.functions[*i];
+				function.name == name && function.has_self && function.associated_type.map(|associated_type| associated_type.name) == Some(type_name)
+			})
+			.collect()
+	}
+
+	// overloads are tried in order, and the first one whose args can be lowered is used
+	fn lower_user_function_call (&mut self, candidates: &[usize], self_arg: Option<(Slot, TuaType)>, args: &[ASTFormula<'a>], location: &Token, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+		let given_arg_count = args.len() + self_arg.is_some() as usize;
+		let mut last_error = None;
+		let snapshot = self.take_snapshot(builder);
+		for function_index in candidates {
+			let function = self.data.functions[*function_index];
+			if function.args.len() < given_arg_count || function.args[given_arg_count..].iter().any(|arg| arg.default.is_none()) {continue;}
+			if let Err(error) = self.check_signature(*function_index) {
+				last_error = last_error.or(Some(error.into_reported()));
+				continue;
+			}
+
+			// the last overload that didn't fit is kept if none of them fit, since its error is the one that's reported
+			self.restore_snapshot(&snapshot, builder);
+			match self.lower_user_function_args(*function_index, &self_arg, args, location, builder) {
+				Ok(output) => return Ok(output),
+				Err(error) => last_error = Some(error),
+			}
+		}
+		Err(last_error.unwrap_or_else(|| RawCompileError::NoMatchingFunction {
+			location: location.clone(),
+			name: self.data.functions[candidates[0]].name.to_string(),
+			arg_count: args.len(),
+		}.into()))
+	}
+
+	fn take_snapshot (&self, builder: &FunctionBuilder<'a>) -> LoweringSnapshot {
+		LoweringSnapshot {
+			instructions_len: builder.instructions.len(),
+			frame_sizes: builder.frame_sizes,
+			errors_len: builder.errors.len(),
+			functions_len: self.functions.len(),
+			queue_len: self.queue.len(),
+			failed_error_types_len: self.failed_error_types.len(),
+		}
+	}
+
+	// undoes an overload that didn't fit, including the anonymous functions and generic functions its args added and the errors in them
+	fn restore_snapshot (&mut self, snapshot: &LoweringSnapshot, builder: &mut FunctionBuilder<'a>) {
+		builder.instructions.truncate(snapshot.instructions_len);
+		builder.frame_sizes = snapshot.frame_sizes;
+		builder.errors.truncate(snapshot.errors_len);
+		self.functions.truncate(snapshot.functions_len);
+		self.function_ids.retain(|_, function_id| *function_id < snapshot.functions_len);
+		self.queue.truncate(snapshot.queue_len);
+		self.failed_error_types.truncate(snapshot.failed_error_types_len);
+	}
+
+	fn lower_user_function_args (&mut self, function_index: usize, self_arg: &Option<(Slot, TuaType)>, args: &[ASTFormula<'a>], location: &Token, builder: &mut FunctionBuilder<'a>) -> Result<LoweredValue, CompileError> {
+		let function = self.data.functions[function_index];
+		let mut bindings = vec!();
+		let mut arg_slots = vec!();
+
+		// self
+		if let Some((self_slot, self_type)) = self_arg {
+			self.unify_type(&function.args[0].ast_type, self_type, function.file_index, &mut bindings, location)?;
+			arg_slots.push(*self_slot);
+		}
+
+		// given args, literals point at the call when they don't match
+		let function_location = std::mem::replace(&mut builder.location, location.clone());
+		let given_slots = self.lower_given_args(function, args, arg_slots.len(), &mut bindings, builder);
+		builder.location = function_location;
+		arg_slots.extend(given_slots?);
+
+		// default args
+		// a bad default is reported when the function is lowered, unless it's generic and its type depends on the call
+		for arg in &function.args[arg_slots.len()..] {
+			let default = get_default_formula(arg.default.unwrap());
+			let slot = self.lower_arg(&default, &arg.ast_type, function.file_index, &mut bindings, builder);
+			arg_slots.push(if function.get_generic_names().is_empty() {slot.map_err(CompileError::into_reported)?} else {slot?});
+		}
+
+		// the generic types have to support everything the function does with them, which is checked here so the error points at the call
+		let requirements = if bindings.is_empty() {vec!()} else {generics::get_generic_requirements(&function)};
+		for requirement in requirements {
+			let Some(missing) = generics::find_missing_requirement(&requirement, &bindings, &self.data, location) else {continue;};
+			return Err(RawCompileError::MissingGenericRequirement {
+				location: location.clone(),
+				type_name: missing.type_name,
+				kind: missing.kind.to_string(),
+				name: missing.name.to_string(),
+				used_on: missing.used_on,
+				required_by: function.name.to_string(),
+			}.into());
+		}
+
+		let return_type = self.get_return_type(function_index, &bindings, location)?;
+		let type_args = function.get_generic_names().into_iter()
+			.map(|name| bindings.iter().find(|(bound_name, _)| *bound_name == name).unwrap().1.clone())
+			.collect();
+		let function_id = self.get_function_id(function_index, type_args);
+		let output = match return_type.get_stack_type() {
+			Some(stack) => Some(builder.new_slot(stack)),
+			None => None,
+		};
+		builder.push(Instruction::Call {function: function_id, args: arg_slots, output});
+		Ok((output, return_type))
+	}
+
+	// literals are lowered after the other args so they can take their type from a generic type that the other args decide
+	// anonymous functions are lowered last, since the types of their args can come from any of the others
+	fn lower_given_args (&mut self, function: FunctionData<'a>, args: &[ASTFormula<'a>], first_arg: usize, bindings: &mut Vec<(&'a str, TuaType)>, builder: &mut FunctionBuilder<'a>) -> Result<Vec<Slot>, CompileError> {
+		let mut arg_slots = vec!(None; args.len());
+		let mut arg_order: Vec<usize> = (0..args.len()).collect();
+		arg_order.sort_by_key(|i| match &args[*i] {
+			ASTFormula::Function {..} => 2,
+			arg if is_literal(arg) => 1,
+			_ => 0,
+		});
+		for i in arg_order {
+			arg_slots[i] = Some(self.lower_arg(&args[i], &function.args[first_arg + i].ast_type, function.file_index, bindings, builder)?);
+		}
+		Ok(arg_slots.into_iter().map(Option::unwrap).collect())
+	}
+
+	// lowers an arg as its type if that's known, otherwise the generic types in it are decided by the arg
+	fn lower_arg (&mut self, arg: &ASTFormula<'a>, arg_type: &ASTType<'a>, file_index: usize, bindings: &mut Vec<(&'a str, TuaType)>, builder: &mut FunctionBuilder<'a>) -> Result<Slot, CompileError> {
+		let mut generic_names = vec!();
+		arg_type.add_generic_names(&mut generic_names);
+		if generic_names.iter().all(|name| bindings.iter().any(|(bound_name, _)| bound_name == name)) {
+			let resolved_type = self.data.resolve_generic_type(arg_type, file_index, bindings, &builder.get_formula_location(arg))?;
+			return self.lower_value_as(arg, &resolved_type, builder).map_err(|error| match error.raw_error() {
+				RawCompileError::MismatchedTypes {location, expected, found} if ASTType::is_generic_name(arg_type.name) => RawCompileError::ConflictingGenericTypes {
+					location: location.clone(),
+					name: arg_type.name.to_string(),
+					first: expected.to_string(),
+					second: found.to_string(),
+				}.into(),
+				_ => error,
+			});
+		}
+
+		// anonymous functions get every part of their type that's already known, and decide the rest
+		if let (ASTFormula::Function {args: function_args, return_type, statements, captures}, "Function") = (arg, arg_type.name) {
+			let location = builder.get_formula_location(arg);
+			let expected_args: Vec<Option<TuaType>> = get_function_arg_types(arg_type).into_iter()
+				.map(|function_arg_type| self.resolve_bound_type(function_arg_type, file_index, bindings, &location))
+				.collect();
+			let expected_return = match get_named_type_arg(arg_type, "returns") {
+				Some(expected_return) => self.resolve_bound_type(expected_return, file_index, bindings, &location),
+				None => Some(TuaType::None),
+			};
+			let (slot, found) = self.lower_anonymous_function(function_args, return_type, statements, captures, &expected_args, expected_return, builder)?;
+			self.unify_type(arg_type, &found, file_index, bindings, &location)?;
+			return Ok(slot.unwrap());
+		}
+
+		let (slot, found) = self.lower_value(arg, None, builder)?;
+		self.unify_type(arg_type, &found, file_index, bindings, &builder.get_formula_location(arg))?;
+		Ok(slot)
+	}
+
+	// a type from a function's signature, or None if it uses a generic type that isn't known yet
+	fn resolve_bound_type (&self, ast_type: &ASTType<'a>, file_index: usize, bindings: &[(&'a str, TuaType)], location: &Token) -> Option<TuaType> {
+		let mut generic_names = vec!();
+		ast_type.add_generic_names(&mut generic_names);
+		if !generic_names.iter().all(|name| bindings.iter().any(|(bound_name, _)| bound_name == name)) {return None;}
+		self.data.resolve_generic_type(ast_type, file_index, bindings, location).ok()
+	}
+
+	// matches a type from a function's signature against the type it's given, and sets the generic types in it
+	fn unify_type (&self, ast_type: &ASTType<'a>, found: &TuaType, file_index: usize, bindings: &mut Vec<(&'a str, TuaType)>, location: &Token) -> Result<(), CompileError> {
+		if ASTType::is_generic_name(ast_type.name) {
+			match bindings.iter().find(|(name, _)| *name == ast_type.name) {
+				Some((_, bound_type)) if bound_type != found => return Err(RawCompileError::ConflictingGenericTypes {
+					location: location.clone(),
+					name: ast_type.name.to_string(),
+					first: bound_type.to_string(),
+					second: found.to_string(),
+				}.into()),
+				Some(_) => {}
+				None => bindings.push((ast_type.name, found.clone())),
+			}
+			return Ok(());
+		}
+		match (ast_type.name, &ast_type.unnamed_type_arg, found) {
+			("Array", Some(inner_type), TuaType::Array(found_inner_type)) | ("Optional", Some(inner_type), TuaType::Optional(found_inner_type)) => {
+				return self.unify_type(inner_type, found_inner_type, file_index, bindings, location);
+			}
+			("Array", _, _) if !matches!(found, TuaType::Array(_)) => return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: String::from("an array"), found: found.to_string()}.into()),
+			("Optional", _, _) if !matches!(found, TuaType::Optional(_)) => return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: String::from("an `Optional`"), found: found.to_string()}.into()),
+			("Function", _, TuaType::Function(found_arg_types, found_return_type)) => {
+				let arg_types = get_function_arg_types(ast_type);
+				if arg_types.len() != found_arg_types.len() {
+					return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: format!("a function that takes {} argument(s)", arg_types.len()), found: found.to_string()}.into());
+				}
+				for (arg_type, found_arg_type) in arg_types.into_iter().zip(found_arg_types) {
+					self.unify_type(arg_type, found_arg_type, file_index, bindings, location)?;
+				}
+				if let Some(return_type) = get_named_type_arg(ast_type, "returns") {
+					self.unify_type(return_type, found_return_type, file_index, bindings, location)?;
+				}
+			}
+			("Function", _, _) => return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: String::from("a function"), found: found.to_string()}.into()),
+			("Tuple", _, TuaType::Tuple(found_item_types)) => {
+				if ast_type.tuple_items.len() != found_item_types.len() {
+					return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: format!("a tuple with {} items", ast_type.tuple_items.len()), found: found.to_string()}.into());
+				}
+				for (item_type, found_item_type) in ast_type.tuple_items.iter().zip(found_item_types) {
+					self.unify_type(item_type, found_item_type, file_index, bindings, location)?;
+				}
+			}
+			("Tuple", _, _) => return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: String::from("a tuple"), found: found.to_string()}.into()),
+			_ => {}
+		}
+		let expected = self.data.resolve_generic_type(ast_type, file_index, bindings, location)?;
+		if expected != *found {
+			return Err(RawCompileError::MismatchedTypes {location: location.clone(), expected: expected.to_string(), found: found.to_string()}.into());
+		}
+		Ok(())
+	}
+
+}
+
+
+
+
+
+// whether a block can never reach its end, `switch` statements are always exhaustive so they only need every case to return
+pub fn block_always_returns (block: &[ASTStatement]) -> bool {
+	block.iter().any(|statement| match statement {
+		ASTStatement::Return {..} | ASTStatement::Throw {..} | ASTStatement::Crash {..} | ASTStatement::Todo {..} => true,
+		ASTStatement::If {true_block, false_block, ..} => block_always_returns(true_block) && block_always_returns(false_block),
+		ASTStatement::Switch {cases, ..} => !cases.is_empty() && cases.iter().all(|case| block_always_returns(&case.block)),
+		ASTStatement::Loop {block} => !block_has_break(block),
+		_ => false,
+	})
+}
+
+// whether a block breaks out of the loop it's in, not counting loops inside of it
+pub fn block_has_break (block: &[ASTStatement]) -> bool {
+	block.iter().any(|statement| match statement {
+		ASTStatement::Break => true,
+		ASTStatement::If {true_block, false_block, ..} => block_has_break(true_block) || block_has_break(false_block),
+		ASTStatement::Switch {cases, ..} => cases.iter().any(|case| block_has_break(&case.block)),
+		_ => false,
+	})
+}
+
+
+
+pub fn is_literal (formula: &ASTFormula) -> bool {
+	match formula {
+		ASTFormula::Int (_) | ASTFormula::UInt (_) | ASTFormula::Float (_) => true,
+		ASTFormula::Operation {left, right, ..} => is_literal(left) && is_literal(right),
+		_ => false,
+	}
+}
+
+// number literals take the expected type when there is one
+pub fn get_default_formula<'a> (default: &'a RawToken) -> ASTFormula<'a> {
+	match default {
+		RawToken::Int (value) => ASTFormula::Int(*value),
+		RawToken::UInt (value) => ASTFormula::UInt(*value),
+		RawToken::Float (value) => ASTFormula::Float(*value),
+		RawToken::TypedNumber (value, tua_type) => ASTFormula::TypedNumber {value: box parser::get_number_formula(value, false).unwrap(), tua_type: tua_type.clone()},
+		RawToken::Bool (value) => ASTFormula::Bool(*value),
+		RawToken::String (value) => ASTFormula::String(value),
+		RawToken::Char (value) => ASTFormula::Char(*value),
+		_ => unreachable!(),
+	}
+}
+
+pub fn get_literal_type (expected: Option<&TuaType>, default_type: TuaType) -> TuaType {
+	let expected = match expected {
+		Some(TuaType::Optional(inner_type) | TuaType::MaybeError(inner_type, _)) => Some(&**inner_type),
+		other => other,
+	};
+	match expected {
+		Some(tua_type) if tua_type.is_number() && (tua_type.is_float() || !default_type.is_float()) => tua_type.clone(),
+		_ => default_type,
+	}
+}
+
+
+
+// (native, arg types not including self, return type)
+pub fn get_native_method (receiver_type: &TuaType, name: &str) -> Option<(NativeFunction, Vec<TuaType>, TuaType)> {
+	Some(match (receiver_type, name) {
+		(tua_type, "abs") if tua_type.is_number() => (NativeFunction::Abs, vec!(), tua_type.clone()),
+		(TuaType::String, "length") => (NativeFunction::StringLength, vec!(), TuaType::UInt64),
+		(TuaType::String, "trim") => (NativeFunction::StringTrim, vec!(), TuaType::String),
+		(TuaType::String, "to_upper") => (NativeFunction::StringToUpper, vec!(), TuaType::String),
+		(TuaType::String, "to_lower") => (NativeFunction::StringToLower, vec!(), TuaType::String),
+		(TuaType::String, "contains") => (NativeFunction::StringContains, vec!(TuaType::String), TuaType::Bool),
+		(TuaType::String, "split") => (NativeFunction::StringSplit, vec!(TuaType::String), TuaType::Array(box TuaType::String)),
+		(TuaType::String, "to_uint") => (NativeFunction::StringToUInt, vec!(), TuaType::Optional(box TuaType::UInt64)),
+		(TuaType::Array(_), "length") => (NativeFunction::ArrayLength, vec!(), TuaType::UInt64),
+		(TuaType::Array(item_type), "add") => (NativeFunction::ArrayAdd, vec!(*item_type.clone()), TuaType::None),
+		(TuaType::Array(item_type), "contains") => (NativeFunction::ArrayContains, vec!(*item_type.clone()), TuaType::Bool),
+		_ => return None,
+	})
+}
+
+
+
+pub fn get_named_type_arg<'b, 'a> (ast_type: &'b ASTType<'a>, name: &str) -> Option<&'b ASTType<'a>> {
+	ast_type.named_type_args.iter().find(|(arg_name, _)| *arg_name == name).map(|(_, arg_type)| arg_type)
+}
+
+// the arg types of `Function<(int, String), returns: bool>`, an arg type that isn't a tuple is the only arg
+pub fn get_function_arg_types<'b, 'a> (ast_type: &'b ASTType<'a>) -> Vec<&'b ASTType<'a>> {
+	match ast_type.unnamed_type_arg.as_deref() {
+		Some(ASTType {name: "Tuple", tuple_items, ..}) => tuple_items.iter().collect(),
+		Some(arg_type) => vec!(arg_type),
+		None => vec!(),
+	}
+}
+
+// the variants of the built-in types that work like choices
+pub fn get_built_in_variants (type_name: &str) -> Option<[&'static str; 2]> {
+	Some(match type_name {
+		"Optional" => ["Nothing", "Filled"],
+		"MaybeError" => ["Value", "Error"],
+		"ControlFlow" => ["Pass", "Return"],
+		_ => return None,
+	})
+}
+
+// whether a value of one error type can be used as another without changing it, like `InvalidUInt` as `(InvalidInputLength or InvalidUInt)`
+pub fn is_error_subtype (found: &TuaType, expected: &TuaType) -> bool {
+	if found == expected {return true;}
+	let TuaType::Union(expected_members) = expected else {return false;};
+	match found {
+		TuaType::Union(found_members) => found_members.iter().all(|member| expected_members.contains(member)),
+		_ => expected_members.contains(found),
+	}
+}
+
+
+
+pub fn get_payload_feild_names (payload: &ASTChoicePayload) -> Vec<String> {
+	match payload {
+		ASTChoicePayload::None => vec!(),
+		ASTChoicePayload::Tuple (types) => (0..types.len()).map(|i| i.to_string()).collect(),
+		ASTChoicePayload::Feilds (feilds) => feilds.iter().map(|(name, _)| name.to_string()).collect(),
+	}
+}
+
+// used in errors, like "`(int, String)`" or "`(radius: float)`"
+pub fn describe_payload (payload: &ASTChoicePayload) -> String {
+	match payload {
+		ASTChoicePayload::None => String::from("no payload"),
+		ASTChoicePayload::Tuple (types) => {
+			let types: Vec<&str> = types.iter().map(|ast_type| ast_type.name).collect();
+			format!("`({})`", types.join(", "))
+		}
+		ASTChoicePayload::Feilds (feilds) => {
+			let feilds: Vec<String> = feilds.iter().map(|(name, ast_type)| format!("{name}: {}", ast_type.name)).collect();
+			format!("`({})`", feilds.join(", "))
+		}
+	}
+}
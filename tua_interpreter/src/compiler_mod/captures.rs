@@ -0,0 +1,181 @@
+use crate::prelude::*;
+
+
+
+// the names that an anonymous function uses but doesn't define, in the order they're first used
+// this includes functions, consts, and choice variants, which are left out when the function is lowered since only variables get captured
+pub fn find_captures<'a> (args: &[&'a str], statements: &[ASTStatement<'a>]) -> Vec<&'a str> {
+	let mut finder = CaptureFinder {
+		scopes: vec!(args.to_vec()),
+		captures: vec!(),
+	};
+	finder.find_in_block(statements);
+	finder.captures
+}
+
+
+
+
+
+struct CaptureFinder<'a> {
+	scopes: Vec<Vec<&'a str>>,
+	captures: Vec<&'a str>,
+}
+
+impl<'a> CaptureFinder<'a> {
+
+
+
+	fn add_name (&mut self, name: &'a str) {
+		let is_defined = self.scopes.iter().any(|scope| scope.contains(&name));
+		if !is_defined && !self.captures.contains(&name) {
+			self.captures.push(name);
+		}
+	}
+
+	fn find_in_block (&mut self, block: &[ASTStatement<'a>]) {
+		self.scopes.push(vec!());
+		for statement in block {
+			self.find_in_statement(statement);
+		}
+		self.scopes.pop();
+	}
+
+	fn find_in_statement (&mut self, statement: &ASTStatement<'a>) {
+		match statement {
+
+			ASTStatement::Print {value} |
+			ASTStatement::Throw {value} |
+			ASTStatement::Crash {message: value} |
+			ASTStatement::Assert {condition: value} |
+			ASTStatement::Todo {message: value} => self.find_in_formula(value),
+
+			ASTStatement::VarInit {var_names, value, ..} => {
+				self.find_in_formula(value);
+				self.scopes.last_mut().unwrap().extend(var_names.iter().copied());
+			}
+
+			ASTStatement::VarAssignment {start_name, var_queries, value, ..} => {
+				self.add_name(start_name);
+				self.find_in_var_queries(var_queries);
+				self.find_in_formula(value);
+			}
+
+			ASTStatement::If {condition, true_block, false_block} => {
+				self.find_in_formula(condition);
+				self.find_in_block(true_block);
+				self.find_in_block(false_block);
+			}
+
+			ASTStatement::Switch {switch_value, cases} => {
+				self.find_in_formula(switch_value);
+				for case in cases {
+					self.scopes.push(vec!());
+					for pattern in &case.patterns {
+						self.find_in_pattern(pattern);
+					}
+					self.find_in_block(&case.block);
+					self.scopes.pop();
+				}
+			}
+
+			ASTStatement::For {var_names, iter, block} => {
+				self.find_in_formula(iter);
+				self.scopes.push(var_names.clone());
+				self.find_in_block(block);
+				self.scopes.pop();
+			}
+
+			ASTStatement::While {condition, block} => {
+				self.find_in_formula(condition);
+				self.find_in_block(block);
+			}
+
+			ASTStatement::Loop {block} => self.find_in_block(block),
+
+			ASTStatement::Break | ASTStatement::Continue => {}
+
+			ASTStatement::FunctionCall {start_name, var_queries, args} => {
+				self.add_name(start_name);
+				self.find_in_var_queries(var_queries);
+				args.iter().for_each(|arg| self.find_in_formula(arg));
+			}
+
+			ASTStatement::Return {value} => {
+				if let Some(value) = value {
+					self.find_in_formula(value);
+				}
+			}
+
+		}
+	}
+
+	fn find_in_var_queries (&mut self, var_queries: &[VarQuery<'a>]) {
+		for var_query in var_queries {
+			if let VarQuery::Index(key) = var_query {
+				self.find_in_formula(key);
+			}
+		}
+	}
+
+	// literal and range patterns can't use names, so only bindings matter here
+	fn find_in_pattern (&mut self, pattern: &ASTPattern<'a>) {
+		match pattern {
+			ASTPattern::Wildcard | ASTPattern::Literal (_) | ASTPattern::Range {..} => {}
+			ASTPattern::Binding (name) => self.scopes.last_mut().unwrap().push(name),
+			ASTPattern::Variant {payload, ..} => match payload {
+				ASTPatternPayload::None => {}
+				ASTPatternPayload::Tuple (items) => items.iter().for_each(|item| self.find_in_pattern(item)),
+				ASTPatternPayload::Feilds (feilds) => feilds.iter().for_each(|(_, feild)| self.find_in_pattern(feild)),
+			},
+			ASTPattern::Tuple (items) => items.iter().for_each(|item| self.find_in_pattern(item)),
+		}
+	}
+
+
+
+	fn find_in_formula (&mut self, formula: &ASTFormula<'a>) {
+		match formula {
+
+			ASTFormula::Name (name) => self.add_name(name),
+
+			ASTFormula::Int (_) | ASTFormula::UInt (_) | ASTFormula::Float (_) | ASTFormula::TypedNumber {..} | ASTFormula::Bool (_) | ASTFormula::String (_) | ASTFormula::Char (_) => {}
+
+			ASTFormula::Tuple (items) => items.iter().for_each(|item| self.find_in_formula(item)),
+
+			ASTFormula::Operation {left, right, ..} => {
+				self.find_in_formula(left);
+				self.find_in_formula(right);
+			}
+
+			ASTFormula::New {feilds, ..} => feilds.iter().for_each(|(_, value)| self.find_in_formula(value)),
+
+			ASTFormula::ChoiceVariant {payload, ..} => match payload {
+				ASTVariantPayload::None => {}
+				ASTVariantPayload::Tuple (values) => values.iter().for_each(|value| self.find_in_formula(value)),
+				ASTVariantPayload::Feilds (feilds) => feilds.iter().for_each(|(_, value)| self.find_in_formula(value)),
+			},
+
+			ASTFormula::Prefix {base, ..} | ASTFormula::As {base, ..} | ASTFormula::ReturnTest {base} => self.find_in_formula(base),
+
+			ASTFormula::IndexQuery {base, key} => {
+				self.find_in_formula(base);
+				self.find_in_formula(key);
+			}
+
+			ASTFormula::PropertyQuery {base, ..} => self.find_in_formula(base),
+
+			ASTFormula::FunctionCall {base, args, ..} => {
+				self.find_in_formula(base);
+				args.iter().for_each(|arg| self.find_in_formula(arg));
+			}
+
+			// an inner function's captures have to be captured by this one too, unless they're defined in it
+			ASTFormula::Function {captures, ..} => captures.iter().for_each(|name| self.add_name(name)),
+
+		}
+	}
+
+
+
+}
@@ -5,13 +5,78 @@ use std::time::Instant;
 
 
 
-pub fn compile_from_dir<P: Into<PathBuf>> (dir: P, logger: &mut Logger) -> Result<((), Vec<CompileError>), CompileError> {
-	let dir = dir.into();
+pub fn compile_from_path<P: Into<PathBuf>> (path: P, entry_points: &EntryPoints, logger: &mut Logger) -> Result<(Option<Program>, Vec<CompileError>), CompileError> {
+	let path = path.into();
 	logger.logln("Starting compilation...");
 	let mut errors: Vec<CompileError> = vec!();
 	let total_start = Instant::now();
 
-	let token_combinations = TokenCombinationNode::from_strs(&vec!(
+	// load files
+	let load_files_start = Instant::now();
+	let (raw_macro_files, raw_tua_files): (Vec<_>, Vec<_>) = load_files(path, logger)?.into_iter().partition(|(_, path)| init::is_macro_file(path));
+	let load_files_time = load_files_start.elapsed();
+
+	// preprocess
+	let preprocessing_start = Instant::now();
+	let preprocessed_tua_files = preprocess_files(raw_tua_files, &mut errors, logger);
+	let preprocessed_macro_files = preprocess_files(raw_macro_files, &mut errors, logger);
+	let preprocessing_time = preprocessing_start.elapsed();
+
+	// lex
+	let lexing_start = Instant::now();
+	let mut lexed_files = lex_files(preprocessed_tua_files, &mut errors, logger);
+	modules::load_imported_files(&mut lexed_files, &mut errors, logger);
+	let lexed_macro_files = lex_files(preprocessed_macro_files, &mut errors, logger);
+	let lexing_time = lexing_start.elapsed();
+
+	// parse
+	let parsing_start = Instant::now();
+	let mut parsed_files = parse_files(&lexed_files, &mut errors, logger);
+	let macro_files = parse_macro_files(&lexed_macro_files, &mut errors, logger);
+	let parsing_time = parsing_start.elapsed();
+
+	// attributes and macros
+	attributes::check_attributes(&parsed_files, &macro_files, &mut errors, logger);
+	macros::expand_macros(&mut parsed_files, &macro_files, &mut errors, logger);
+	constructors::add_default_constructors(&mut parsed_files, &mut errors, logger);
+
+	// modules
+	let module_graph = modules::resolve_modules(&parsed_files, &mut errors, logger);
+
+	// names
+	let resolving_start = Instant::now();
+	let symbol_table = resolver::resolve_names(&parsed_files, &module_graph, &mut errors, logger);
+	let resolving_time = resolving_start.elapsed();
+
+	// bytecode
+	let lowering_start = Instant::now();
+	let program = bytecode::lower_program(&parsed_files, &module_graph, &symbol_table, entry_points, &mut errors, logger);
+	let lowering_time = lowering_start.elapsed();
+
+	logger.logln("");
+	logger.logln("Finished compilation");
+	let total_time = total_start.elapsed();
+	logger.logln("");
+	logger.logln(format!("    loading: {load_files_time:?}"));
+	logger.logln(format!("    preprocessing: {preprocessing_time:?}"));
+	logger.logln(format!("    lexing: {lexing_time:?}"));
+	logger.logln(format!("    parsing: {parsing_time:?}"));
+	logger.logln(format!("    resolving names: {resolving_time:?}"));
+	logger.logln(format!("    lowering: {lowering_time:?}"));
+	logger.logln("");
+	logger.logln(format!("Total time: {total_time:?}"));
+	logger.logln(format!("Total minus loading: --- {:?} ---", total_time - load_files_time));
+	logger.logln("");
+
+	Ok((program, errors))
+}
+
+
+
+
+
+pub fn get_token_combinations() -> TokenCombinationNode {
+	TokenCombinationNode::from_strs(&vec!(
 		"..",
 		"==",
 		"!=",
@@ -32,20 +97,29 @@ pub fn compile_from_dir<P: Into<PathBuf>> (dir: P, logger: &mut Logger) -> Resul
 
 		"++",
 		"--",
-	));
+	))
+}
 
-	// load files
-	let load_files_start = Instant::now();
+
+
+pub fn load_files (path: PathBuf, logger: &mut Logger) -> Result<Vec<(String, PathBuf)>, CompileError> {
 	logger.logln("");
 	logger.logln("Loading files ...");
 	let mut init_logger = Logger::new("Loading Tua files");
-    let raw_tua_files = init::load_tua_files_from_dir(dir, &mut init_logger)?;
+	let mut raw_tua_files = if path.is_dir() {
+		init::load_tua_files_from_dir(path, &mut init_logger)?
+	} else {
+		vec!(init::load_tua_file(path, &mut init_logger)?)
+	};
+	raw_tua_files.push(init::load_std_macros());
 	logger.join(init_logger);
 	logger.logln("done");
-	let load_files_time = load_files_start.elapsed();
+	Ok(raw_tua_files)
+}
 
-	// preprocess
-	let preprocessing_start = Instant::now();
+
+
+pub fn preprocess_files (raw_tua_files: Vec<(String, PathBuf)>, errors: &mut Vec<CompileError>, logger: &mut Logger) -> Vec<(PreprocessedTuaFile, PathBuf)> {
 	logger.logln("");
 	logger.logln("Preprocessing files ...");
 	let mut preprocessed_tua_files = vec!();
@@ -54,74 +128,69 @@ pub fn compile_from_dir<P: Into<PathBuf>> (dir: P, logger: &mut Logger) -> Resul
 		preprocess_logger.logln(format!("Preprocessing file {path:?}..."));
 		match init::preprocess_tua_file(contents, &path, &mut preprocess_logger) {
 			Ok(next_file) => preprocessed_tua_files.push((next_file, path)),
-			Err(error) => errors.push(error),
+			Err(error) => errors.push(error.with_path(&path)),
 		}
 		preprocess_logger.logln("done");
 		logger.join(preprocess_logger);
 	}
 	logger.logln("done");
-	let preprocessing_time = preprocessing_start.elapsed();
+	preprocessed_tua_files
+}
 
-	// lex
-	let lexing_start = Instant::now();
+
+
+pub fn lex_files (preprocessed_tua_files: Vec<(PreprocessedTuaFile, PathBuf)>, errors: &mut Vec<CompileError>, logger: &mut Logger) -> Vec<(LexedTuaFile, PathBuf)> {
 	logger.logln("");
 	logger.logln("Lexing files ...");
+	let token_combinations = get_token_combinations();
 	let mut lexed_files = vec!();
-		for (contents, path) in preprocessed_tua_files {
+	for (contents, path) in preprocessed_tua_files {
 		let mut lex_logger = Logger::new("Lexing Tua file");
 		lex_logger.logln(format!("Lexing file {path:?}"));
-		let (next_file, mut next_errors) = lexer::lex_tua_file(contents, &token_combinations, &path, &mut lex_logger);
+		let (next_file, next_errors) = lexer::lex_tua_file(contents, &token_combinations, &path, &mut lex_logger);
+		errors.extend(next_errors.into_iter().map(|error| error.with_path(&path)));
 		lexed_files.push((next_file, path));
-		errors.append(&mut next_errors);
 		lex_logger.logln("done");
 		logger.join(lex_logger);
 	}
 	logger.logln("done");
-	let lexing_time = lexing_start.elapsed();
+	lexed_files
+}
 
-	// parse
-	let parsing_start = Instant::now();
+
+
+pub fn parse_files<'a> (lexed_files: &'a [(LexedTuaFile, PathBuf)], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Vec<(ParsedTuaFile<'a>, &'a PathBuf)> {
 	logger.logln("");
 	logger.logln("Parsing files ...");
 	let mut parsed_files = vec!();
 	for (contents, path) in lexed_files.iter() {
 		let mut parse_logger = Logger::new("Parsing Tua file");
 		parse_logger.logln(format!("Parsing file {path:?}"));
-		let (next_file, mut next_errors) = parser::parse_tua_file(contents, path, &mut parse_logger);
+		let (next_file, next_errors) = parser::parse_tua_file(contents, path, &mut parse_logger);
 		parsed_files.push((next_file, path));
-		errors.append(&mut next_errors);
+		errors.extend(next_errors.into_iter().map(|error| error.with_path(path)));
 		parse_logger.logln("done");
 		logger.join(parse_logger);
 	}
 	logger.logln("done");
-	let parsing_time = parsing_start.elapsed();
+	parsed_files
+}
 
+
+
+pub fn parse_macro_files<'a> (lexed_files: &'a [(LexedTuaFile, PathBuf)], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Vec<(ParsedMacroFile<'a>, &'a PathBuf)> {
 	logger.logln("");
-	logger.logln("Finished compilation (but not really)");
-	let total_time = total_start.elapsed();
-	// print
-	/**/
-	logger.logln("\n\n\nOutput:");
-	for (file, path) in &parsed_files {
-		logger.logln("\nNext file:");
-		for item in &file.definitions {
-			logger.logln(format!("{item:#?}"));
-		}
-	}
-	logger.logln("\n\n\nErrors:");
-	for error in &errors {
-		logger.logln(format!("\nNext error:\n{error}"));
+	logger.logln("Parsing macro files ...");
+	let mut parsed_files = vec!();
+	for (contents, path) in lexed_files.iter() {
+		let mut parse_logger = Logger::new("Parsing Tua macro file");
+		parse_logger.logln(format!("Parsing file {path:?}"));
+		let (next_file, next_errors) = parser::parse_tuam_file(contents, path, &mut parse_logger);
+		parsed_files.push((next_file, path));
+		errors.extend(next_errors.into_iter().map(|error| error.with_path(path)));
+		parse_logger.logln("done");
+		logger.join(parse_logger);
 	}
-	logger.logln("\n\n");
-	logger.logln(format!("    loading: {load_files_time:?}"));
-	logger.logln(format!("    preprocessing: {preprocessing_time:?}"));
-	logger.logln(format!("    lexing: {lexing_time:?}"));
-	logger.logln(format!("    parsing: {parsing_time:?}"));
-	logger.logln("");
-	logger.logln(format!("Total time: {total_time:?}"));
-	logger.logln(format!("Total minus loading: --- {:?} ---", total_time - load_files_time));
-	logger.logln("");
-	/**/
-
-	Ok(((), errors))
+	logger.logln("done");
+	parsed_files
 }
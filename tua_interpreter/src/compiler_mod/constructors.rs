@@ -0,0 +1,79 @@
+use crate::prelude::*;
+
+
+
+// adds `Type.new (...)` for every object with `#makeDefaultConstructor`, which takes the object's feilds in order (except the ones with `#notInConstructor`, which use their defaults)
+// the constructor goes right after its object so that a hand-written `Type.new` with the same args is the one reported as a duplicate
+pub fn add_default_constructors (parsed_files: &mut [(ParsedTuaFile, &PathBuf)], errors: &mut Vec<CompileError>, logger: &mut Logger) {
+	logger.logln("");
+	logger.logln("Adding default constructors ...");
+	for (file, path) in parsed_files.iter_mut() {
+		let errors_start = errors.len();
+		let mut i = 0;
+		while i < file.definitions.len() {
+			let ASTDefinition::Object {attributes, name, feilds} = &file.definitions[i] else {
+				i += 1;
+				continue;
+			};
+			if !attributes::has_attribute(attributes, "makeDefaultConstructor") {
+				i += 1;
+				continue;
+			}
+			let constructor_errors_start = errors.len();
+			let constructor = make_default_constructor(name, feilds, &file.locations, errors);
+			let properties = get_constructor_properties(&file.properties[i]);
+			file.definitions.insert(i + 1, constructor);
+			file.properties.insert(i + 1, properties);
+			// a feild that's missing its default is already reported, so the `new` in the constructor isn't checked again
+			file.poisoned.insert(i + 1, file.poisoned[i] || errors.len() > constructor_errors_start);
+			i += 2;
+		}
+		for error in &mut errors[errors_start..] {
+			error.set_path(path);
+		}
+	}
+	logger.logln("done");
+}
+
+
+
+pub fn make_default_constructor<'a> (object_name: &'a str, feilds: &[ASTObjectFeild<'a>], locations: &TokenLocations, errors: &mut Vec<CompileError>) -> ASTDefinition<'a> {
+	let object_type = ASTType {name: object_name, unnamed_type_arg: None, named_type_args: vec!(), tuple_items: vec!()};
+	let mut args = vec!();
+	let mut new_feilds = vec!();
+	for feild in feilds {
+		if attributes::has_attribute(&feild.attributes, "notInConstructor") {
+			if feild.default_value.is_none() {
+				errors.push(RawCompileError::MissingConstructorDefault {
+					location: locations.get_token(feild.name),
+					feild_name: feild.name.to_string(),
+					object_name: object_name.to_string(),
+				}.into());
+			}
+			continue;
+		}
+		args.push(ASTFunctionArg {name: feild.name, ast_type: feild.ast_type.clone(), default: None});
+		new_feilds.push((feild.name, ASTFormula::Name(feild.name)));
+	}
+	ASTDefinition::Function {
+		attributes: vec!(),
+		name: "new",
+		associated_type: Some(object_type.clone()),
+		args,
+		return_type: object_type,
+		statements: vec!(ASTStatement::Return {value: Some(ASTFormula::New {name: object_name, feilds: new_feilds})}),
+	}
+}
+
+// the constructor can be used wherever the object can, and can always create it
+pub fn get_constructor_properties<'a> (object_properties: &DefinitionProperties<'a>) -> DefinitionProperties<'a> {
+	let mut output = DefinitionProperties::default();
+	if object_properties.get_bool("is_global") {
+		output.set("is_global", MacroValue::Bool(true));
+	}
+	let locks = object_properties.get_names("lock");
+	if !locks.is_empty() {
+		output.set("unlock_keys", MacroValue::List(locks.into_iter().map(MacroValue::Name).collect()));
+	}
+	output
+}
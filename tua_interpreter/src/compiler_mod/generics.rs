@@ -0,0 +1,414 @@
+use crate::prelude::*;
+use super::bytecode::{FunctionData, ProgramData};
+
+
+
+// something a generic function does with a value of a generic type, which every type it's called with has to support
+// for example, `item_1.say_thing_1()` in `say_stuff (item_1: any_1, ...)` requires `function any_1.say_thing_1 (self)`
+#[derive(Debug)]
+pub enum GenericRequirement<'a> {
+	Function {value: GenericValue<'a>, name: &'a str, arg_count: usize},
+	Feild {value: GenericValue<'a>, name: &'a str},
+	// `for item in value`, which works on arrays and on types with `to_iter` and `next_item` functions
+	Iterable {value: GenericValue<'a>},
+}
+
+impl<'a> GenericRequirement<'a> {
+
+	pub fn get_value (&self) -> &GenericValue<'a> {
+		match self {
+			Self::Function {value, ..} | Self::Feild {value, ..} | Self::Iterable {value} => value,
+		}
+	}
+
+	fn is_same (&self, other: &Self) -> bool {
+		match (self, other) {
+			(Self::Function {value, name, ..}, Self::Function {value: other_value, name: other_name, ..}) |
+			(Self::Feild {value, name}, Self::Feild {value: other_value, name: other_name}) => value.is_same(other_value) && name == other_name,
+			(Self::Iterable {value}, Self::Iterable {value: other_value}) => value.is_same(other_value),
+			_ => false,
+		}
+	}
+
+}
+
+
+
+// a value that comes from one of the generic args, like `item` in `for item in array do ... end` when `array` is an `any`
+// its type is only known once the generic type is, so the requirements on it are checked by following the steps from that type
+#[derive(Debug, Clone)]
+pub struct GenericValue<'a> {
+	pub generic_name: &'a str,
+	pub steps: Vec<ValueStep<'a>>,
+	// what the function calls the value, for errors
+	pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueStep<'a> {
+	Call (&'a str),
+	Feild (&'a str),
+	Item,
+}
+
+impl<'a> GenericValue<'a> {
+
+	fn then (&self, step: ValueStep<'a>, description: String) -> Self {
+		let mut steps = self.steps.clone();
+		steps.push(step);
+		Self {generic_name: self.generic_name, steps, description}
+	}
+
+	fn is_same (&self, other: &Self) -> bool {
+		self.generic_name == other.generic_name && self.steps == other.steps
+	}
+
+	pub fn describe (&self) -> String {
+		if self.steps.is_empty() {
+			format!("its `{}` value", self.generic_name)
+		} else {
+			format!("`{}`, which comes from its `{}` value", self.description, self.generic_name)
+		}
+	}
+
+}
+
+
+
+pub fn get_generic_requirements<'a> (function: &FunctionData<'a>) -> Vec<GenericRequirement<'a>> {
+	let mut finder = RequirementFinder {
+		vars: function.args.iter().map(|arg| (arg.name, TrackedVar::Typed(&arg.ast_type))).collect(),
+		requirements: vec!(),
+	};
+	finder.find_in_block(function.statements);
+	finder.requirements
+}
+
+
+
+// what a type is missing when it doesn't support a requirement
+pub struct MissingRequirement<'a> {
+	pub type_name: String,
+	pub kind: &'static str,
+	pub name: &'a str,
+	pub used_on: String,
+}
+
+// checks a requirement with `bindings` being the generic types of the call being checked
+// values that can't be followed to a type are skipped here, and their errors come from lowering the function instead
+pub fn find_missing_requirement<'a> (requirement: &GenericRequirement<'a>, bindings: &[(&str, TuaType)], data: &ProgramData, location: &Token) -> Option<MissingRequirement<'a>> {
+	let value = requirement.get_value();
+	let tua_type = get_value_type(value, bindings, data, location)?;
+	match requirement {
+		GenericRequirement::Function {name, arg_count, ..} => {
+			if has_function(&tua_type, name, *arg_count, data) {return None;}
+			Some(MissingRequirement {type_name: tua_type.to_string(), kind: "function", name, used_on: value.describe()})
+		}
+		GenericRequirement::Feild {name, ..} => {
+			let has_feild = match &tua_type {
+				TuaType::Object (object_name) => data.objects.iter().any(|object| object.name == object_name && object.feilds.iter().any(|feild| feild.name == *name)),
+				_ => false,
+			};
+			if has_feild {return None;}
+			Some(MissingRequirement {type_name: tua_type.to_string(), kind: "feild", name, used_on: value.describe()})
+		}
+		GenericRequirement::Iterable {..} => {
+			if matches!(tua_type, TuaType::Array(_)) {return None;}
+			if !has_function(&tua_type, "to_iter", 0, data) {
+				return Some(MissingRequirement {type_name: tua_type.to_string(), kind: "function", name: "to_iter", used_on: value.describe()});
+			}
+			let iterator = value.then(ValueStep::Call("to_iter"), format!("{}.to_iter()", value.description));
+			let iterator_type = get_value_type(&iterator, bindings, data, location)?;
+			if has_function(&iterator_type, "next_item", 0, data) {return None;}
+			Some(MissingRequirement {type_name: iterator_type.to_string(), kind: "function", name: "next_item", used_on: iterator.describe()})
+		}
+	}
+}
+
+fn get_value_type (value: &GenericValue, bindings: &[(&str, TuaType)], data: &ProgramData, location: &Token) -> Option<TuaType> {
+	let mut tua_type = bindings.iter().find(|(name, _)| *name == value.generic_name)?.1.clone();
+	for step in &value.steps {
+		tua_type = match step {
+			ValueStep::Call (name) => get_method_return_type(&tua_type, name, data, location)?,
+			ValueStep::Feild (name) => get_feild_type(&tua_type, name, data, location)?,
+			ValueStep::Item => match tua_type {
+				TuaType::Array (item_type) => *item_type,
+				_ => {
+					let iterator_type = get_method_return_type(&tua_type, "to_iter", data, location)?;
+					let TuaType::Optional(item_type) = get_method_return_type(&iterator_type, "next_item", data, location)? else {return None;};
+					*item_type
+				}
+			},
+		};
+	}
+	Some(tua_type)
+}
+
+fn has_function (tua_type: &TuaType, name: &str, arg_count: usize, data: &ProgramData) -> bool {
+	let type_name = tua_type.get_type_name();
+	let has_user_function = data.functions.iter().any(|function| {
+		function.name == name
+			&& function.has_self
+			&& function.associated_type.map(|associated_type| associated_type.name) == Some(&*type_name)
+			&& function.args.len() > arg_count
+			&& function.args[arg_count + 1..].iter().all(|arg| arg.default.is_some())
+	});
+	let has_native_function = match bytecode::get_native_method(tua_type, name) {
+		Some((_, arg_types, _)) => arg_types.len() == arg_count,
+		None => name == "to_string" && arg_count == 0,
+	};
+	has_user_function || has_native_function
+}
+
+// generic functions return different types depending on how they're called, so they aren't followed
+fn get_method_return_type (tua_type: &TuaType, name: &str, data: &ProgramData, location: &Token) -> Option<TuaType> {
+	let type_name = tua_type.get_type_name();
+	let user_function = data.functions.iter().find(|function| {
+		function.name == name && function.has_self && function.associated_type.map(|associated_type| associated_type.name) == Some(&*type_name)
+	});
+	if let Some(function) = user_function {
+		if !function.get_generic_names().is_empty() {return None;}
+		return data.resolve_type(function.return_type, function.file_index, location).ok();
+	}
+	match bytecode::get_native_method(tua_type, name) {
+		Some((_, _, return_type)) => Some(return_type),
+		None => fns::some_if(name == "to_string", || TuaType::String),
+	}
+}
+
+fn get_feild_type (tua_type: &TuaType, name: &str, data: &ProgramData, location: &Token) -> Option<TuaType> {
+	let TuaType::Object(object_name) = tua_type else {return None;};
+	let object = data.objects.iter().find(|object| object.name == object_name)?;
+	let feild = object.feilds.iter().find(|feild| feild.name == name)?;
+	data.resolve_type(&feild.ast_type, object.file_index, location).ok()
+}
+
+
+
+
+
+// variables are tracked by name only, so a variable that shadows a generic one can add requirements that aren't really needed
+struct RequirementFinder<'a> {
+	vars: Vec<(&'a str, TrackedVar<'a>)>,
+	requirements: Vec<GenericRequirement<'a>>,
+}
+
+#[derive(Clone)]
+enum TrackedVar<'a> {
+	Typed (&'a ASTType<'a>),
+	Generic (GenericValue<'a>),
+}
+
+impl<'a> RequirementFinder<'a> {
+
+	fn get_var (&self, name: &str) -> Option<&TrackedVar<'a>> {
+		self.vars.iter().rev()
+			.find(|(var_name, _)| *var_name == name)
+			.map(|(_, tracked_var)| tracked_var)
+	}
+
+	fn get_generic_var (&self, name: &str) -> Option<GenericValue<'a>> {
+		match self.get_var(name)? {
+			TrackedVar::Typed (ast_type) => fns::some_if(ASTType::is_generic_name(ast_type.name), || GenericValue {
+				generic_name: ast_type.name,
+				steps: vec!(),
+				description: name.to_string(),
+			}),
+			TrackedVar::Generic (value) => Some(value.clone()),
+		}
+	}
+
+	// `array.to_iter()` is a value that comes from `array`, so the requirements on it can be checked too
+	fn get_generic_value (&self, formula: &'a ASTFormula<'a>) -> Option<GenericValue<'a>> {
+		match formula {
+			ASTFormula::Name (name) => self.get_generic_var(name),
+			ASTFormula::PropertyQuery {base, key} => {
+				let value = self.get_generic_value(base)?;
+				Some(value.then(ValueStep::Feild(key), format!("{}.{key}", value.description)))
+			}
+			ASTFormula::FunctionCall {base, ..} => {
+				let ASTFormula::PropertyQuery {base: receiver, key} = &**base else {return None;};
+				let value = self.get_generic_value(receiver)?;
+				Some(value.then(ValueStep::Call(key), format!("{}.{key}()", value.description)))
+			}
+			_ => None,
+		}
+	}
+
+	fn add_requirement (&mut self, new_requirement: GenericRequirement<'a>) {
+		if !self.requirements.iter().any(|requirement| requirement.is_same(&new_requirement)) {
+			self.requirements.push(new_requirement);
+		}
+	}
+
+
+
+	fn find_in_block (&mut self, block: &'a [ASTStatement<'a>]) {
+		for statement in block {
+			self.find_in_statement(statement);
+		}
+	}
+
+	fn find_in_statement (&mut self, statement: &'a ASTStatement<'a>) {
+		match statement {
+
+			ASTStatement::Print {value} |
+			ASTStatement::Throw {value} |
+			ASTStatement::Crash {message: value} |
+			ASTStatement::Assert {condition: value} |
+			ASTStatement::Todo {message: value} => self.find_in_formula(value),
+
+			ASTStatement::VarInit {var_names, var_type, value} => {
+				self.find_in_formula(value);
+				let [var_name] = &**var_names else {return;};
+				let tracked_var = match (var_type, value) {
+					(Some(var_type), _) => Some(TrackedVar::Typed(var_type)),
+					(None, ASTFormula::Name(name)) => self.get_var(name).cloned(),
+					(None, value) => self.get_generic_value(value).map(|value| TrackedVar::Generic(GenericValue {description: var_name.to_string(), ..value})),
+				};
+				if let Some(tracked_var) = tracked_var {
+					self.vars.push((var_name, tracked_var));
+				}
+			}
+
+			ASTStatement::VarAssignment {start_name, var_queries, value, ..} => {
+				self.find_in_var_queries(start_name, var_queries, false, 0);
+				self.find_in_formula(value);
+			}
+
+			ASTStatement::If {condition, true_block, false_block} => {
+				self.find_in_formula(condition);
+				self.find_in_block(true_block);
+				self.find_in_block(false_block);
+			}
+
+			ASTStatement::Switch {switch_value, cases} => {
+				self.find_in_formula(switch_value);
+				for case in cases {
+					self.find_in_block(&case.block);
+				}
+			}
+
+			ASTStatement::For {var_names, iter, block} => {
+				self.find_in_formula(iter);
+				if let [var_name] = &**var_names {
+					// the items of a `any_item[]` are `any_item`s
+					let item_type = match iter {
+						ASTFormula::Name (array_name) => match self.get_var(array_name) {
+							Some(TrackedVar::Typed(ast_type)) if ast_type.name == "Array" => ast_type.unnamed_type_arg.as_deref(),
+							_ => None,
+						},
+						_ => None,
+					};
+					// and the items of other generic values come from their `to_iter` and `next_item` functions
+					if let Some(item_type) = item_type {
+						self.vars.push((var_name, TrackedVar::Typed(item_type)));
+					} else if let Some(value) = self.get_generic_value(iter) {
+						let item = value.then(ValueStep::Item, var_name.to_string());
+						self.add_requirement(GenericRequirement::Iterable {value});
+						self.vars.push((var_name, TrackedVar::Generic(item)));
+					}
+				}
+				self.find_in_block(block);
+			}
+
+			ASTStatement::While {condition, block} => {
+				self.find_in_formula(condition);
+				self.find_in_block(block);
+			}
+
+			ASTStatement::Loop {block} => self.find_in_block(block),
+
+			ASTStatement::Break | ASTStatement::Continue => {}
+
+			ASTStatement::FunctionCall {start_name, var_queries, args} => {
+				self.find_in_var_queries(start_name, var_queries, true, args.len());
+				args.iter().for_each(|arg| self.find_in_formula(arg));
+			}
+
+			ASTStatement::Return {value} => {
+				if let Some(value) = value {
+					self.find_in_formula(value);
+				}
+			}
+
+		}
+	}
+
+	// `is_call` is for statements like `item.say_name()`, where the last query is the function's name
+	fn find_in_var_queries (&mut self, start_name: &'a str, var_queries: &'a [VarQuery<'a>], is_call: bool, arg_count: usize) {
+		let mut value = self.get_generic_var(start_name);
+		for (i, var_query) in var_queries.iter().enumerate() {
+			match var_query {
+				VarQuery::Feild (name) => {
+					let Some(current_value) = value.take() else {continue;};
+					if is_call && i == var_queries.len() - 1 {
+						self.add_requirement(GenericRequirement::Function {value: current_value, name, arg_count});
+					} else {
+						value = Some(current_value.then(ValueStep::Feild(name), format!("{}.{name}", current_value.description)));
+						self.add_requirement(GenericRequirement::Feild {value: current_value, name});
+					}
+				}
+				VarQuery::Index (key) => {
+					value = None;
+					self.find_in_formula(key);
+				}
+			}
+		}
+	}
+
+
+
+	fn find_in_formula (&mut self, formula: &'a ASTFormula<'a>) {
+		match formula {
+
+			ASTFormula::Name (_) | ASTFormula::Int (_) | ASTFormula::UInt (_) | ASTFormula::Float (_) | ASTFormula::TypedNumber {..} | ASTFormula::Bool (_) | ASTFormula::String (_) | ASTFormula::Char (_) => {}
+
+			ASTFormula::Tuple (items) => items.iter().for_each(|item| self.find_in_formula(item)),
+
+			ASTFormula::Operation {left, right, ..} => {
+				self.find_in_formula(left);
+				self.find_in_formula(right);
+			}
+
+			ASTFormula::New {feilds, ..} => feilds.iter().for_each(|(_, value)| self.find_in_formula(value)),
+
+			ASTFormula::ChoiceVariant {payload, ..} => match payload {
+				ASTVariantPayload::None => {}
+				ASTVariantPayload::Tuple (values) => values.iter().for_each(|value| self.find_in_formula(value)),
+				ASTVariantPayload::Feilds (feilds) => feilds.iter().for_each(|(_, value)| self.find_in_formula(value)),
+			},
+
+			ASTFormula::Prefix {base, ..} | ASTFormula::As {base, ..} | ASTFormula::ReturnTest {base} => self.find_in_formula(base),
+
+			ASTFormula::IndexQuery {base, key} => {
+				self.find_in_formula(base);
+				self.find_in_formula(key);
+			}
+
+			ASTFormula::PropertyQuery {base, key} => {
+				self.find_in_formula(base);
+				if let Some(value) = self.get_generic_value(base) {
+					self.add_requirement(GenericRequirement::Feild {value, name: key});
+				}
+			}
+
+			ASTFormula::FunctionCall {base, args, ..} => {
+				match &**base {
+					ASTFormula::PropertyQuery {base: receiver, key} => {
+						self.find_in_formula(receiver);
+						if let Some(value) = self.get_generic_value(receiver) {
+							self.add_requirement(GenericRequirement::Function {value, name: key, arg_count: args.len()});
+						}
+					}
+					base => self.find_in_formula(base),
+				}
+				args.iter().for_each(|arg| self.find_in_formula(arg));
+			}
+
+			ASTFormula::Function {statements, ..} => self.find_in_block(statements),
+
+		}
+	}
+
+}
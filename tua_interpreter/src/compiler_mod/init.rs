@@ -4,6 +4,18 @@ use crate::prelude::*;
 
 
 
+pub const STD_DIR: &str = "<std>";
+
+pub const BUNDLED_STD_FILES: &[(&str, &str)] = &[
+	("input.tua", include_str!("../../std/input.tua")),
+	("macros.tuam", include_str!("../../std/macros.tuam")),
+];
+
+// the macros that every project can use, like `#global`
+pub const STD_MACROS_FILE: &str = "macros.tuam";
+
+
+
 
 
 pub fn load_tua_files_from_dir (dir: PathBuf, logger: &mut Logger) -> Result<Vec<(String, PathBuf)>, CompileError> {
@@ -14,7 +26,7 @@ pub fn load_tua_files_from_dir (dir: PathBuf, logger: &mut Logger) -> Result<Vec
 		let file_path = all_file_paths.pop().unwrap();
 		logger.logln(format!("Loading file {file_path:?}..."));
 		let file_extension = file_path.extension().map(OsStr::to_str);
-		if file_extension != Some(Some("tua")) {println!("{:?}", file_path); continue;}
+		if file_extension != Some(Some("tua")) && file_extension != Some(Some("tuam")) {continue;}
 		let file_contents = fs::read_to_string(&file_path).map_err(|e: IoError| -> CompileError {e.into()})?;
 		logger.logln("done");
 		output.push((file_contents, file_path));
@@ -24,14 +36,49 @@ pub fn load_tua_files_from_dir (dir: PathBuf, logger: &mut Logger) -> Result<Vec
 
 
 
+pub fn load_tua_file (file_path: PathBuf, logger: &mut Logger) -> Result<(String, PathBuf), CompileError> {
+	logger.logln(format!("Loading file {file_path:?}..."));
+	let file_contents = fs::read_to_string(&file_path).map_err(|e: IoError| -> CompileError {e.into()})?;
+	logger.logln("done");
+	Ok((file_contents, file_path))
+}
+
+
+
+pub fn load_std_macros () -> (String, PathBuf) {
+	let path = PathBuf::from(STD_DIR).join(STD_MACROS_FILE);
+	let contents = get_bundled_std_file(&path).unwrap();
+	(contents.to_string(), path)
+}
+
+
+
+pub fn is_macro_file (path: &Path) -> bool {
+	path.extension().and_then(OsStr::to_str) == Some("tuam")
+}
+
+
+
+pub fn is_std_path (path: &Path) -> bool {
+	path.starts_with(STD_DIR)
+}
+
+
+
+pub fn get_bundled_std_file (path: &Path) -> Option<&'static str> {
+	let relative_path = path.strip_prefix(STD_DIR).ok()?;
+	BUNDLED_STD_FILES.iter()
+		.find(|(file_path, _)| Path::new(file_path) == relative_path)
+		.map(|(_, contents)| *contents)
+}
+
+
+
 
 
 pub fn preprocess_tua_file (raw_tua_file: String, path: &Path, logger: &mut Logger) -> Result<PreprocessedTuaFile, CompileError> {
 	let raw_tua_file = seperate_file_chars(raw_tua_file)?;
-	let contents = match remove_comments(raw_tua_file.contents) {
-		Ok(contents) => contents,
-		Err(error) => return Ok(PreprocessedTuaFile::default()),
-	};
+	let contents = remove_comments(raw_tua_file.contents)?;
 	Ok(PreprocessedTuaFile {contents})
 }
 
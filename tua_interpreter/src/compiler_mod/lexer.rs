@@ -54,8 +54,22 @@ pub fn tokenize_code (contents: &[CharData], token_combinations: &TokenCombinati
 				index = end_char_index + 1;
 			}
 
-			// names and numbers
-			'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => {
+			// tuple items (`pair.0`), which would otherwise be lexed as part of a float when they're chained (`pair.0.1`)
+			'0'..='9' if index >= 1 && contents[index - 1].char == '.' && (index < 2 || contents[index - 2].char != '.') => {
+				let word_end = fns::get_word_end(contents, index);
+				output.push(BasicToken::name_from_chars(&contents[index..=word_end]));
+				index = word_end + 1;
+			}
+
+			// numbers
+			'0'..='9' => {
+				let (number, end_index) = tokenize_number(contents, index)?;
+				output.push(number);
+				index = end_index;
+			}
+
+			// names
+			'a'..='z' | 'A'..='Z' | '_' => {
 				let word_end = fns::get_word_end(contents, index);
 				output.push(BasicToken::name_from_chars(&contents[index..=word_end]));
 				index = word_end + 1;
@@ -90,6 +104,181 @@ pub fn tokenize_code (contents: &[CharData], token_combinations: &TokenCombinati
 
 
 
+// decimal (`1_000`, `3.14`, `1.5e-3`), hex (`0xff`), binary (`0b1010`), and octal (`0o17`) numbers, which can end with a type (`255_uint_8`)
+// a `.` is only part of a number when a digit comes after it, so `5.to_string()` and `1..5` still work
+fn tokenize_number (contents: &[CharData], start: usize) -> Result<(BasicToken, usize), CompileError> {
+	let start_char = &contents[start];
+	let char_at = |index: usize| contents.get(index).map(|char_data| char_data.char);
+	let is_decimal_digit = |index: usize| char_at(index).map_or(false, |char| char.is_ascii_digit());
+	let mut index = start;
+
+	// radix
+	let radix = match (char_at(index), char_at(index + 1)) {
+		(Some('0'), Some('x')) => 16,
+		(Some('0'), Some('b')) => 2,
+		(Some('0'), Some('o')) => 8,
+		_ => 10,
+	};
+	if radix != 10 {index += 2;}
+
+	// digits, fraction, and exponent
+	let mut digits = String::new();
+	let mut is_float = false;
+	scan_digits(contents, &mut index, radix, &mut digits)?;
+	if radix != 10 && is_decimal_digit(index) {
+		let location = Token {token: RawToken::Name(char_at(index).unwrap().to_string()), line_num: contents[index].line_num, char_num: contents[index].char_num};
+		let radix_name = if radix == 2 {"a binary"} else {"an octal"};
+		return Err(RawCompileError::InvalidNumberLiteral {location, details: format!("`{}` isn't {radix_name} digit", char_at(index).unwrap())}.into());
+	}
+	if radix == 10 && char_at(index) == Some('.') && is_decimal_digit(index + 1) {
+		is_float = true;
+		digits.push('.');
+		index += 1;
+		scan_digits(contents, &mut index, radix, &mut digits)?;
+	}
+	if radix == 10 && matches!(char_at(index), Some('e' | 'E')) {
+		let has_sign = matches!(char_at(index + 1), Some('+' | '-'));
+		let digits_start = index + if has_sign {2} else {1};
+		if is_decimal_digit(digits_start) {
+			is_float = true;
+			digits.push('e');
+			if has_sign {digits.push(char_at(index + 1).unwrap());}
+			index = digits_start;
+			scan_digits(contents, &mut index, radix, &mut digits)?;
+		}
+	}
+
+	// hex digits can be letters, so a hex number's type has to come after a `_` (`0xff_uint_8`)
+	if radix == 16 && char_at(index).is_some_and(|char| char.is_ascii_alphabetic()) {
+		let location = Token {token: RawToken::Name(char_at(index).unwrap().to_string()), line_num: contents[index].line_num, char_num: contents[index].char_num};
+		return Err(RawCompileError::InvalidNumberLiteral {location, details: format!("`{}` isn't a hex digit, and a hex number's type has to come after a `_`", char_at(index).unwrap())}.into());
+	}
+
+	// type suffix
+	let suffix_start = match (char_at(index), char_at(index + 1)) {
+		(Some('_'), Some(next_char)) if next_char.is_ascii_alphabetic() => Some(index + 1),
+		(Some(char), _) if char.is_ascii_alphabetic() => Some(index),
+		_ => None,
+	};
+	let mut suffix = None;
+	if let Some(suffix_start) = suffix_start {
+		let suffix_end = fns::get_word_end(contents, suffix_start);
+		suffix = Some(contents[suffix_start..=suffix_end].iter().map(|char_data| char_data.char).collect::<String>());
+		index = suffix_end + 1;
+	}
+
+	let source = contents[start..index].iter().map(|char_data| char_data.char).collect::<String>();
+	let location = Token {token: RawToken::Name(source.clone()), line_num: start_char.line_num, char_num: start_char.char_num};
+	if digits.is_empty() {
+		return Err(RawCompileError::InvalidNumberLiteral {location, details: format!("expected digits after `{source}`")}.into());
+	}
+	let tua_type = match suffix.as_deref().map(get_number_type) {
+		None => None,
+		Some(Some(tua_type)) => Some(tua_type),
+		Some(None) => return Err(RawCompileError::InvalidNumberLiteral {location, details: format!("`{}` isn't a number type", suffix.unwrap())}.into()),
+	};
+
+	// value
+	let value = if is_float {
+		if let Some(tua_type) = tua_type.as_ref().filter(|tua_type| !tua_type.is_float()) {
+			return Err(RawCompileError::InvalidNumberLiteral {location, details: format!("`{source}` has a fraction or exponent, so its type can't be `{tua_type}`")}.into());
+		}
+		RawToken::Float(digits.parse().unwrap())
+	} else {
+		// signed types can hold one more below zero than above it, so that's left for when the literal is lowered
+		let max_value = match &tua_type {
+			Some(tua_type) => get_max_literal(tua_type),
+			None => u64::MAX as u128,
+		};
+		let value = u128::from_str_radix(&digits, radix).ok().filter(|value| *value <= max_value);
+		let Some(value) = value else {
+			let tua_type = tua_type.unwrap_or(TuaType::UInt64);
+			return Err(RawCompileError::LiteralOutOfRange {location, value: source, tua_type: tua_type.to_string()}.into());
+		};
+		match i64::try_from(value) {
+			Ok(value) => RawToken::Int(value),
+			Err(_) => RawToken::UInt(value as u64),
+		}
+	};
+	let token = match tua_type {
+		Some(tua_type) => RawToken::TypedNumber(box value, tua_type),
+		None => value,
+	};
+
+	Ok((
+		BasicToken {
+			token: RawBasicToken::Number(token),
+			line_num: start_char.line_num,
+			char_num: start_char.char_num,
+		},
+		index,
+	))
+}
+
+// `_` seperates digits when another digit comes after it and starts the type suffix when a letter does, anywhere else it's a mistake
+fn scan_digits (contents: &[CharData], index: &mut usize, radix: u32, digits: &mut String) -> Result<(), CompileError> {
+	let is_digit = |index: usize| contents.get(index).map_or(false, |char_data| char_data.char.is_digit(radix));
+	while *index < contents.len() {
+		let current_char = contents[*index].char;
+		// the type ends the digits even when it starts with a hex digit, like `0x10_float_64`
+		if current_char == '_' && *index + 1 < contents.len() {
+			let word_end = fns::get_word_end(contents, *index + 1);
+			let word = contents[*index + 1..=word_end].iter().map(|char_data| char_data.char).collect::<String>();
+			if get_number_type(&word).is_some() {return Ok(());}
+		}
+		if current_char == '_' && !is_digit(*index + 1) {
+			let details = match contents.get(*index + 1).map(|char_data| char_data.char) {
+				Some(next_char) if next_char.is_ascii_alphabetic() => return Ok(()),
+				Some('_') => "there can only be one `_` between digits",
+				_ => "`_` has to go between two digits or before the type",
+			};
+			let location = Token {token: RawToken::Name(String::from("_")), line_num: contents[*index].line_num, char_num: contents[*index].char_num};
+			return Err(RawCompileError::InvalidNumberLiteral {location, details: details.to_string()}.into());
+		}
+		if is_digit(*index) {
+			digits.push(current_char);
+		} else if current_char != '_' {
+			return Ok(());
+		}
+		*index += 1;
+	}
+	Ok(())
+}
+
+pub fn get_number_type (name: &str) -> Option<TuaType> {
+	Some(match name {
+		"int_8" => TuaType::Int8,
+		"int_16" => TuaType::Int16,
+		"int_32" => TuaType::Int32,
+		"int" | "int_64" => TuaType::Int64,
+		"uint_8" => TuaType::UInt8,
+		"uint_16" => TuaType::UInt16,
+		"uint_32" => TuaType::UInt32,
+		"uint" | "uint_64" => TuaType::UInt64,
+		"float" | "float_32" => TuaType::Float32,
+		"float_64" => TuaType::Float64,
+		_ => return None,
+	})
+}
+
+// the largest number that can be written before the type's `-` is applied
+fn get_max_literal (tua_type: &TuaType) -> u128 {
+	match tua_type {
+		TuaType::Int8   => i8::MAX as u128 + 1,
+		TuaType::Int16  => i16::MAX as u128 + 1,
+		TuaType::Int32  => i32::MAX as u128 + 1,
+		TuaType::Int64  => i64::MAX as u128 + 1,
+		TuaType::UInt8  => u8::MAX as u128,
+		TuaType::UInt16 => u16::MAX as u128,
+		TuaType::UInt32 => u32::MAX as u128,
+		_ => u64::MAX as u128,
+	}
+}
+
+
+
+
+
 fn tokenize_formatted_string (contents: &[CharData], mut index: usize, token_combinations: &TokenCombinationNode) -> Result<(BasicToken, usize), CompileError> {
     let start_token = &contents[index];
     index += 2;
@@ -167,6 +356,7 @@ pub fn basic_token_data_to_token_data (basic_token: BasicToken) -> Token {
         RawBasicToken::FormattedString {start, items} => basic_formatted_string_to_formatted_string(start, items),
         RawBasicToken::Char            (content)   => RawToken::Char(content),
         RawBasicToken::Special         (content) => token_from_special_str(&content),
+        RawBasicToken::Number          (token) => token,
     };
     Token {
         token,
@@ -178,22 +368,13 @@ pub fn basic_token_data_to_token_data (basic_token: BasicToken) -> Token {
 
 
 pub fn token_from_name_string (input: String) -> RawToken {
-    if let Ok(value) = input.parse::<i64>() {
-        return RawToken::Int(value);
-    }
-    if let Ok(value) = input.parse::<u64>() {
-        return RawToken::UInt(value);
-    }
-    if let Ok(value) = input.parse::<f64>() {
-        return RawToken::Float(value);
-    }
     match &*input {
         "true" => RawToken::Bool(true),
         "false" => RawToken::Bool(false),
         "and" => RawToken::Operator(Operator::And),
         "or" => RawToken::Operator(Operator::Or),
         "xor" => RawToken::Operator(Operator::Xor),
-        "not" => RawToken::Operator(Operator::Not),
+        "not" => RawToken::PrefixOperator(PrefixOperator::Not),
         "as" => RawToken::Operator(Operator::As),
         _ => RawToken::Name(input),
     }
@@ -264,3 +445,75 @@ pub fn token_from_special_str (input: &str) -> RawToken {
         _ => panic!("unknown special token {input}"),
     }
 }
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+	use crate::prelude::*;
+
+	// the tokens of a single line, or the errors from lexing it
+	fn lex_line (source: &str) -> Result<Vec<RawToken>, Vec<String>> {
+		let path = Path::new("test.tua");
+		let mut logger = Logger::new("test");
+		let file = init::preprocess_tua_file(source.to_string(), path, &mut logger).unwrap();
+		let (file, errors) = lexer::lex_tua_file(file, &compiler::get_token_combinations(), path, &mut logger);
+		if !errors.is_empty() {
+			return Err(errors.iter().map(|error| error.raw_error().to_string()).collect());
+		}
+		Ok(file.contents.into_iter().map(|token| token.token).collect())
+	}
+
+
+
+	#[test]
+	fn radix_prefixes () {
+		assert_eq!(lex_line("0xff 0b1010 0o17"), Ok(vec!(RawToken::Int(255), RawToken::Int(10), RawToken::Int(15))));
+		assert_eq!(lex_line("0b102"), Err(vec!(String::from("invalid number literal: `2` isn't a binary digit"))));
+	}
+
+	#[test]
+	fn digit_seperators () {
+		assert_eq!(lex_line("1_000_000 0xff_ff"), Ok(vec!(RawToken::Int(1_000_000), RawToken::Int(0xffff))));
+		assert_eq!(lex_line("1_"), Err(vec!(String::from("invalid number literal: `_` has to go between two digits or before the type"))));
+		assert_eq!(lex_line("1__0"), Err(vec!(String::from("invalid number literal: there can only be one `_` between digits"))));
+		assert_eq!(lex_line("1_.5"), Err(vec!(String::from("invalid number literal: `_` has to go between two digits or before the type"))));
+	}
+
+	#[test]
+	fn fractions_and_exponents () {
+		assert_eq!(lex_line("3.25 1.5e-3 2E3"), Ok(vec!(RawToken::Float(3.25), RawToken::Float(1.5e-3), RawToken::Float(2e3))));
+		// `.` is only part of a number when a digit comes after it
+		assert_eq!(lex_line("1..5"), Ok(vec!(RawToken::Int(1), RawToken::Operator(Operator::Concat), RawToken::Int(5))));
+	}
+
+	#[test]
+	fn type_suffixes () {
+		assert_eq!(lex_line("255_uint_8 7int"), Ok(vec!(
+			RawToken::TypedNumber(box RawToken::Int(255), TuaType::UInt8),
+			RawToken::TypedNumber(box RawToken::Int(7), TuaType::Int64),
+		)));
+		assert_eq!(lex_line("5_number"), Err(vec!(String::from("invalid number literal: `number` isn't a number type"))));
+		assert_eq!(lex_line("1.5_int"), Err(vec!(String::from("invalid number literal: `1.5_int` has a fraction or exponent, so its type can't be `int`"))));
+	}
+
+	#[test]
+	fn hex_type_suffixes () {
+		assert_eq!(lex_line("0xff_uint_8 0x10_float_64 0xf_f"), Ok(vec!(
+			RawToken::TypedNumber(box RawToken::Int(255), TuaType::UInt8),
+			RawToken::TypedNumber(box RawToken::Int(16), TuaType::Float64),
+			RawToken::Int(0xff),
+		)));
+		assert_eq!(lex_line("0x10float_64"), Err(vec!(String::from("invalid number literal: `l` isn't a hex digit, and a hex number's type has to come after a `_`"))));
+	}
+
+	#[test]
+	fn out_of_range_literals () {
+		assert_eq!(lex_line("18446744073709551615"), Ok(vec!(RawToken::UInt(u64::MAX))));
+		assert_eq!(lex_line("18446744073709551616"), Err(vec!(String::from("the literal `18446744073709551616` does not fit in the type `uint`"))));
+		assert_eq!(lex_line("256_uint_8"), Err(vec!(String::from("the literal `256_uint_8` does not fit in the type `uint_8`"))));
+	}
+
+}
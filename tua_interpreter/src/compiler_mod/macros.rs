@@ -0,0 +1,189 @@
+use crate::prelude::*;
+
+
+
+// runs the code of every macro that's used on a definition, which sets the definition's properties (like `lock` for `#set_permission(...)`)
+// uses that don't fit the macro (wrong arg count or definition type) are reported by the attribute check and skipped here
+pub fn expand_macros<'a> (parsed_files: &mut [(ParsedTuaFile<'a>, &'a PathBuf)], macro_files: &'a [(ParsedMacroFile<'a>, &'a PathBuf)], errors: &mut Vec<CompileError>, logger: &mut Logger) {
+	logger.logln("");
+	logger.logln("Expanding macros ...");
+	for (file, path) in parsed_files.iter_mut() {
+		let errors_start = errors.len();
+		let mut all_properties = vec!();
+		for definition in &file.definitions {
+			let mut properties = DefinitionProperties::default();
+			for attribute in definition.attributes() {
+				let Some(ast_macro) = get_macro(macro_files, attribute.name) else {continue;};
+				if !is_allowed_target(ast_macro, definition.type_name()) || attribute.args.len() != ast_macro.args.len() {continue;}
+				if let Err(details) = run_macro(ast_macro, attribute, &mut properties) {
+					errors.push(RawCompileError::MacroFailed {
+						location: file.locations.get_token(attribute.name),
+						name: ast_macro.name.to_string(),
+						details,
+					}.into());
+				}
+			}
+			all_properties.push(properties);
+		}
+		file.properties = all_properties;
+		for error in &mut errors[errors_start..] {
+			error.set_path(path);
+		}
+	}
+	logger.logln("done");
+}
+
+
+
+pub fn get_macro<'a> (macro_files: &'a [(ParsedMacroFile<'a>, &'a PathBuf)], name: &str) -> Option<&'a ASTMacro<'a>> {
+	macro_files.iter()
+		.flat_map(|(file, _)| file.macros.iter())
+		.find(|ast_macro| ast_macro.name == name)
+}
+
+// macros can only be used on definitions, not on feilds
+pub fn is_allowed_target (ast_macro: &ASTMacro, target: &str) -> bool {
+	match &ast_macro.allowed_definition_types {
+		Some(allowed_types) => allowed_types.contains(&target),
+		None => target != "feild",
+	}
+}
+
+
+
+fn run_macro<'a> (ast_macro: &'a ASTMacro<'a>, attribute: &ASTAttribute<'a>, input: &mut DefinitionProperties<'a>) -> Result<(), String> {
+	if ast_macro.set_is_this {
+		input.set(&format!("is_{}", ast_macro.name), MacroValue::Bool(true));
+	}
+	let mut args = vec!();
+	for (name, arg) in ast_macro.args.iter().zip(&attribute.args) {
+		let value = match arg {
+			ASTFormula::Name (name) => MacroValue::Name(name),
+			ASTFormula::Int (value) => MacroValue::Int(*value),
+			ASTFormula::UInt (value) => MacroValue::Int(*value as i64),
+			ASTFormula::Bool (value) => MacroValue::Bool(*value),
+			ASTFormula::String (value) => MacroValue::String(value),
+			_ => return Err(format!("the arg `{name}` has to be a name or a literal")),
+		};
+		args.push((*name, value));
+	}
+	let mut runner = MacroRunner {input, args};
+	runner.run_block(&ast_macro.code)
+}
+
+
+
+
+
+// macro code is a small subset of Tua that only works with `args` and `input`
+struct MacroRunner<'b, 'a> {
+	input: &'b mut DefinitionProperties<'a>,
+	args: Vec<(&'a str, MacroValue<'a>)>,
+}
+
+impl<'b, 'a> MacroRunner<'b, 'a> {
+
+	fn run_block (&mut self, block: &'a [ASTStatement<'a>]) -> Result<(), String> {
+		for statement in block {
+			self.run_statement(statement)?;
+		}
+		Ok(())
+	}
+
+	fn run_statement (&mut self, statement: &'a ASTStatement<'a>) -> Result<(), String> {
+		match statement {
+
+			// `input.name = value`
+			ASTStatement::VarAssignment {start_name, var_queries, operator: AssignmentOperator::Equals, value} => {
+				let name = get_input_property(start_name, var_queries)?;
+				let value = self.evaluate(value)?;
+				self.input.set(name, value);
+			}
+
+			// `input.name.add(value)`
+			ASTStatement::FunctionCall {start_name, var_queries, args} => {
+				let [property_queries @ .., VarQuery::Feild(function_name)] = &**var_queries else {
+					return Err(String::from("only `add` can be called in macro code"));
+				};
+				if *function_name != "add" || args.len() != 1 {
+					return Err(String::from("only `add` can be called in macro code"));
+				}
+				let name = get_input_property(start_name, property_queries)?;
+				let value = self.evaluate(&args[0])?;
+				match self.input.get_mut(name) {
+					Some(MacroValue::List(items)) => items.push(value),
+					Some(other) => return Err(format!("`input.{name}` is `{other}`, which isn't a list")),
+					None => self.input.set(name, MacroValue::List(vec!(value))),
+				}
+			}
+
+			ASTStatement::If {condition, true_block, false_block} => {
+				match self.evaluate(condition)? {
+					MacroValue::Bool(true) => self.run_block(true_block)?,
+					MacroValue::Bool(false) => self.run_block(false_block)?,
+					other => return Err(format!("expected a bool, found `{other}`")),
+				}
+			}
+
+			// lets macros reject definitions they can't be used on
+			ASTStatement::Crash {message} => {
+				return Err(match self.evaluate(message)? {
+					MacroValue::String(message) => message.to_string(),
+					other => other.to_string(),
+				});
+			}
+
+			_ => return Err(String::from("only `input.x = value`, `input.x.add(value)`, `if`, and `crash` can be used in macro code")),
+		}
+		Ok(())
+	}
+
+	fn evaluate (&self, formula: &'a ASTFormula<'a>) -> Result<MacroValue<'a>, String> {
+		Ok(match formula {
+
+			ASTFormula::Int (value) => MacroValue::Int(*value),
+			ASTFormula::UInt (value) => MacroValue::Int(*value as i64),
+			ASTFormula::Bool (value) => MacroValue::Bool(*value),
+			ASTFormula::String (value) => MacroValue::String(value),
+
+			ASTFormula::PropertyQuery {base, key} => match &**base {
+				// args can be found by name (`args.name`) or by position (`args.0`)
+				ASTFormula::Name ("args") => {
+					let arg = match key.parse::<usize>() {
+						Ok(position) => self.args.get(position),
+						Err(_) => self.args.iter().find(|(name, _)| name == key),
+					};
+					let Some((_, value)) = arg else {
+						return Err(format!("there's no arg `{key}`"));
+					};
+					value.clone()
+				}
+				ASTFormula::Name ("input") => {
+					let Some(value) = self.input.get(key) else {
+						return Err(format!("`input.{key}` isn't set"));
+					};
+					value.clone()
+				}
+				_ => return Err(String::from("only `args` and `input` can be used in macro code")),
+			},
+
+			ASTFormula::Operation {operator: Operator::Equal, left, right} => MacroValue::Bool(self.evaluate(left)? == self.evaluate(right)?),
+			ASTFormula::Operation {operator: Operator::NotEqual, left, right} => MacroValue::Bool(self.evaluate(left)? != self.evaluate(right)?),
+
+			ASTFormula::Prefix {operator: PrefixOperator::Not, base} => match self.evaluate(base)? {
+				MacroValue::Bool(value) => MacroValue::Bool(!value),
+				other => return Err(format!("expected a bool, found `{other}`")),
+			},
+
+			_ => return Err(String::from("only literals, `args.x`, `args.0`, `input.x`, `==`, `!=`, and `not` can be used in macro code")),
+		})
+	}
+
+}
+
+fn get_input_property<'a> (start_name: &str, var_queries: &[VarQuery<'a>]) -> Result<&'a str, String> {
+	match (start_name, var_queries) {
+		("input", [VarQuery::Feild(name)]) => Ok(name),
+		_ => Err(String::from("macro code can only change the properties of `input`, like `input.lock`")),
+	}
+}
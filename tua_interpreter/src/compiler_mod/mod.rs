@@ -2,3 +2,12 @@ pub mod compiler;
 pub mod init;
 pub mod lexer;
 pub mod parser;
+pub mod captures;
+pub mod modules;
+pub mod attributes;
+pub mod macros;
+pub mod constructors;
+pub mod resolver;
+pub mod bytecode;
+pub mod patterns;
+pub mod generics;
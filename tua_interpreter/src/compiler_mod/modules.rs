@@ -0,0 +1,200 @@
+use crate::prelude::*;
+
+
+
+// `use std.input` -> "<std>/input.tua"
+// `use this.parsing` -> "parsing.tua" or "parsing_mod/parsing.tua" next to the file that has the `use`
+// `use this.cli.parsing` -> "cli_mod/parsing.tua" or "cli_mod/parsing_mod/parsing.tua"
+pub fn resolve_use_path (use_path: &[&str], importer_path: &Path) -> Option<PathBuf> {
+	let (root, module_path) = use_path.split_first()?;
+	let (module_name, module_dirs) = module_path.split_last()?;
+	let mut module_dir = match *root {
+		"std" => PathBuf::from(init::STD_DIR),
+		"this" => importer_path.parent()?.to_path_buf(),
+		_ => return None,
+	};
+	for dir_name in module_dirs {
+		module_dir.push(format!("{dir_name}_mod"));
+	}
+	let candidates = [
+		module_dir.clone().push_inline(format!("{module_name}.tua")),
+		module_dir.push_inline(format!("{module_name}_mod")).push_inline(format!("{module_name}.tua")),
+	];
+	candidates.into_iter().find(|path| module_exists(path))
+}
+
+
+
+pub fn module_exists (path: &Path) -> bool {
+	if init::is_std_path(path) {
+		init::get_bundled_std_file(path).is_some()
+	} else {
+		path.is_file()
+	}
+}
+
+
+
+pub fn get_use_paths<'a, 'b> (file: &'b ParsedTuaFile<'a>) -> Vec<&'b Vec<&'a str>> {
+	file.definitions.iter()
+		.filter_map(|definition| match definition {
+			ASTDefinition::Use {paths} => Some(paths),
+			_ => None,
+		})
+		.flatten()
+		.collect()
+}
+
+
+
+
+
+// files can be imported from outside the project (std files or imports of a single-file project), so they have to be found before the main parsing pass
+pub fn load_imported_files (lexed_files: &mut Vec<(LexedTuaFile, PathBuf)>, errors: &mut Vec<CompileError>, logger: &mut Logger) {
+	logger.logln("");
+	logger.logln("Loading imported files ...");
+	let mut i = 0;
+	while i < lexed_files.len() {
+
+		// find imports
+		let module_paths = {
+			let (file, path) = &lexed_files[i];
+			let (parsed_file, _) = parser::parse_tua_file(file, path, &mut Logger::new("Finding imports"));
+			get_use_paths(&parsed_file).into_iter()
+				.filter_map(|use_path| resolve_use_path(use_path, path))
+				.collect::<Vec<PathBuf>>()
+		};
+
+		// load new files
+		for module_path in module_paths {
+			if lexed_files.iter().any(|(_, path)| fns::is_same_file(path, &module_path)) {continue;}
+			let raw_tua_file = match init::get_bundled_std_file(&module_path) {
+				Some(contents) => (contents.to_string(), module_path),
+				None => match init::load_tua_file(module_path, logger) {
+					Ok(v) => v,
+					Err(error) => {
+						errors.push(error);
+						continue;
+					}
+				}
+			};
+			let preprocessed_tua_files = compiler::preprocess_files(vec!(raw_tua_file), errors, logger);
+			lexed_files.append(&mut compiler::lex_files(preprocessed_tua_files, errors, logger));
+		}
+
+		i += 1;
+	}
+	logger.logln("done");
+}
+
+
+
+
+
+pub fn resolve_modules<'a> (parsed_files: &[(ParsedTuaFile<'a>, &'a PathBuf)], errors: &mut Vec<CompileError>, logger: &mut Logger) -> ModuleGraph<'a> {
+	logger.logln("");
+	logger.logln("Resolving modules ...");
+
+	// imports
+	let mut imports = vec!();
+	for (file, path) in parsed_files {
+		let mut file_imports = vec!();
+		for use_path in get_use_paths(file) {
+			let file_index = resolve_use_path(use_path, path)
+				.and_then(|module_path| parsed_files.iter().position(|(_, path)| fns::is_same_file(path, &module_path)));
+			let Some(file_index) = file_index else {
+				errors.push(CompileError::from(RawCompileError::UnresolvedImport {
+					location: file.locations.get_token(use_path[0]),
+					path: use_path.join("."),
+				}).with_path(path));
+				continue;
+			};
+			file_imports.push(ModuleImport {
+				name: use_path.last().unwrap(),
+				path: use_path.clone(),
+				file_index,
+			});
+		}
+		imports.push(file_imports);
+	}
+	let module_graph = ModuleGraph {imports};
+
+	// circular imports
+	let mut visit_states = vec!(VisitState::NotVisited; parsed_files.len());
+	for file_index in 0..parsed_files.len() {
+		find_circular_imports(file_index, &mut vec!(), &mut visit_states, &module_graph, parsed_files, errors);
+	}
+
+	logger.logln("done");
+	module_graph
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum VisitState {
+	NotVisited,
+	Visiting,
+	Visited,
+}
+
+fn find_circular_imports (file_index: usize, import_stack: &mut Vec<usize>, visit_states: &mut [VisitState], module_graph: &ModuleGraph, parsed_files: &[(ParsedTuaFile, &PathBuf)], errors: &mut Vec<CompileError>) {
+	if visit_states[file_index] != VisitState::NotVisited {return;}
+	visit_states[file_index] = VisitState::Visiting;
+	import_stack.push(file_index);
+	for import in &module_graph.imports[file_index] {
+		match visit_states[import.file_index] {
+			VisitState::NotVisited => find_circular_imports(import.file_index, import_stack, visit_states, module_graph, parsed_files, errors),
+			VisitState::Visiting => {
+				let cycle_start = import_stack.iter().position(|i| *i == import.file_index).unwrap();
+				let cycle = import_stack[cycle_start..].iter()
+					.chain([&import.file_index])
+					.map(|i| parsed_files[*i].1.clone())
+					.collect();
+				errors.push(CompileError::from(RawCompileError::CircularImport {
+					location: parsed_files[file_index].0.locations.get_token(import.path[0]),
+					cycle,
+				}).with_path(parsed_files[file_index].1));
+			}
+			VisitState::Visited => {}
+		}
+	}
+	import_stack.pop();
+	visit_states[file_index] = VisitState::Visited;
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+	use crate::prelude::*;
+
+
+
+	#[test]
+	fn std_uses_resolve_to_the_bundled_std () {
+		let importer_path = Path::new("project/main.tua");
+		assert_eq!(modules::resolve_use_path(&["std", "input"], importer_path), Some(PathBuf::from(init::STD_DIR).join("input.tua")));
+		assert_eq!(modules::resolve_use_path(&["std", "missing"], importer_path), None);
+	}
+
+	#[test]
+	fn this_uses_resolve_next_to_the_importer () {
+		let dir = std::env::temp_dir().join("tua_test_this_uses");
+		fs::create_dir_all(dir.join("parsing_mod")).unwrap();
+		fs::create_dir_all(dir.join("cli_mod")).unwrap();
+		fs::write(dir.join("parsing_mod/parsing.tua"), "").unwrap();
+		fs::write(dir.join("cli_mod/args.tua"), "").unwrap();
+		let importer_path = dir.join("main.tua");
+		let parsing_path = modules::resolve_use_path(&["this", "parsing"], &importer_path);
+		let args_path = modules::resolve_use_path(&["this", "cli", "args"], &importer_path);
+		let missing_path = modules::resolve_use_path(&["this", "missing"], &importer_path);
+		fs::remove_dir_all(&dir).unwrap();
+		assert_eq!(parsing_path, Some(dir.join("parsing_mod/parsing.tua")));
+		assert_eq!(args_path, Some(dir.join("cli_mod/args.tua")));
+		assert_eq!(missing_path, None);
+	}
+
+}
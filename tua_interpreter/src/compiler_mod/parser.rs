@@ -4,44 +4,103 @@ use crate::prelude::*;
 
 pub fn parse_tua_file<'a> (file: &'a LexedTuaFile, path: &Path, logger: &mut Logger) -> (ParsedTuaFile<'a>, Vec<CompileError>) {
 	let contents = &file.contents;
+	let locations = TokenLocations::from_tokens(contents);
 	let mut definitions = vec!();
+	let mut poisoned = vec!();
 	let mut errors = vec!();
 	let mut index = 0;
 	while index < contents.len() {
-		match &contents[index].token {
-			RawToken::Name(name) => {
-				let new_definition = match parse_definition(name, &mut index, contents, &mut errors, logger) {
-					Ok(v) => v,
-					Err(error) => {
-						errors.push(error);
-						return (ParsedTuaFile {definitions}, errors);
-					},
-				};
+		let definition_start = index;
+		let errors_start = errors.len();
+		match parse_top_level_item(&mut index, contents, &mut errors, logger) {
+			Ok(new_definition) => {
 				definitions.push(new_definition);
+				poisoned.push(errors.len() > errors_start);
 			}
-			_ => {
-				errors.push(RawCompileError::new_unexpected_token(&contents[index], "'function', 'object', 'choice', 'type', 'static', 'use', or '#'", "while parsing top-level definitions (detecting token type)").into());
-				return (ParsedTuaFile {definitions}, errors);
+			Err(error) => {
+				// skip to the next definition and keep going so that every error in the file is reported
+				errors.push(error);
+				skip_to_next_definition(definition_start, &mut index, contents);
 			}
 		}
 	}
-	(ParsedTuaFile {definitions}, errors)
+	let properties = definitions.iter().map(|_| DefinitionProperties::default()).collect();
+	(ParsedTuaFile {definitions, properties, poisoned, locations}, errors)
 }
 
 
 
-pub fn parse_definition<'a> (name: &str, index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTDefinition<'a>, CompileError> {
-	match name {
+// `.tuam` files only have macro definitions, like `macro global (set_is_this = true)`
+pub fn parse_tuam_file<'a> (file: &'a LexedTuaFile, path: &Path, logger: &mut Logger) -> (ParsedMacroFile<'a>, Vec<CompileError>) {
+	let contents = &file.contents;
+	let locations = TokenLocations::from_tokens(contents);
+	let mut macros = vec!();
+	let mut errors = vec!();
+	let mut index = 0;
+	while index < contents.len() {
+		let macro_start = index;
+		match parse_macro_definition(&mut index, contents, &mut errors, logger) {
+			Ok(new_macro) => macros.push(new_macro),
+			Err(error) => {
+				errors.push(error);
+				skip_to_next_macro(macro_start, &mut index, contents);
+			}
+		}
+	}
+	(ParsedMacroFile {macros, locations}, errors)
+}
+
+pub fn skip_to_next_macro (macro_start: usize, index: &mut usize, contents: &[Token]) {
+	*index = (*index).max(macro_start + 1);
+	while *index < contents.len() && !(is_first_on_line(*index, contents) && matches!(&contents[*index].token, RawToken::Name(name) if name == "macro")) {
+		*index += 1;
+	}
+}
+
+
+
+pub fn parse_top_level_item<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTDefinition<'a>, CompileError> {
+	let attributes = parse_attributes(index, contents, errors, logger)?;
+	let Some(next_token) = contents.get(*index) else {
+		return Err(RawCompileError::new_unexpected_end_of_file(&contents[*index - 1], "[definition]").into());
+	};
+	let RawToken::Name(name) = &next_token.token else {
+		return Err(RawCompileError::new_unexpected_token(&contents[*index], "'function', 'object', 'choice', 'type', 'static', 'use', 'global_use', or '#'", "while parsing top-level definitions (detecting token type)").into());
+	};
+	parse_definition(name, attributes, index, contents, errors, logger)
+}
+
+
+
+pub fn parse_definition<'a> (name: &str, attributes: Vec<ASTAttribute<'a>>, index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTDefinition<'a>, CompileError> {
+	let definition_start = *index;
+	let mut definition = match name {
 		"function" => parse_function_definition(index, contents, errors, logger),
 		"object"   => parse_object_definition(index, contents, errors, logger),
 		"choice"   => parse_choice_definition(index, contents, errors, logger),
 		"type"     => parse_type_definition(index, contents, errors, logger),
 		"const"    => parse_const_definition(index, contents, errors, logger),
 		"static"   => parse_static_definition(index, contents, errors, logger),
-		"use"      => {logger.print_all(); todo!("use definition")}
-		"#"        => {logger.print_all(); todo!("# definition")}
-		_ => Err(RawCompileError::new_unexpected_token(&contents[*index], "'function', 'object', 'choice', 'type', 'static', 'use', or '#'", "while parsing top-level definitions (detecting token content)").into())
+		"use"      => parse_use_definition(index, contents, errors, logger),
+		"global_use" => parse_global_use_definition(index, contents, errors, logger),
+		_ => Err(RawCompileError::UnknownDefinition {
+			location: contents[*index].clone(),
+			name: name.to_string(),
+			suggestion: fns::get_closest_name(name, DEFINITION_KEYWORDS.iter().copied()).map(str::to_string),
+		}.into())
+	}?;
+	if let Some(first_attribute) = attributes.first() {
+		let target = definition.type_name();
+		match definition.attributes_mut() {
+			Some(definition_attributes) => *definition_attributes = attributes,
+			None => errors.push(RawCompileError::InvalidAttributeTarget {
+				location: contents[definition_start].clone(),
+				name: first_attribute.name.to_string(),
+				target: target.to_string(),
+			}.into()),
+		}
 	}
+	Ok(definition)
 }
 
 
@@ -61,12 +120,305 @@ pub fn get_next_token_checked<'a> (index: &usize, contents: &'a [Token]) -> Opti
 
 
 
+pub const DEFINITION_KEYWORDS: &[&str] = &["function", "object", "choice", "type", "const", "static", "use", "global_use"];
+pub const BLOCK_KEYWORDS: &[&str] = &["if", "switch", "for", "while", "loop"];
+
+pub fn is_first_on_line (index: usize, contents: &[Token]) -> bool {
+	index == 0 || contents[index - 1].line_num != contents[index].line_num
+}
+
+pub fn is_last_on_line (index: usize, contents: &[Token]) -> bool {
+	contents.get(index + 1).map_or(true, |next_token| next_token.line_num != contents[index].line_num)
+}
+
+// definitions (and their attributes) are only looked for at the start of a line so that names like `type` inside a function don't stop the skipping
+pub fn is_definition_start (index: usize, contents: &[Token]) -> bool {
+	if !is_first_on_line(index, contents) {return false;}
+	match &contents[index].token {
+		RawToken::Name(name) => DEFINITION_KEYWORDS.contains(&&**name),
+		RawToken::Octothorp => true,
+		_ => false,
+	}
+}
+
+
+
+// panic-mode recovery for definitions, always skips at least one token so parsing can't get stuck
+pub fn skip_to_next_definition (definition_start: usize, index: &mut usize, contents: &[Token]) {
+	*index = (*index).max(definition_start + 1);
+	while *index < contents.len() && !is_definition_start(*index, contents) && !is_misspelled_definition_start(*index, contents) {
+		*index += 1;
+	}
+}
+
+// things like `funciton range`, so they can be reported instead of skipped
+pub fn is_misspelled_definition_start (index: usize, contents: &[Token]) -> bool {
+	let (RawToken::Name(name), Some(RawToken::Name(_))) = (&contents[index].token, get_next_token_checked(&(index + 1), contents)) else {return false;};
+	if !is_first_on_line(index, contents) || is_first_on_line(index + 1, contents) {return false;}
+	fns::get_closest_name(name, DEFINITION_KEYWORDS.iter().copied()).is_some()
+}
+
+
+
+// panic-mode recovery for statements: a block statement (if, while, etc) is skipped up to its matching 'end', anything else is skipped to the end of the line
+// stops early at anything that looks like the start of a definition, since the current function is probably missing its 'end'
+pub fn skip_to_next_statement (statement_start: usize, index: &mut usize, contents: &[Token]) {
+	let is_block = matches!(&contents[statement_start].token, RawToken::Name(name) if BLOCK_KEYWORDS.contains(&&**name));
+	if !is_block {
+		// if the error is at the start of a line then the statement most likely just wasn't finished
+		if *index > statement_start && *index < contents.len() && is_first_on_line(*index, contents) && matches!(contents[*index].token, RawToken::Name(_)) {return;}
+		let error_line = contents.get(*index).unwrap_or(&contents[contents.len() - 1]).line_num;
+		*index = (*index).max(statement_start + 1);
+		while *index < contents.len() && contents[*index].line_num <= error_line && !is_definition_start(*index, contents) {
+			*index += 1;
+		}
+		return;
+	}
+	let mut depth = 0usize;
+	*index = statement_start;
+	while *index < contents.len() {
+		if *index > statement_start && is_definition_start(*index, contents) {return;}
+		match &contents[*index].token {
+			RawToken::Name(name) if is_first_on_line(*index, contents) && BLOCK_KEYWORDS.contains(&&**name) => depth += 1,
+			RawToken::Colon if is_last_on_line(*index, contents) => depth += 1, // switch cases
+			RawToken::Name(name) if name == "end" => {
+				depth = depth.saturating_sub(1);
+				if depth == 0 {
+					*index += 1;
+					return;
+				}
+			}
+			_ => {}
+		}
+		*index += 1;
+	}
+}
+
+
+
+// parses a statement into `statements`, or records the error and skips past the statement
+// errors are only passed up when the skipping reaches a new definition or the end of the file
+pub fn parse_block_statement<'a> (statements: &mut Vec<ASTStatement<'a>>, index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<(), CompileError> {
+	let statement_start = *index;
+	match parse_statement(index, contents, errors, logger) {
+		Ok(statement) => statements.push(statement),
+		Err(error) => {
+			skip_to_next_statement(statement_start, index, contents);
+			if *index >= contents.len() || is_definition_start(*index, contents) {
+				return Err(error);
+			}
+			errors.push(error);
+		}
+	}
+	Ok(())
+}
+
+
+
+
+
+pub fn parse_attributes<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<Vec<ASTAttribute<'a>>, CompileError> {
+	let mut attributes = vec!();
+	while get_next_token_checked(index, contents) == Some(&RawToken::Octothorp) {
+		attributes.push(parse_attribute(index, contents, errors, logger)?);
+	}
+	Ok(attributes)
+}
+
+
+
+pub fn parse_attribute<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTAttribute<'a>, CompileError> {
+	*index += 1;
+
+	// name
+	let RawToken::Name(name) = get_next_token(index, contents, "[name of attribute]")? else {
+		return Err(RawCompileError::new_unexpected_token(&contents[*index], "[name of attribute]", "while parsing attribute").into());
+	};
+	*index += 1;
+
+	// args
+	let mut args = vec!();
+	if get_next_token_checked(index, contents) == Some(&RawToken::OpenParen) {
+		*index += 1;
+		'args: loop {
+
+			// close paren?
+			if *get_next_token(index, contents, "[argument] or ')'")? == RawToken::CloseParen {
+				*index += 1;
+				break 'args;
+			}
+
+			args.push(parse_formula(index, contents, errors, logger)?);
+
+			// seperator
+			match *get_next_token(index, contents, "',' or ')'")? {
+				RawToken::Comma => {
+					*index += 1;
+					continue;
+				}
+				RawToken::CloseParen => {
+					*index += 1;
+					break 'args;
+				}
+				_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "',' or ')'", "while parsing attribute args seperator").into()),
+			}
+
+		}
+	}
+
+	Ok(ASTAttribute {
+		name,
+		args,
+	})
+}
+
+
+
+
+
+pub fn parse_macro_definition<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTMacro<'a>, CompileError> {
+
+	// 'macro'
+	if *get_next_token(index, contents, "'macro'")? != RawToken::Name(String::from("macro")) {
+		return Err(RawCompileError::new_unexpected_token(&contents[*index], "'macro'", "while parsing macro definition").into());
+	}
+	*index += 1;
+
+	// name
+	let RawToken::Name(name) = get_next_token(index, contents, "[name of macro]")? else {
+		return Err(RawCompileError::new_unexpected_token(&contents[*index], "[name of macro]", "while parsing macro definition").into());
+	};
+	*index += 1;
+	if *get_next_token(index, contents, "'('")? != RawToken::OpenParen {
+		return Err(RawCompileError::new_unexpected_token(&contents[*index], "'('", "while parsing macro definition").into());
+	}
+	*index += 1;
+
+	// properties
+	let mut output = ASTMacro {
+		name,
+		args: vec!(),
+		allowed_definition_types: None,
+		set_is_this: false,
+		code: vec!(),
+	};
+	loop {
+
+		// close paren?
+		let next_token = get_next_token(index, contents, "[macro property] or ')'")?;
+		if *next_token == RawToken::CloseParen {
+			*index += 1;
+			break;
+		}
+
+		// property name
+		let RawToken::Name(property_name) = next_token else {
+			return Err(RawCompileError::new_unexpected_token(&contents[*index], "[macro property] or ')'", "while parsing macro properties").into());
+		};
+		let property_location = contents[*index].clone();
+		*index += 1;
+		// `name: value` is a common mistake, so it's reported but the property is still parsed
+		let next_token = get_next_token(index, contents, "'='")?;
+		if *next_token == RawToken::Colon {
+			errors.push(RawCompileError::new_unexpected_token(&contents[*index], "'='", "while parsing macro properties").into());
+		} else if *next_token != RawToken::AssignmentOperator(AssignmentOperator::Equals) {
+			return Err(RawCompileError::new_unexpected_token(&contents[*index], "'='", "while parsing macro properties").into());
+		}
+		*index += 1;
+
+		// property value
+		match &**property_name {
+			"args" => output.args = parse_macro_name_list(index, contents)?,
+			"allowed_definition_types" => output.allowed_definition_types = Some(parse_macro_name_list(index, contents)?),
+			"set_is_this" => {
+				let RawToken::Bool(value) = get_next_token(index, contents, "'true' or 'false'")? else {
+					return Err(RawCompileError::new_unexpected_token(&contents[*index], "'true' or 'false'", "while parsing macro property `set_is_this`").into());
+				};
+				output.set_is_this = *value;
+				*index += 1;
+			}
+			"code" => output.code = parse_macro_code(index, contents, errors, logger)?,
+			_ => return Err(RawCompileError::UnknownMacroProperty {location: property_location, name: property_name.to_string()}.into()),
+		}
+
+	}
+
+	Ok(output)
+}
+
+
+
+// `[name, other_name]`
+pub fn parse_macro_name_list<'a> (index: &mut usize, contents: &'a [Token]) -> Result<Vec<&'a str>, CompileError> {
+	if *get_next_token(index, contents, "'['")? != RawToken::OpenSquareBracket {
+		return Err(RawCompileError::new_unexpected_token(&contents[*index], "'['", "while parsing macro property list").into());
+	}
+	*index += 1;
+	let mut names = vec!();
+	loop {
+		match get_next_token(index, contents, "[name] or ']'")? {
+			RawToken::CloseSquareBracket => {
+				*index += 1;
+				break;
+			}
+			RawToken::Name(name) => {
+				names.push(&**name);
+				*index += 1;
+			}
+			_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "[name] or ']'", "while parsing macro property list").into()),
+		}
+		match get_next_token(index, contents, "',' or ']'")? {
+			RawToken::Comma => *index += 1,
+			RawToken::CloseSquareBracket => {
+				*index += 1;
+				break;
+			}
+			_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "',' or ']'", "while parsing macro property list seperator").into()),
+		}
+	}
+	Ok(names)
+}
+
+
+
+// `function() ... end`
+pub fn parse_macro_code<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTBlock<'a>, CompileError> {
+	for (expected_token, expected) in [(RawToken::Name(String::from("function")), "'function'"), (RawToken::OpenParen, "'('"), (RawToken::CloseParen, "')'")] {
+		if *get_next_token(index, contents, expected)? != expected_token {
+			return Err(RawCompileError::new_unexpected_token(&contents[*index], expected, "while parsing macro code").into());
+		}
+		*index += 1;
+	}
+	let mut block = vec!();
+	loop {
+
+		// ending token?
+		if *get_next_token(index, contents, "[statement] or 'end'")? == RawToken::Name(String::from("end")) {
+			*index += 1;
+			break;
+		}
+
+		// statement
+		parse_block_statement(&mut block, index, contents, errors, logger)?;
+
+	}
+	Ok(block)
+}
+
+
+
 
 
 pub fn parse_type<'a> (index: &mut usize, contents: &'a [Token], _errors: &mut Vec<CompileError>, _logger: &mut Logger) -> Result<ASTType<'a>, CompileError> {
 
+	// tuple
+	let name_token = get_next_token(index, contents, "[name of type] or '('")?;
+	if *name_token == RawToken::OpenParen {
+		let output = parse_tuple_type(index, contents, _errors, _logger)?;
+		return parse_type_postfix(output, index, contents);
+	}
+
 	// type name
-	let name_token = get_next_token(index, contents, "[name of type]")?;
 	let RawToken::Name(type_name) = name_token else {
 		return Err(RawCompileError::InvalidTypeName {location: contents[*index].clone()}.into());
 	};
@@ -80,13 +432,18 @@ pub fn parse_type<'a> (index: &mut usize, contents: &'a [Token], _errors: &mut V
 	}
 	let unnamed_type_arg = unnamed_type_arg.map(|v| box v);
 
-	let mut output = ASTType {
+	let output = ASTType {
 		name: type_name,
 		unnamed_type_arg,
 		named_type_args,
+		tuple_items: vec!(),
 	};
 
-	// post-fix types
+	parse_type_postfix(output, index, contents)
+}
+
+// post-fix types
+pub fn parse_type_postfix<'a> (mut output: ASTType<'a>, index: &mut usize, contents: &'a [Token]) -> Result<ASTType<'a>, CompileError> {
 	let output = loop {
 		match get_next_token_checked(index, contents) {
 			Some(&RawToken::OpenSquareBracket) => {
@@ -97,6 +454,7 @@ pub fn parse_type<'a> (index: &mut usize, contents: &'a [Token], _errors: &mut V
 					name: "Array",
 					unnamed_type_arg: Some(box output),
 					named_type_args: vec!(),
+					tuple_items: vec!(),
 				};
 				*index += 1;
 			}
@@ -116,6 +474,39 @@ pub fn parse_type<'a> (index: &mut usize, contents: &'a [Token], _errors: &mut V
 	Ok(output)
 }
 
+// `(int, String)`, or `()` for no items
+pub fn parse_tuple_type<'a> (index: &mut usize, contents: &'a [Token], _errors: &mut Vec<CompileError>, _logger: &mut Logger) -> Result<ASTType<'a>, CompileError> {
+	*index += 1;
+	let mut tuple_items = vec!();
+	loop {
+
+		// end?
+		if *get_next_token(index, contents, "[type] or ')'")? == RawToken::CloseParen {
+			*index += 1;
+			break;
+		}
+
+		// item
+		tuple_items.push(parse_type(index, contents, _errors, _logger)?);
+
+		match *get_next_token(index, contents, "',' or ')'")? {
+			RawToken::Comma => *index += 1,
+			RawToken::CloseParen => {
+				*index += 1;
+				break;
+			}
+			_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "',' or ')'", "while parsing tuple type").into()),
+		}
+
+	}
+	Ok(ASTType {
+		name: "Tuple",
+		unnamed_type_arg: None,
+		named_type_args: vec!(),
+		tuple_items,
+	})
+}
+
 
 
 pub fn parse_type_args<'a> (index: &mut usize, contents: &'a [Token], _errors: &mut Vec<CompileError>, _logger: &mut Logger) -> Result<(Option<ASTType<'a>>, Vec<(&'a str, ASTType<'a>)>), CompileError> {
@@ -179,41 +570,38 @@ pub fn parse_type_args<'a> (index: &mut usize, contents: &'a [Token], _errors: &
 
 pub fn parse_formula<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTFormula<'a>, CompileError> {
 	logger.logln(format!("parsing formula at {:?}", contents[*index]));
+	parse_formula_with_precedence(0, index, contents, errors, logger)
+}
 
-	let first_item = parse_formula_item(index, contents, errors, logger)?;
-	let mut formula_items = vec!(first_item);
-	let mut formula_operators = vec!();
-
-	'extra_items: loop {
-		let operator = match get_next_token_checked(index, contents) {
-			Some(RawToken::Operator(v)) => v,
-			_ => break 'extra_items,
-		};
-		*index += 1;
-		let next_item = parse_formula_item(index, contents, errors, logger)?;
-		formula_operators.push((operator, operator.get_eval_level()));
-		formula_items.push(next_item);
-	}
+// precedence climbing: the right side of each operator takes every operator after it that binds tighter
+pub fn parse_formula_with_precedence<'a> (min_precedence: usize, index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTFormula<'a>, CompileError> {
+	let mut output = parse_formula_item(index, contents, errors, logger)?;
+	let mut last_comparison: Option<&Operator> = None;
+	loop {
 
-	'combining: for level in (0..=Operator::MAX_EVAL_LEVEL).rev() {
-		let mut i = 0;
-		'current_level_loop: loop {
-			if i >= formula_operators.len() {break 'current_level_loop;}
-			let (_, current_eval_level) = formula_operators[i];
-			if current_eval_level != level {
-				i += 1;
-				continue;
+		// operator
+		let Some(RawToken::Operator(operator)) = get_next_token_checked(index, contents) else {break;};
+		let Some(precedence) = operator.get_precedence() else {break;};
+		if precedence < min_precedence {break;}
+		if operator.is_comparison() {
+			if let Some(last_comparison) = last_comparison {
+				return Err(RawCompileError::ChainedComparison {
+					location: contents[*index].clone(),
+					first: last_comparison.to_string(),
+					second: operator.to_string(),
+				}.into());
 			}
-			let left = formula_items.remove(i);
-			let right = formula_items.remove(i);
-			let (current_operator, _) = formula_operators.remove(i);
-			formula_items.insert(i, ASTFormula::Operation {left: box left, right: box right, operator: current_operator.clone()});
-			// no increment
+			last_comparison = Some(operator);
 		}
-	}
+		*index += 1;
 
-	Ok(formula_items.pop().unwrap())
+		// right side
+		let right_min_precedence = if operator.is_right_associative() {precedence} else {precedence + 1};
+		let right = parse_formula_with_precedence(right_min_precedence, index, contents, errors, logger)?;
+		output = ASTFormula::Operation {left: box output, right: box right, operator: operator.clone()};
 
+	}
+	Ok(output)
 }
 
 
@@ -226,13 +614,16 @@ pub fn parse_formula_item<'a> (index: &mut usize, contents: &'a [Token], errors:
 		RawToken::Name(value) => {
 			match &**value {
 				"new" => parse_formula_item_new(index, contents, errors, logger)?,
+				"function" => parse_formula_item_function(index, contents, errors, logger)?,
+				_ if is_type_name(value) => parse_formula_item_choice_variant(value, index, contents, errors, logger)?,
 				_ => {
 					*index += 1;
 					ASTFormula::Name(value)
 				}
 			}
 		}
-		RawToken::Operator(Operator::Not) => parse_formula_item_not(index, contents, errors, logger)?,
+		RawToken::PrefixOperator(PrefixOperator::Not) => parse_formula_item_prefix(PrefixOperator::Not, index, contents, errors, logger)?,
+		RawToken::Operator(Operator::Minus) => parse_formula_item_prefix(PrefixOperator::Negate, index, contents, errors, logger)?,
 		RawToken::OpenParen => parse_formula_item_parens(index, contents, errors, logger)?,
 		RawToken::Int(value) => {
 			*index += 1;
@@ -246,6 +637,10 @@ pub fn parse_formula_item<'a> (index: &mut usize, contents: &'a [Token], errors:
 			*index += 1;
 			ASTFormula::Float(*value)
 		}
+		RawToken::TypedNumber(value, tua_type) => {
+			*index += 1;
+			ASTFormula::TypedNumber {value: box get_number_formula(value, false).unwrap(), tua_type: tua_type.clone()}
+		}
 		RawToken::Bool(value) => {
 			*index += 1;
 			ASTFormula::Bool(*value)
@@ -362,16 +757,188 @@ pub fn parse_formula_item_new<'a> (index: &mut usize, contents: &'a [Token], err
 
 
 
-pub fn parse_formula_item_not<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTFormula<'a>, CompileError> {
+// `function (a, b: int) returns int ... end`, args without a type get it from the `Function<...>` type that the function is given as
+pub fn parse_formula_item_function<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTFormula<'a>, CompileError> {
+	*index += 1;
+
+	// args
+	if *get_next_token(index, contents, "'('")? != RawToken::OpenParen {
+		return Err(RawCompileError::new_unexpected_token(&contents[*index], "'('", "while parsing anonymous function").into());
+	}
+	*index += 1;
+	let mut args: Vec<(&str, Option<ASTType>)> = vec!();
+	loop {
+
+		// end?
+		let arg_token = get_next_token(index, contents, "[name of arg] or ')'")?;
+		if *arg_token == RawToken::CloseParen {
+			*index += 1;
+			break;
+		}
+
+		// arg name
+		let RawToken::Name(arg_name) = arg_token else {
+			return Err(RawCompileError::new_unexpected_token(&contents[*index], "[name of arg] or ')'", "while parsing anonymous function args").into());
+		};
+		if args.iter().any(|(name, _)| name == arg_name) {
+			errors.push(RawCompileError::DuplicateFunctionArg {location: contents[*index].clone()}.into());
+		}
+		*index += 1;
+
+		// arg type
+		let mut arg_type = None;
+		if *get_next_token(index, contents, "':', ',', or ')'")? == RawToken::Colon {
+			*index += 1;
+			arg_type = Some(parse_type(index, contents, errors, logger)?);
+		}
+		args.push((arg_name, arg_type));
+
+		match *get_next_token(index, contents, "',' or ')'")? {
+			RawToken::Comma => *index += 1,
+			RawToken::CloseParen => {
+				*index += 1;
+				break;
+			}
+			_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "',' or ')'", "while parsing anonymous function args seperator").into()),
+		}
+
+	}
+
+	// return type
+	let mut return_type = None;
+	if get_next_token_checked(index, contents) == Some(&RawToken::Name(String::from("returns"))) {
+		*index += 1;
+		return_type = Some(parse_type(index, contents, errors, logger)?);
+	}
+
+	// statements
+	let mut statements = vec!();
+	loop {
+		if *get_next_token(index, contents, "[statement] or 'end'")? == RawToken::Name(String::from("end")) {
+			*index += 1;
+			break;
+		}
+		parse_block_statement(&mut statements, index, contents, errors, logger)?;
+	}
+
+	let arg_names: Vec<&str> = args.iter().map(|(name, _)| *name).collect();
+	let captures = captures::find_captures(&arg_names, &statements);
+	Ok(ASTFormula::Function {
+		args,
+		return_type,
+		statements,
+		captures,
+	})
+}
+
+
+
+// type names start with an uppercase letter, so `Job.Programmer` and `Filled(x)` can be told apart from property queries and function calls
+pub fn is_type_name (name: &str) -> bool {
+	name.starts_with(|char: char| char.is_ascii_uppercase())
+}
+
+
+
+pub fn parse_formula_item_choice_variant<'a> (name: &'a str, index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTFormula<'a>, CompileError> {
+	*index += 1;
+
+	// `Choice.Variant`, `Variant(...)`, or just a name (like `Nothing` or `Person` in `Person.new()`)
+	let (choice, variant) = match (get_next_token_checked(index, contents), get_next_token_checked(&(*index + 1), contents)) {
+		(Some(RawToken::Period), Some(RawToken::Name(variant))) if is_type_name(variant) => {
+			*index += 2;
+			(Some(name), &**variant)
+		}
+		(Some(RawToken::OpenParen), _) => (None, name),
+		_ => return Ok(ASTFormula::Name(name)),
+	};
+
+	let payload = if get_next_token_checked(index, contents) == Some(&RawToken::OpenParen) {
+		parse_variant_payload(index, contents, errors, logger)?
+	} else {
+		ASTVariantPayload::None
+	};
+
+	Ok(ASTFormula::ChoiceVariant {choice, variant, payload})
+}
+
+
+
+// either `(value, value)` or `(name: value, name: value)`
+pub fn parse_variant_payload<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTVariantPayload<'a>, CompileError> {
+	*index += 1;
+
+	let mut values = vec!();
+	let mut feilds = vec!();
+	loop {
+
+		// close paren?
+		if *get_next_token(index, contents, "[payload value] or ')'")? == RawToken::CloseParen {
+			*index += 1;
+			break;
+		}
+
+		// named feild or value
+		match (get_next_token_checked(index, contents), get_next_token_checked(&(*index + 1), contents)) {
+			(Some(RawToken::Name(feild_name)), Some(RawToken::Colon)) => {
+				if !values.is_empty() {
+					return Err(RawCompileError::new_unexpected_token(&contents[*index], "[payload value]", "while parsing choice variant payload (payloads can't mix named and unnamed feilds)").into());
+				}
+				*index += 2;
+				feilds.push((&**feild_name, parse_formula(index, contents, errors, logger)?));
+			}
+			_ => {
+				if !feilds.is_empty() {
+					return Err(RawCompileError::new_unexpected_token(&contents[*index], "[name of feild]", "while parsing choice variant payload (payloads can't mix named and unnamed feilds)").into());
+				}
+				values.push(parse_formula(index, contents, errors, logger)?);
+			}
+		}
+
+		// seperator / end
+		match *get_next_token(index, contents, "',' or ')'")? {
+			RawToken::Comma => *index += 1,
+			RawToken::CloseParen => {
+				*index += 1;
+				break;
+			}
+			_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "',' or ')'", "while parsing choice variant payload").into()),
+		}
+
+	}
+
+	Ok(match (values.is_empty(), feilds.is_empty()) {
+		(true, true) => ASTVariantPayload::None,
+		(false, _) => ASTVariantPayload::Tuple(values),
+		(_, false) => ASTVariantPayload::Feilds(feilds),
+	})
+}
+
+
+
+// prefix operators only take the item right after them, so `-x.y` is `-(x.y)` and `not a == b` is `(not a) == b`
+pub fn parse_formula_item_prefix<'a> (operator: PrefixOperator, index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTFormula<'a>, CompileError> {
 	
 	*index += 1;
 	let base = parse_formula_item(index, contents, errors, logger)?;
 
-	Ok(ASTFormula::Not {
-		base: box base,
+	Ok(match operator {
+		PrefixOperator::Negate => negate_formula(base),
+		_ => ASTFormula::Prefix {operator, base: box base},
 	})
 }
 
+// negative literals are folded into the literal, so that `-5` can be given to an `int_8` and `-9223372036854775808` fits in an `int`
+pub fn negate_formula (base: ASTFormula) -> ASTFormula {
+	match base {
+		ASTFormula::Int (value) if value != i64::MIN => ASTFormula::Int(-value),
+		ASTFormula::UInt (value) if value == 1 << 63 => ASTFormula::Int(i64::MIN),
+		ASTFormula::Float (value) => ASTFormula::Float(-value),
+		ASTFormula::TypedNumber {value, tua_type} => ASTFormula::TypedNumber {value: box negate_formula(*value), tua_type},
+		base => ASTFormula::Prefix {operator: PrefixOperator::Negate, base: box base},
+	}
+}
+
 
 
 pub fn parse_formula_item_parens<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTFormula<'a>, CompileError> {
@@ -647,7 +1214,7 @@ pub fn parse_function_definition<'a> (index: &mut usize, contents: &'a [Token],
 			// default value
 			let mut next_token = get_next_token(index, contents, "',', ')', or [default value]")?;
 			let default_value = match *next_token {
-				RawToken::Int(_) | RawToken::UInt(_) | RawToken::Float(_) | RawToken::Bool(_) | RawToken::String(_) | RawToken::Char(_) => {
+				RawToken::Int(_) | RawToken::UInt(_) | RawToken::Float(_) | RawToken::TypedNumber(..) | RawToken::Bool(_) | RawToken::String(_) | RawToken::Char(_) => {
 					let output = next_token;
 					*index += 1;
 					next_token = get_next_token(index, contents, "',', ')', or [default value]")?;
@@ -707,11 +1274,11 @@ pub fn parse_function_definition<'a> (index: &mut usize, contents: &'a [Token],
                 break;
             }
         }
-        let new_statement = parse_statement(index, contents, errors, logger)?;
-        statements.push(new_statement);
+        parse_block_statement(&mut statements, index, contents, errors, logger)?;
     }
 
 	Ok(ASTDefinition::Function {
+		attributes: vec!(),
 		name: function_name,
 		associated_type,
 		args,
@@ -759,6 +1326,9 @@ pub fn parse_object_definition<'a> (index: &mut usize, contents: &'a [Token], er
 			break 'feilds;
 		}
 		
+		// attributes
+		let attributes = parse_attributes(index, contents, errors, logger)?;
+
 		// feild name
 		let feild_name = match get_next_token(index, contents, "[name of feild]")? {
 			RawToken::Name(v) => v,
@@ -789,6 +1359,7 @@ pub fn parse_object_definition<'a> (index: &mut usize, contents: &'a [Token], er
 		}
 
 		feilds.push(ASTObjectFeild {
+			attributes,
 			name: feild_name,
 			ast_type,
 			default_value,
@@ -810,6 +1381,7 @@ pub fn parse_object_definition<'a> (index: &mut usize, contents: &'a [Token], er
 	}
 
 	Ok(ASTDefinition::Object {
+		attributes: vec!(),
 		name,
 		feilds,
 	})
@@ -864,7 +1436,14 @@ pub fn parse_choice_definition<'a> (index: &mut usize, contents: &'a [Token], er
 		};
 		*index += 1;
 
-		choices.push(choice_name);
+		// payload
+		let payload = if get_next_token_checked(index, contents) == Some(&RawToken::OpenParen) {
+			parse_choice_payload(index, contents, errors, logger)?
+		} else {
+			ASTChoicePayload::None
+		};
+
+		choices.push(ASTChoiceVariant {name: choice_name, payload});
 
 		// seperator / end
 		match *get_next_token(index, contents, "',' or ')'")? {
@@ -882,6 +1461,7 @@ pub fn parse_choice_definition<'a> (index: &mut usize, contents: &'a [Token], er
 	}
 
 	Ok(ASTDefinition::Choice {
+		attributes: vec!(),
 		name,
 		choices,
 	})
@@ -889,6 +1469,58 @@ pub fn parse_choice_definition<'a> (index: &mut usize, contents: &'a [Token], er
 
 
 
+// either `(type, type)` or `(name: type, name: type)`
+pub fn parse_choice_payload<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTChoicePayload<'a>, CompileError> {
+	*index += 1;
+
+	let mut types = vec!();
+	let mut feilds = vec!();
+	loop {
+
+		// close paren?
+		if *get_next_token(index, contents, "[payload type] or ')'")? == RawToken::CloseParen {
+			*index += 1;
+			break;
+		}
+
+		// named feild or type
+		match (get_next_token_checked(index, contents), get_next_token_checked(&(*index + 1), contents)) {
+			(Some(RawToken::Name(feild_name)), Some(RawToken::Colon)) => {
+				if !types.is_empty() {
+					return Err(RawCompileError::new_unexpected_token(&contents[*index], "[payload type]", "while parsing choice payload (payloads can't mix named and unnamed feilds)").into());
+				}
+				*index += 2;
+				feilds.push((&**feild_name, parse_type(index, contents, errors, logger)?));
+			}
+			_ => {
+				if !feilds.is_empty() {
+					return Err(RawCompileError::new_unexpected_token(&contents[*index], "[name of feild]", "while parsing choice payload (payloads can't mix named and unnamed feilds)").into());
+				}
+				types.push(parse_type(index, contents, errors, logger)?);
+			}
+		}
+
+		// seperator / end
+		match *get_next_token(index, contents, "',' or ')'")? {
+			RawToken::Comma => *index += 1,
+			RawToken::CloseParen => {
+				*index += 1;
+				break;
+			}
+			_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "',' or ')'", "while parsing choice payload").into()),
+		}
+
+	}
+
+	Ok(match (types.is_empty(), feilds.is_empty()) {
+		(true, true) => ASTChoicePayload::None,
+		(false, _) => ASTChoicePayload::Tuple(types),
+		(_, false) => ASTChoicePayload::Feilds(feilds),
+	})
+}
+
+
+
 
 
 
@@ -920,6 +1552,7 @@ pub fn parse_type_definition<'a> (index: &mut usize, contents: &'a [Token], erro
 	let ast_type = parse_type(index, contents, errors, logger)?;
 
 	Ok(ASTDefinition::Type {
+		attributes: vec!(),
 		name,
 		ast_type,
 	})
@@ -958,6 +1591,7 @@ pub fn parse_const_definition<'a> (index: &mut usize, contents: &'a [Token], err
 	let value = parse_formula(index, contents, errors, logger)?;
 
 	Ok(ASTDefinition::Const {
+		attributes: vec!(),
 		name,
 		value,
 	})
@@ -972,32 +1606,129 @@ pub fn parse_const_definition<'a> (index: &mut usize, contents: &'a [Token], err
 
 
 
-pub fn parse_static_definition<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTDefinition<'a>, CompileError> {
-	logger.logln(format!("parsing static at {:?}", contents[*index]));
-	*index += 1;
+pub fn parse_static_definition<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTDefinition<'a>, CompileError> {
+	logger.logln(format!("parsing static at {:?}", contents[*index]));
+	*index += 1;
+
+	// name
+	let name = match get_next_token(index, contents, "[name of static]")? {
+		RawToken::Name(v) => v,
+		_ => {
+			errors.push(RawCompileError::new_unexpected_token(&contents[*index], "[name of static]", "while parsing static definition").into());
+			""
+		}
+	};
+	*index += 1;
+
+	// equal sign
+	if *get_next_token(index, contents, "'='")? != RawToken::AssignmentOperator(AssignmentOperator::Equals) {
+		return Err(RawCompileError::new_unexpected_token(&contents[*index], "'='", "while parsing static definition").into());
+	}
+	*index += 1;
+
+	// value
+	let value = parse_formula(index, contents, errors, logger)?;
+
+	Ok(ASTDefinition::Static {
+		attributes: vec!(),
+		name,
+		value,
+	})
+}
+
+
+
+
+
+
+
+
+
+
+pub fn parse_use_definition<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTDefinition<'a>, CompileError> {
+	logger.logln(format!("parsing use at {:?}", contents[*index]));
+	*index += 1;
+
+	let mut paths = vec!();
+	loop {
+
+		// path
+		let mut path = vec!();
+		loop {
+			let RawToken::Name(module_name) = get_next_token(index, contents, "[name of module]")? else {
+				return Err(RawCompileError::new_unexpected_token(&contents[*index], "[name of module]", "while parsing use definition").into());
+			};
+			path.push(&**module_name);
+			*index += 1;
+			if get_next_token_checked(index, contents) != Some(&RawToken::Period) {break;}
+			*index += 1;
+		}
+		paths.push(path);
+
+		// seperator
+		if get_next_token_checked(index, contents) != Some(&RawToken::Comma) {break;}
+		*index += 1;
+
+	}
+
+	Ok(ASTDefinition::Use {
+		paths,
+	})
+}
+
+
+
+
+
+
+
+
+
+
+pub fn parse_global_use_definition<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTDefinition<'a>, CompileError> {
+	logger.logln(format!("parsing global_use at {:?}", contents[*index]));
+	*index += 1;
+
+	// open paren
+	match *get_next_token(index, contents, "'('")? {
+		RawToken::OpenParen => {},
+		_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "'('", "while parsing global_use definition").into()),
+	}
+	*index += 1;
+
+	let mut names = vec!();
+	'names: loop {
+
+		// close paren?
+		if *get_next_token(index, contents, "[name] or ')'")? == RawToken::CloseParen {
+			*index += 1;
+			break 'names;
+		}
+
+		// name
+		let RawToken::Name(name) = get_next_token(index, contents, "[name]")? else {
+			return Err(RawCompileError::new_unexpected_token(&contents[*index], "[name]", "while parsing global_use definition").into());
+		};
+		names.push(&**name);
+		*index += 1;
 
-	// name
-	let name = match get_next_token(index, contents, "[name of static]")? {
-		RawToken::Name(v) => v,
-		_ => {
-			errors.push(RawCompileError::new_unexpected_token(&contents[*index], "[name of static]", "while parsing static definition").into());
-			""
+		// seperator / end
+		match *get_next_token(index, contents, "',' or ')'")? {
+			RawToken::Comma => {
+				*index += 1;
+				continue;
+			}
+			RawToken::CloseParen => {
+				*index += 1;
+				break 'names;
+			}
+			_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "',' or ')'", "while parsing global_use seperator").into()),
 		}
-	};
-	*index += 1;
 
-	// equal sign
-	if *get_next_token(index, contents, "'='")? != RawToken::AssignmentOperator(AssignmentOperator::Equals) {
-		return Err(RawCompileError::new_unexpected_token(&contents[*index], "'='", "while parsing static definition").into());
 	}
-	*index += 1;
-
-	// value
-	let value = parse_formula(index, contents, errors, logger)?;
 
-	Ok(ASTDefinition::Static {
-		name,
-		value,
+	Ok(ASTDefinition::GlobalUse {
+		names,
 	})
 }
 
@@ -1106,6 +1837,7 @@ pub fn parse_statement_var_init<'a> (index: &mut usize, contents: &'a [Token], e
 
 	// var names
 	let mut var_names = vec!();
+	let mut var_type = None;
 	loop {
 		let var_name_token = get_next_token(index, contents, "[name of variable]")?;
 		let RawToken::Name(var_name) = var_name_token else {
@@ -1113,7 +1845,7 @@ pub fn parse_statement_var_init<'a> (index: &mut usize, contents: &'a [Token], e
 		};
 		var_names.push(var_name.as_str());
 		*index += 1;
-		let seperator_token = get_next_token(index, contents, "'=' or ','")?;
+		let seperator_token = get_next_token(index, contents, "'=', ':' or ','")?;
 		match seperator_token {
 			RawToken::AssignmentOperator(AssignmentOperator::Equals) => {
 				*index += 1;
@@ -1123,7 +1855,17 @@ pub fn parse_statement_var_init<'a> (index: &mut usize, contents: &'a [Token], e
 				*index += 1;
 				continue;
 			}
-			_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "'=' or ','", "while parsing 'var' statement's variable name seperator").into()),
+			RawToken::Colon => {
+				*index += 1;
+				var_type = Some(parse_type(index, contents, errors, logger)?);
+				let equals_token = get_next_token(index, contents, "'='")?;
+				let RawToken::AssignmentOperator(AssignmentOperator::Equals) = equals_token else {
+					return Err(RawCompileError::new_unexpected_token(&contents[*index], "'='", "while parsing 'var' statement's variable type").into());
+				};
+				*index += 1;
+				break;
+			}
+			_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "'=', ':' or ','", "while parsing 'var' statement's variable name seperator").into()),
 		}
 	}
 
@@ -1132,6 +1874,7 @@ pub fn parse_statement_var_init<'a> (index: &mut usize, contents: &'a [Token], e
 
 	Ok(ASTStatement::VarInit {
 		var_names,
+		var_type,
 		value: formula,
 	})
 }
@@ -1161,8 +1904,7 @@ pub fn parse_statement_if<'a> (index: &mut usize, contents: &'a [Token], errors:
 		}
 
 		// statement
-		let statement = parse_statement(index, contents, errors, logger)?;
-		true_block.push(statement);
+		parse_block_statement(&mut true_block, index, contents, errors, logger)?;
 
 	};
 	*index += 1;
@@ -1178,8 +1920,7 @@ pub fn parse_statement_if<'a> (index: &mut usize, contents: &'a [Token], errors:
 			}
 
 			// statement
-			let statement = parse_statement(index, contents, errors, logger)?;
-			false_block.push(statement);
+			parse_block_statement(&mut false_block, index, contents, errors, logger)?;
 
 		}
 		*index += 1;
@@ -1200,9 +1941,15 @@ pub fn parse_statement_switch<'a> (index: &mut usize, contents: &'a [Token], err
 	// switch value
 	let switch_value = parse_formula(index, contents, errors, logger)?;
 
+	// do token, which ends the switch value so that a first case like `(true, _):` or `-1:` isn't read as part of it
+	if *get_next_token(index, contents, "'do'")? != RawToken::Name(String::from("do")) {
+		return Err(RawCompileError::new_unexpected_token(&contents[*index], "'do'", "while parsing 'switch' statement").into());
+	}
+	*index += 1;
+
 	// cases
 	let mut cases = vec!();
-	let mut default_case = None;
+	let mut has_default_case = false;
 	'switch: loop {
 
 		// ending token?
@@ -1211,13 +1958,25 @@ pub fn parse_statement_switch<'a> (index: &mut usize, contents: &'a [Token], err
 			break 'switch;
 		}
 
-		// case value
+		// patterns
 		let case_token_index = *index;
-		let case_value = parse_formula(index, contents, errors, logger)?;
-
-		// colon
-		if *get_next_token(index, contents, "':'")? != RawToken::Colon {
-			return Err(RawCompileError::new_unexpected_token(&contents[*index], "':'", "while parsing switch statement case").into());
+		let mut patterns = vec!();
+		loop {
+			patterns.push(parse_pattern(index, contents, errors, logger)?);
+			match get_next_token(index, contents, "',' or ':'")? {
+				RawToken::Comma => *index += 1,
+				RawToken::Colon => {
+					*index += 1;
+					break;
+				}
+				_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "',' or ':'", "while parsing switch statement case").into()),
+			}
+		}
+		if contents[case_token_index].token == RawToken::Name(String::from("default")) {
+			if has_default_case {
+				errors.push(RawCompileError::MultipleDefaultCases {location: contents[case_token_index].clone()}.into());
+			}
+			has_default_case = true;
 		}
 
 		// block
@@ -1231,24 +1990,181 @@ pub fn parse_statement_switch<'a> (index: &mut usize, contents: &'a [Token], err
 			}
 
 			// statement
-			let statement = parse_statement(index, contents, errors, logger)?;
-			block.push(statement);
+			parse_block_statement(&mut block, index, contents, errors, logger)?;
 
 		}
 
-		if case_value == ASTFormula::Name("default") {
-			if default_case.is_some() {
-				errors.push(RawCompileError::MultipleDefaultCases {location: contents[case_token_index].clone()}.into());
+		cases.push(ASTSwitchCase {patterns, block});
+
+	}
+
+	Ok(ASTStatement::Switch {switch_value, cases})
+}
+
+
+
+pub fn parse_pattern<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTPattern<'a>, CompileError> {
+	let first_token = get_next_token(index, contents, "[pattern]")?;
+	match first_token {
+
+		RawToken::Name(name) if name == "default" || name == "_" => {
+			*index += 1;
+			Ok(ASTPattern::Wildcard)
+		}
+		RawToken::Name(name) if is_type_name(name) => parse_pattern_variant(name, index, contents, errors, logger),
+		RawToken::Name(name) => {
+			*index += 1;
+			Ok(ASTPattern::Binding(name))
+		}
+
+		// tuple, or just parentheses around a pattern
+		RawToken::OpenParen => {
+			*index += 1;
+			let mut items = vec!();
+			let mut has_comma = false;
+			loop {
+				if *get_next_token(index, contents, "[pattern] or ')'")? == RawToken::CloseParen {
+					*index += 1;
+					break;
+				}
+				items.push(parse_pattern(index, contents, errors, logger)?);
+				match get_next_token(index, contents, "',' or ')'")? {
+					RawToken::Comma => {
+						*index += 1;
+						has_comma = true;
+					}
+					RawToken::CloseParen => {
+						*index += 1;
+						break;
+					}
+					_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "',' or ')'", "while parsing tuple pattern").into()),
+				}
 			}
-			default_case = Some(block);
-		} else {
-			cases.push((case_value, block));
+			if items.len() == 1 && !has_comma {
+				return Ok(items.pop().unwrap());
+			}
+			Ok(ASTPattern::Tuple(items))
+		}
+
+		// literals and ranges (`1..10` doesn't include 10, `1..=10` does)
+		_ => {
+			let start = parse_pattern_literal(index, contents)?;
+			let is_inclusive = match get_next_token_checked(index, contents) {
+				Some(RawToken::Operator(Operator::Concat)) => false,
+				Some(RawToken::AssignmentOperator(AssignmentOperator::Concat)) => true,
+				_ => return Ok(ASTPattern::Literal(start)),
+			};
+			*index += 1;
+			let end = parse_pattern_literal(index, contents)?;
+			Ok(ASTPattern::Range {start, end, is_inclusive})
+		}
+
+	}
+}
+
+
+
+// the value inside a `RawToken::TypedNumber`, which can be a `UInt` that only fits once it's negative (like `-9223372036854775808_int`)
+pub fn get_number_formula<'a> (token: &RawToken, is_negative: bool) -> Option<ASTFormula<'a>> {
+	Some(match (token, is_negative) {
+		(RawToken::Int(value), _) => ASTFormula::Int(if is_negative {-value} else {*value}),
+		(RawToken::UInt(value), true) => ASTFormula::Int(fns::some_if(*value == 1 << 63, || i64::MIN)?),
+		(RawToken::UInt(value), false) => ASTFormula::UInt(*value),
+		(RawToken::Float(value), _) => ASTFormula::Float(if is_negative {-value} else {*value}),
+		_ => unreachable!(),
+	})
+}
+
+
+
+pub fn parse_pattern_literal<'a> (index: &mut usize, contents: &'a [Token]) -> Result<ASTFormula<'a>, CompileError> {
+	let is_negative = get_next_token_checked(index, contents) == Some(&RawToken::Operator(Operator::Minus));
+	if is_negative {*index += 1;}
+	let token = get_next_token(index, contents, "[pattern]")?;
+	let output = match (token, is_negative) {
+		(RawToken::Int(_) | RawToken::UInt(_) | RawToken::Float(_), _) => get_number_formula(token, is_negative),
+		(RawToken::TypedNumber(value, tua_type), _) => get_number_formula(value, is_negative).map(|value| ASTFormula::TypedNumber {value: box value, tua_type: tua_type.clone()}),
+		(RawToken::Bool(value), false) => Some(ASTFormula::Bool(*value)),
+		(RawToken::String(value), false) => Some(ASTFormula::String(value)),
+		(RawToken::Char(value), false) => Some(ASTFormula::Char(*value)),
+		_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "[pattern]", "while parsing switch pattern").into()),
+	};
+	// only a `uint` that's too big to be negative can't be made negative
+	let Some(output) = output else {
+		let (value, tua_type) = match token {
+			RawToken::TypedNumber(value, tua_type) => (value.to_string(), tua_type.to_string()),
+			_ => (token.to_string(), String::from("int")),
+		};
+		return Err(RawCompileError::LiteralOutOfRange {location: contents[*index].clone(), value: format!("-{value}"), tua_type}.into());
+	};
+	*index += 1;
+	Ok(output)
+}
+
+
+
+pub fn parse_pattern_variant<'a> (name: &'a str, index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTPattern<'a>, CompileError> {
+	*index += 1;
+
+	// `Choice.Variant` or `Variant`
+	let (choice, variant) = match (get_next_token_checked(index, contents), get_next_token_checked(&(*index + 1), contents)) {
+		(Some(RawToken::Period), Some(RawToken::Name(variant))) => {
+			*index += 2;
+			(Some(name), &**variant)
+		}
+		_ => (None, name),
+	};
+
+	// payload
+	if get_next_token_checked(index, contents) != Some(&RawToken::OpenParen) {
+		return Ok(ASTPattern::Variant {choice, variant, payload: ASTPatternPayload::None});
+	}
+	*index += 1;
+	let mut items = vec!();
+	let mut feilds = vec!();
+	loop {
+
+		// close paren?
+		if *get_next_token(index, contents, "[pattern] or ')'")? == RawToken::CloseParen {
+			*index += 1;
+			break;
+		}
+
+		// named feild or pattern
+		match (get_next_token_checked(index, contents), get_next_token_checked(&(*index + 1), contents)) {
+			(Some(RawToken::Name(feild_name)), Some(RawToken::Colon)) => {
+				if !items.is_empty() {
+					return Err(RawCompileError::new_unexpected_token(&contents[*index], "[pattern]", "while parsing choice variant pattern (payloads can't mix named and unnamed feilds)").into());
+				}
+				*index += 2;
+				feilds.push((&**feild_name, parse_pattern(index, contents, errors, logger)?));
+			}
+			_ => {
+				if !feilds.is_empty() {
+					return Err(RawCompileError::new_unexpected_token(&contents[*index], "[name of feild]", "while parsing choice variant pattern (payloads can't mix named and unnamed feilds)").into());
+				}
+				items.push(parse_pattern(index, contents, errors, logger)?);
+			}
+		}
+
+		// seperator / end
+		match *get_next_token(index, contents, "',' or ')'")? {
+			RawToken::Comma => *index += 1,
+			RawToken::CloseParen => {
+				*index += 1;
+				break;
+			}
+			_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "',' or ')'", "while parsing choice variant pattern").into()),
 		}
 
 	}
 
-	logger.print_all();
-	Ok(ASTStatement::Switch {switch_value, cases, default_case})
+	let payload = match (items.is_empty(), feilds.is_empty()) {
+		(true, true) => ASTPatternPayload::None,
+		(false, _) => ASTPatternPayload::Tuple(items),
+		(_, false) => ASTPatternPayload::Feilds(feilds),
+	};
+	Ok(ASTPattern::Variant {choice, variant, payload})
 }
 
 
@@ -1256,8 +2172,65 @@ pub fn parse_statement_switch<'a> (index: &mut usize, contents: &'a [Token], err
 pub fn parse_statement_for<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTStatement<'a>, CompileError> {
 	*index += 1;
 
-	logger.print_all();
-	todo!("'for' statement");
+	// 'each' token (optional, but only if it isn't the variable name)
+	if *get_next_token(index, contents, "[name of variable] or 'each'")? == RawToken::Name(String::from("each")) {
+		match get_next_token(&(*index + 1), contents, "[name of variable], 'in', or ','")? {
+			RawToken::Name(name) if name != "in" => *index += 1,
+			_ => {}
+		}
+	}
+
+	// var names
+	let mut var_names = vec!();
+	loop {
+		let var_name_token = get_next_token(index, contents, "[name of variable]")?;
+		let RawToken::Name(var_name) = var_name_token else {
+			return Err(RawCompileError::new_unexpected_token(&contents[*index], "[name of variable]", "while parsing 'for' statement's variable names").into());
+		};
+		var_names.push(var_name.as_str());
+		*index += 1;
+		match get_next_token(index, contents, "'in' or ','")? {
+			RawToken::Name(name) if name == "in" => {
+				*index += 1;
+				break;
+			}
+			RawToken::Comma => {
+				*index += 1;
+				continue;
+			}
+			_ => return Err(RawCompileError::new_unexpected_token(&contents[*index], "'in' or ','", "while parsing 'for' statement's variable name seperator").into()),
+		}
+	}
+
+	// iter
+	let iter = parse_formula(index, contents, errors, logger)?;
+
+	// do token
+	if *get_next_token(index, contents, "'do'")? != RawToken::Name(String::from("do")) {
+		return Err(RawCompileError::new_unexpected_token(&contents[*index], "'do'", "while parsing 'for' statement").into());
+	}
+	*index += 1;
+
+	// block
+	let mut block = vec!();
+	loop {
+
+		// ending token?
+		if *get_next_token(index, contents, "[statement] or 'end'")? == RawToken::Name(String::from("end")) {
+			*index += 1;
+			break;
+		}
+
+		// statement
+		parse_block_statement(&mut block, index, contents, errors, logger)?;
+
+	}
+
+	Ok(ASTStatement::For {
+		var_names,
+		iter,
+		block,
+	})
 }
 
 
@@ -1265,8 +2238,33 @@ pub fn parse_statement_for<'a> (index: &mut usize, contents: &'a [Token], errors
 pub fn parse_statement_while<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTStatement<'a>, CompileError> {
 	*index += 1;
 
-	logger.print_all();
-	todo!("'while' statement");
+	let condition = parse_formula(index, contents, errors, logger)?;
+
+	// do token
+	if *get_next_token(index, contents, "'do'")? != RawToken::Name(String::from("do")) {
+		return Err(RawCompileError::new_unexpected_token(&contents[*index], "'do'", "while parsing 'while' statement").into());
+	}
+	*index += 1;
+
+	// block
+	let mut block = vec!();
+	loop {
+
+		// ending token?
+		if *get_next_token(index, contents, "[statement] or 'end'")? == RawToken::Name(String::from("end")) {
+			*index += 1;
+			break;
+		}
+
+		// statement
+		parse_block_statement(&mut block, index, contents, errors, logger)?;
+
+	}
+
+	Ok(ASTStatement::While {
+		condition,
+		block,
+	})
 }
 
 
@@ -1285,8 +2283,7 @@ pub fn parse_statement_loop<'a> (index: &mut usize, contents: &'a [Token], error
 		}
 
 		// statement
-		let statement = parse_statement(index, contents, errors, logger)?;
-		block.push(statement);
+		parse_block_statement(&mut block, index, contents, errors, logger)?;
 
 	}
 
@@ -1300,8 +2297,8 @@ pub fn parse_statement_loop<'a> (index: &mut usize, contents: &'a [Token], error
 pub fn parse_statement_return<'a> (index: &mut usize, contents: &'a [Token], errors: &mut Vec<CompileError>, logger: &mut Logger) -> Result<ASTStatement<'a>, CompileError> {
 	*index += 1;
 
+	// a bare `return` leaves the index on the `end` after it
 	return Ok(ASTStatement::Return {value: if *get_next_token(index, contents, "[return value] or 'end'")? == RawToken::Name(String::from("end")) {
-		*index -= 1;
 		None
 	} else {
 		Some(parse_formula(index, contents, errors, logger)?)
@@ -1320,7 +2317,10 @@ pub fn parse_statement_var_assignment_or_function_call<'a> (first_token: &'a str
 
 		RawToken::AssignmentOperator(assignment_operator) => {
 			*index += 1;
-			let value = parse_formula(index, contents, errors, logger)?;
+			let value = match assignment_operator {
+				AssignmentOperator::PlusPlus | AssignmentOperator::MinusMinus => ASTFormula::Int(1),
+				_ => parse_formula(index, contents, errors, logger)?,
+			};
 			ASTStatement::VarAssignment {
 				start_name: first_token,
 				var_queries,
@@ -1355,7 +2355,6 @@ pub fn parse_statement_var_assignment_or_function_call<'a> (first_token: &'a str
 				}
 
 			}
-			println!("end token: {:?}", contents[*index]);
 			ASTStatement::FunctionCall {
 				start_name: first_token,
 				var_queries,
@@ -1394,6 +2393,10 @@ pub fn parse_var_queries<'a> (index: &mut usize, contents: &'a [Token], errors:
 			RawToken::OpenSquareBracket => {
 				*index += 1;
 				let key = parse_formula(index, contents, errors, logger)?;
+				if *get_next_token(index, contents, "']'")? != RawToken::CloseSquareBracket {
+					return Err(RawCompileError::new_unexpected_token(&contents[*index], "']'", "while parsing index in start of statement").into());
+				}
+				*index += 1;
 				output.push(VarQuery::Index(key));
 			}
 
@@ -1403,3 +2406,166 @@ pub fn parse_var_queries<'a> (index: &mut usize, contents: &'a [Token], errors:
 
 	Ok(output)
 }
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+	use crate::prelude::*;
+
+	pub fn lex (source: &str) -> LexedTuaFile {
+		let path = Path::new("test.tua");
+		let mut logger = Logger::new("test");
+		let file = init::preprocess_tua_file(source.to_string(), path, &mut logger).unwrap();
+		let (file, errors) = lexer::lex_tua_file(file, &compiler::get_token_combinations(), path, &mut logger);
+		assert!(errors.is_empty(), "{errors:?}");
+		file
+	}
+
+	pub fn parse_errors (file: &LexedTuaFile) -> Vec<String> {
+		let (_, errors) = parser::parse_tua_file(file, Path::new("test.tua"), &mut Logger::new("test"));
+		errors.iter().map(|error| error.raw_error().to_string()).collect()
+	}
+
+	// the statements of the first function in the file
+	pub fn parse_statements (file: &LexedTuaFile) -> Vec<ASTStatement<'_>> {
+		let (parsed_file, errors) = parser::parse_tua_file(file, Path::new("test.tua"), &mut Logger::new("test"));
+		assert!(errors.is_empty(), "{errors:?}");
+		let Some(ASTDefinition::Function {statements, ..}) = parsed_file.definitions.into_iter().next() else {panic!("expected a function")};
+		statements
+	}
+
+	// the value of the first `var` statement in the first function
+	pub fn parse_var_value (file: &LexedTuaFile) -> ASTFormula<'_> {
+		let Some(ASTStatement::VarInit {value, ..}) = parse_statements(file).into_iter().next() else {panic!("expected a var statement")};
+		value
+	}
+
+	pub fn operation<'a> (operator: Operator, left: ASTFormula<'a>, right: ASTFormula<'a>) -> ASTFormula<'a> {
+		ASTFormula::Operation {operator, left: box left, right: box right}
+	}
+
+
+
+	#[test]
+	fn bare_return_before_end () {
+		let file = lex("function main\n\treturn\nend\n");
+		assert_eq!(parse_statements(&file), vec!(ASTStatement::Return {value: None}));
+	}
+
+	#[test]
+	fn return_with_value () {
+		let file = lex("function main returns int\n\treturn 5\nend\n");
+		assert_eq!(parse_statements(&file), vec!(ASTStatement::Return {value: Some(ASTFormula::Int(5))}));
+	}
+
+	#[test]
+	fn bare_return_in_if () {
+		let file = lex("function main\n\tif true then return end\n\tprint 1\nend\n");
+		let statements = parse_statements(&file);
+		assert_eq!(statements.len(), 2);
+		assert_eq!(statements[0], ASTStatement::If {condition: ASTFormula::Bool(true), true_block: vec!(ASTStatement::Return {value: None}), false_block: vec!()});
+	}
+
+	#[test]
+	fn var_init_with_several_names () {
+		let file = lex("function main\n\tvar a, b = pair\nend\n");
+		assert_eq!(parse_statements(&file), vec!(ASTStatement::VarInit {var_names: vec!("a", "b"), var_type: None, value: ASTFormula::Name("pair")}));
+	}
+
+	#[test]
+	fn switch_value_ends_with_do () {
+		let file = lex("function main\n\tswitch pair do\n\t\t(true, _):\n\t\tend\n\t\t-1:\n\t\tend\n\tend\nend\n");
+		let statements = parse_statements(&file);
+		let ASTStatement::Switch {switch_value, cases} = &statements[0] else {panic!("expected a switch statement")};
+		assert_eq!(*switch_value, ASTFormula::Name("pair"));
+		assert_eq!(cases[0].patterns, vec!(ASTPattern::Tuple(vec!(ASTPattern::Literal(ASTFormula::Bool(true)), ASTPattern::Wildcard))));
+		assert_eq!(cases[1].patterns, vec!(ASTPattern::Literal(ASTFormula::Int(-1))));
+		let file = lex("function main\n\tswitch a -\n\t\tb do\n\t\t1:\n\t\tend\n\tend\nend\n");
+		let statements = parse_statements(&file);
+		let ASTStatement::Switch {switch_value, ..} = &statements[0] else {panic!("expected a switch statement")};
+		assert_eq!(*switch_value, operation(Operator::Minus, ASTFormula::Name("a"), ASTFormula::Name("b")));
+	}
+
+	#[test]
+	fn pattern_numbers_are_negated_like_formulas () {
+		let file = lex("function main\n\tswitch x do\n\t\t-9223372036854775808:\n\t\tend\n\t\t-2.5:\n\t\tend\n\tend\nend\n");
+		let statements = parse_statements(&file);
+		let ASTStatement::Switch {cases, ..} = &statements[0] else {panic!("expected a switch statement")};
+		assert_eq!(cases[0].patterns, vec!(ASTPattern::Literal(ASTFormula::Int(i64::MIN))));
+		assert_eq!(cases[1].patterns, vec!(ASTPattern::Literal(ASTFormula::Float(-2.5))));
+		let file = lex("function main\n\tswitch x do\n\t\t-9223372036854775809:\n\t\tend\n\tend\nend\n");
+		assert_eq!(parse_errors(&file), vec!(String::from("the literal `-9223372036854775809` does not fit in the type `int`")));
+	}
+
+	#[test]
+	fn syntax_errors_poison_their_definition () {
+		let file = lex("function broken\n\tfor i in [1, 2] do\n\t\tprint i\n\tend\nend\n\nfunction main\n\tprint 1\nend\n");
+		let (parsed_file, errors) = parser::parse_tua_file(&file, Path::new("test.tua"), &mut Logger::new("test"));
+		assert_eq!(errors.len(), 1);
+		assert_eq!(parsed_file.poisoned, vec!(true, false));
+	}
+
+	#[test]
+	fn macro_property_with_colon () {
+		let file = lex("macro tag (\n\targs = [level]\n\tallowed_definition_types: [function]\n)\n");
+		let (parsed_file, errors) = parser::parse_tuam_file(&file, Path::new("test.tuam"), &mut Logger::new("test"));
+		let errors: Vec<String> = errors.iter().map(|error| error.raw_error().to_string()).collect();
+		assert_eq!(errors, vec!(String::from("expected '=', found `:`")));
+		assert_eq!(parsed_file.macros[0].allowed_definition_types, Some(vec!("function")));
+	}
+
+	#[test]
+	fn power_is_right_associative () {
+		let file = lex("function main\n\tvar x = 2^3^2\nend\n");
+		let expected = operation(Operator::Power, ASTFormula::Int(2), operation(Operator::Power, ASTFormula::Int(3), ASTFormula::Int(2)));
+		assert_eq!(parse_var_value(&file), expected);
+	}
+
+	#[test]
+	fn and_binds_tighter_than_or () {
+		let file = lex("function main\n\tvar x = a or b and c\nend\n");
+		let expected = operation(Operator::Or, ASTFormula::Name("a"), operation(Operator::And, ASTFormula::Name("b"), ASTFormula::Name("c")));
+		assert_eq!(parse_var_value(&file), expected);
+	}
+
+	#[test]
+	fn minus_is_left_associative () {
+		let file = lex("function main\n\tvar x = a - b - c\nend\n");
+		let expected = operation(Operator::Minus, operation(Operator::Minus, ASTFormula::Name("a"), ASTFormula::Name("b")), ASTFormula::Name("c"));
+		assert_eq!(parse_var_value(&file), expected);
+	}
+
+	#[test]
+	fn negative_literals_are_folded () {
+		let file = lex("function main\n\tvar x = (-5, -2.5, -9223372036854775808, -5_int_8, -x)\nend\n");
+		let expected = ASTFormula::Tuple(vec!(
+			ASTFormula::Int(-5),
+			ASTFormula::Float(-2.5),
+			ASTFormula::Int(i64::MIN),
+			ASTFormula::TypedNumber {value: box ASTFormula::Int(-5), tua_type: TuaType::Int8},
+			ASTFormula::Prefix {operator: PrefixOperator::Negate, base: box ASTFormula::Name("x")},
+		));
+		assert_eq!(parse_var_value(&file), expected);
+	}
+
+	#[test]
+	fn prefix_operators_bind_tighter_than_binary_ones () {
+		let file = lex("function main\n\tvar x = not a and -b * c\nend\n");
+		let not_a = ASTFormula::Prefix {operator: PrefixOperator::Not, base: box ASTFormula::Name("a")};
+		let negative_b = ASTFormula::Prefix {operator: PrefixOperator::Negate, base: box ASTFormula::Name("b")};
+		let expected = operation(Operator::And, not_a, operation(Operator::Times, negative_b, ASTFormula::Name("c")));
+		assert_eq!(parse_var_value(&file), expected);
+	}
+
+	#[test]
+	fn chained_comparisons_are_rejected () {
+		let file = lex("function main\n\tvar x = a < b < c\nend\n");
+		assert_eq!(parse_errors(&file), vec!(String::from("comparison operators cannot be chained, found `<` and then `<`")));
+		let file = lex("function main\n\tvar x = a < b and b < c\nend\n");
+		assert!(parse_errors(&file).is_empty());
+	}
+
+}
@@ -0,0 +1,103 @@
+use crate::prelude::*;
+use super::bytecode::ProgramData;
+
+
+
+// switch patterns with everything that doesn't matter for exhaustiveness stripped out
+#[derive(Debug, Clone)]
+pub enum SimplePattern {
+	Any,
+	// the variant index of a choice / `Optional`, or 0 for `true` and 1 for `false`
+	Constructor {index: usize, feilds: Vec<SimplePattern>},
+	// literals and ranges, which never cover a whole type on their own
+	Opaque,
+}
+
+
+
+// returns an example of a value that none of the rows match, or None if the rows cover every value
+// each row is one pattern per column, and `types` is the type of each column
+pub fn find_missing_pattern (rows: &[Vec<SimplePattern>], types: &[TuaType], data: &ProgramData, location: &Token) -> Result<Option<Vec<String>>, CompileError> {
+	let Some((first_type, other_types)) = types.split_first() else {
+		return Ok(if rows.is_empty() {Some(vec!())} else {None});
+	};
+
+	// constructors used in the first column
+	let mut used_constructors = vec!();
+	for row in rows {
+		if let SimplePattern::Constructor {index, ..} = &row[0] {
+			if !used_constructors.contains(index) {used_constructors.push(*index);}
+		}
+	}
+
+	// if every constructor is used then each one has to be checked on its own
+	let constructors = get_constructors(first_type, data, location)?;
+	if let Some(constructors) = &constructors {
+		if !constructors.is_empty() && constructors.iter().enumerate().all(|(index, _)| used_constructors.contains(&index)) {
+			for (constructor_index, (constructor_name, feild_types)) in constructors.iter().enumerate() {
+				let specialized_rows: Vec<Vec<SimplePattern>> = rows.iter()
+					.filter_map(|row| match &row[0] {
+						SimplePattern::Any => Some(vec!(SimplePattern::Any; feild_types.len())),
+						SimplePattern::Constructor {index, feilds} if *index == constructor_index => Some(feilds.clone()),
+						_ => None,
+					}.map(|mut new_row| {
+						new_row.extend_from_slice(&row[1..]);
+						new_row
+					}))
+					.collect();
+				let mut specialized_types = feild_types.clone();
+				specialized_types.extend_from_slice(other_types);
+				if let Some(mut missing) = find_missing_pattern(&specialized_rows, &specialized_types, data, location)? {
+					let missing_feilds: Vec<String> = missing.drain(..feild_types.len()).collect();
+					missing.insert(0, format_constructor(constructor_name, &missing_feilds));
+					return Ok(Some(missing));
+				}
+			}
+			return Ok(None);
+		}
+	}
+
+	// otherwise, only the rows that match anything in the first column matter
+	let default_rows: Vec<Vec<SimplePattern>> = rows.iter()
+		.filter(|row| matches!(row[0], SimplePattern::Any))
+		.map(|row| row[1..].to_vec())
+		.collect();
+	let Some(mut missing) = find_missing_pattern(&default_rows, other_types, data, location)? else {
+		return Ok(None);
+	};
+	let unused_constructor = constructors.as_ref().and_then(|constructors| {
+		constructors.iter().enumerate()
+			.find(|(index, _)| !used_constructors.contains(index))
+			.map(|(_, (name, feild_types))| format_constructor(name, &vec!(String::from("_"); feild_types.len())))
+	});
+	missing.insert(0, unused_constructor.unwrap_or_else(|| String::from("_")));
+	Ok(Some(missing))
+}
+
+
+
+// (name, feild types) for each constructor of a type, or None if it has too many to list (like ints or strings)
+fn get_constructors (tua_type: &TuaType, data: &ProgramData, location: &Token) -> Result<Option<Vec<(String, Vec<TuaType>)>>, CompileError> {
+	if *tua_type == TuaType::Bool {
+		return Ok(Some(vec!((String::from("true"), vec!()), (String::from("false"), vec!()))));
+	}
+	// tuples have one unnamed constructor, so a missing one is shown as `(_, false)`
+	if let TuaType::Tuple(item_types) = tua_type {
+		return Ok(Some(vec!((String::new(), item_types.clone()))));
+	}
+	let Some(variants) = data.get_variants(tua_type, location)? else {
+		return Ok(None);
+	};
+	Ok(Some(variants.into_iter()
+		.map(|variant| (variant.full_name, variant.feilds.into_iter().map(|(_, feild_type)| feild_type).collect()))
+		.collect()
+	))
+}
+
+fn format_constructor (name: &str, feilds: &[String]) -> String {
+	if feilds.is_empty() {
+		name.to_string()
+	} else {
+		format!("{name}({})", feilds.join(", "))
+	}
+}
@@ -0,0 +1,592 @@
+use crate::prelude::*;
+
+
+
+// free functions that are built into the interpreter
+pub const NATIVE_FUNCTIONS: &[&str] = &["read_line"];
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+	Function,
+	Object,
+	Choice,
+	TypeAlias,
+	Const,
+	Static,
+}
+
+impl SymbolKind {
+
+	pub fn get_name (&self) -> &'static str {
+		match self {
+			Self::Function  => "function",
+			Self::Object    => "object",
+			Self::Choice    => "choice",
+			Self::TypeAlias => "type",
+			Self::Const     => "const",
+			Self::Static    => "static",
+		}
+	}
+
+	pub fn is_type (&self) -> bool {
+		matches!(self, Self::Object | Self::Choice | Self::TypeAlias)
+	}
+
+}
+
+#[derive(Debug)]
+pub struct Symbol<'a> {
+	pub kind: SymbolKind,
+	pub name: &'a str,
+	// the `Person` in `Person.say_name`
+	pub associated_type: Option<&'a str>,
+	pub file_index: usize,
+	pub is_global: bool,
+	pub definition: &'a ASTDefinition<'a>,
+}
+
+#[derive(Debug, Default)]
+pub struct SymbolTable<'a> {
+	pub symbols: Vec<Symbol<'a>>,
+	// statements, consts, and statics that use names that couldn't be resolved, which lowering skips since they're already reported
+	pub rejected_statements: Vec<&'a ASTStatement<'a>>,
+	pub rejected_consts: Vec<(usize, &'a str)>,
+}
+
+
+
+
+
+impl<'a> SymbolTable<'a> {
+
+	// types share one project-wide namespace, consts and statics are per-file, and functions can be overloaded as long as their arg types differ
+	pub fn new (parsed_files: &'a [(ParsedTuaFile<'a>, &'a PathBuf)], errors: &mut Vec<CompileError>) -> Self {
+		let mut output = Self::default();
+		for (file_index, (file, path)) in parsed_files.iter().enumerate() {
+			let errors_start = errors.len();
+			for (definition, properties) in file.definitions.iter().zip(&file.properties) {
+				let Some(new_symbol) = Self::get_symbol(definition, properties, file_index) else {continue;};
+				// `any` and `any_*` are always generic types
+				if new_symbol.kind.is_type() && ASTType::is_generic_name(new_symbol.name) {
+					errors.push(RawCompileError::InvalidTypeName {location: file.locations.get_token(new_symbol.name)}.into());
+					continue;
+				}
+				let is_duplicate = output.symbols.iter().any(|symbol| Self::is_duplicate(symbol, &new_symbol));
+				if is_duplicate {
+					errors.push(RawCompileError::DuplicateName {
+						location: file.locations.get_token(new_symbol.name),
+						name: get_full_name(new_symbol.associated_type, new_symbol.name),
+						kind: new_symbol.kind.get_name().to_string(),
+					}.into());
+					continue;
+				}
+				output.symbols.push(new_symbol);
+			}
+			for error in &mut errors[errors_start..] {
+				error.set_path(path);
+			}
+		}
+		output
+	}
+
+	fn get_symbol (definition: &'a ASTDefinition<'a>, properties: &DefinitionProperties, file_index: usize) -> Option<Symbol<'a>> {
+		let (kind, name, associated_type) = match definition {
+			ASTDefinition::Function {name, associated_type, args, ..} => {
+				let has_self = args.first().map(|arg| arg.name == "self").unwrap_or(false);
+				let associated_type = associated_type.as_ref().or_else(|| fns::some_if(has_self, || &args[0].ast_type));
+				(SymbolKind::Function, *name, associated_type.map(|ast_type| ast_type.name))
+			}
+			ASTDefinition::Object {name, ..} => (SymbolKind::Object, *name, None),
+			ASTDefinition::Choice {name, ..} => (SymbolKind::Choice, *name, None),
+			ASTDefinition::Type   {name, ..} => (SymbolKind::TypeAlias, *name, None),
+			ASTDefinition::Const  {name, ..} => (SymbolKind::Const, *name, None),
+			ASTDefinition::Static {name, ..} => (SymbolKind::Static, *name, None),
+			ASTDefinition::Use {..} | ASTDefinition::GlobalUse {..} => return None,
+		};
+		if name.is_empty() {return None;}
+		let is_global = properties.get_bool("is_global");
+		Some(Symbol {kind, name, associated_type, file_index, is_global, definition})
+	}
+
+	fn is_duplicate (old: &Symbol, new: &Symbol) -> bool {
+		if old.name != new.name {return false;}
+		if old.kind.is_type() && new.kind.is_type() {return true;}
+		if old.kind != new.kind {return false;}
+		let is_same_scope = old.file_index == new.file_index || (old.is_global && new.is_global);
+		match (old.definition, new.definition) {
+			(ASTDefinition::Function {args: old_args, ..}, ASTDefinition::Function {args: new_args, ..}) => {
+				is_same_scope
+					&& old.associated_type == new.associated_type
+					&& old_args.len() == new_args.len()
+					&& old_args.iter().zip(new_args.iter()).all(|(old_arg, new_arg)| old_arg.ast_type == new_arg.ast_type)
+			}
+			_ => is_same_scope,
+		}
+	}
+
+
+
+	pub fn get_type (&self, name: &str) -> Option<&Symbol<'a>> {
+		self.symbols.iter().find(|symbol| symbol.kind.is_type() && symbol.name == name)
+	}
+
+	// consts and statics
+	pub fn get_value (&self, name: &str, file_index: usize) -> Option<&Symbol<'a>> {
+		self.symbols.iter().find(|symbol| {
+			matches!(symbol.kind, SymbolKind::Const | SymbolKind::Static)
+				&& symbol.name == name
+				&& (symbol.file_index == file_index || symbol.is_global)
+		})
+	}
+
+	pub fn has_function (&self, name: &str, file_index: usize) -> bool {
+		NATIVE_FUNCTIONS.contains(&name) || self.get_functions(file_index).any(|function_name| function_name == name)
+	}
+
+	pub fn has_associated_function (&self, type_name: &str, name: &str) -> bool {
+		self.symbols.iter().any(|symbol| symbol.kind == SymbolKind::Function && symbol.associated_type == Some(type_name) && symbol.name == name)
+	}
+
+	// names of the plain functions that can be called from a file
+	pub fn get_functions (&self, file_index: usize) -> impl Iterator<Item = &'a str> + '_ {
+		self.symbols.iter()
+			.filter(move |symbol| {
+				symbol.kind == SymbolKind::Function
+					&& symbol.associated_type.is_none()
+					&& (symbol.file_index == file_index || symbol.is_global)
+			})
+			.map(|symbol| symbol.name)
+	}
+
+	// the variants of a choice (or of a built-in type like `Optional`), or None if there's no choice with that name
+	pub fn get_variants (&self, choice_name: &str) -> Option<Vec<&'a str>> {
+		if let Some(variants) = bytecode::get_built_in_variants(choice_name) {
+			return Some(variants.to_vec());
+		}
+		let symbol = self.get_type(choice_name)?;
+		let ASTDefinition::Choice {choices, ..} = symbol.definition else {return None;};
+		Some(choices.iter().map(|variant| variant.name).collect())
+	}
+
+	pub fn is_statement_rejected (&self, statement: &ASTStatement) -> bool {
+		self.rejected_statements.iter().any(|rejected| std::ptr::eq(*rejected, statement))
+	}
+
+	fn is_const_rejected (&self, name: &str, file_index: usize) -> bool {
+		let Some(symbol) = self.get_value(name, file_index) else {return false;};
+		self.rejected_consts.contains(&(symbol.file_index, symbol.name))
+	}
+
+	pub fn get_all_variants (&self) -> Vec<&'a str> {
+		let mut output = vec!("Nothing", "Filled", "Value", "Error", "Pass", "Return");
+		for symbol in &self.symbols {
+			if let ASTDefinition::Choice {choices, ..} = symbol.definition {
+				output.extend(choices.iter().map(|variant| variant.name));
+			}
+		}
+		output
+	}
+
+}
+
+fn get_full_name (associated_type: Option<&str>, name: &str) -> String {
+	match associated_type {
+		Some(associated_type) => format!("{associated_type}.{name}"),
+		None => name.to_string(),
+	}
+}
+
+
+
+
+
+pub fn resolve_names<'a> (parsed_files: &'a [(ParsedTuaFile<'a>, &'a PathBuf)], module_graph: &ModuleGraph<'a>, errors: &mut Vec<CompileError>, logger: &mut Logger) -> SymbolTable<'a> {
+	logger.logln("");
+	logger.logln("Resolving names ...");
+	let mut symbol_table = SymbolTable::new(parsed_files, errors);
+
+	// consts and statics go first, so the statements that use one with an unknown name can be skipped too
+	let mut rejected_consts = vec!();
+	for (file_index, (file, path)) in parsed_files.iter().enumerate() {
+		let errors_start = errors.len();
+		let mut resolver = Resolver::new(&symbol_table, module_graph, file_index, &file.locations, errors);
+		for definition in &file.definitions {
+			let (ASTDefinition::Const {name, value, ..} | ASTDefinition::Static {name, value, ..}) = definition else {continue;};
+			resolver.resolve_formula(value);
+			if resolver.rejects_definition {
+				rejected_consts.push((file_index, *name));
+				resolver.rejects_definition = false;
+			}
+		}
+		for error in &mut errors[errors_start..] {
+			error.set_path(path);
+		}
+	}
+	symbol_table.rejected_consts = rejected_consts;
+
+	let mut rejected_statements = vec!();
+	for (file_index, (file, path)) in parsed_files.iter().enumerate() {
+		let errors_start = errors.len();
+		let mut resolver = Resolver::new(&symbol_table, module_graph, file_index, &file.locations, errors);
+		for (definition, is_poisoned) in file.definitions.iter().zip(&file.poisoned) {
+			let ASTDefinition::Function {args, statements, ..} = definition else {continue;};
+			if *is_poisoned {continue;}
+			resolver.scopes.push(args.iter().map(|arg| arg.name).collect());
+			resolver.resolve_block(statements);
+			resolver.scopes.pop();
+		}
+		rejected_statements.append(&mut resolver.rejected_statements);
+		for error in &mut errors[errors_start..] {
+			error.set_path(path);
+		}
+	}
+	symbol_table.rejected_statements = rejected_statements;
+
+	logger.logln("done");
+	symbol_table
+}
+
+
+
+
+
+struct Resolver<'a, 'b> {
+	symbol_table: &'b SymbolTable<'a>,
+	module_graph: &'b ModuleGraph<'a>,
+	file_index: usize,
+	locations: &'b TokenLocations<'a>,
+	scopes: Vec<Vec<&'a str>>,
+	errors: &'b mut Vec<CompileError>,
+	// the innermost statement being resolved, or None in a const or static
+	current_statement: Option<&'a ASTStatement<'a>>,
+	rejected_statements: Vec<&'a ASTStatement<'a>>,
+	rejects_definition: bool,
+}
+
+impl<'a, 'b> Resolver<'a, 'b> {
+
+	fn new (symbol_table: &'b SymbolTable<'a>, module_graph: &'b ModuleGraph<'a>, file_index: usize, locations: &'b TokenLocations<'a>, errors: &'b mut Vec<CompileError>) -> Self {
+		Self {
+			symbol_table,
+			module_graph,
+			file_index,
+			locations,
+			scopes: vec!(),
+			errors,
+			current_statement: None,
+			rejected_statements: vec!(),
+			rejects_definition: false,
+		}
+	}
+
+
+
+	fn resolve_block (&mut self, block: &'a [ASTStatement<'a>]) {
+		self.scopes.push(vec!());
+		for statement in block {
+			let outer_statement = self.current_statement.replace(statement);
+			self.resolve_statement(statement);
+			self.current_statement = outer_statement;
+		}
+		self.scopes.pop();
+	}
+
+	fn resolve_statement (&mut self, statement: &'a ASTStatement<'a>) {
+		match statement {
+
+			ASTStatement::Print {value} |
+			ASTStatement::Throw {value} |
+			ASTStatement::Crash {message: value} |
+			ASTStatement::Assert {condition: value} |
+			ASTStatement::Todo {message: value} => self.resolve_formula(value),
+
+			ASTStatement::VarInit {var_names, value, ..} => {
+				self.resolve_formula(value);
+				self.scopes.last_mut().unwrap().extend(var_names.iter().copied());
+			}
+
+			ASTStatement::VarAssignment {start_name, var_queries, value, ..} => {
+				if !self.is_var(start_name) && self.symbol_table.get_value(start_name, self.file_index).is_none() {
+					self.push_undefined_name(start_name);
+				}
+				self.resolve_var_queries(var_queries);
+				self.resolve_formula(value);
+			}
+
+			ASTStatement::If {condition, true_block, false_block} => {
+				self.resolve_formula(condition);
+				self.resolve_block(true_block);
+				self.resolve_block(false_block);
+			}
+
+			ASTStatement::Switch {switch_value, cases} => {
+				self.resolve_formula(switch_value);
+				for case in cases {
+					self.scopes.push(vec!());
+					for pattern in &case.patterns {
+						self.resolve_pattern(pattern);
+					}
+					self.resolve_block(&case.block);
+					self.scopes.pop();
+				}
+			}
+
+			ASTStatement::For {var_names, iter, block} => {
+				self.resolve_formula(iter);
+				self.scopes.push(var_names.clone());
+				self.resolve_block(block);
+				self.scopes.pop();
+			}
+
+			ASTStatement::While {condition, block} => {
+				self.resolve_formula(condition);
+				self.resolve_block(block);
+			}
+
+			ASTStatement::Loop {block} => self.resolve_block(block),
+
+			ASTStatement::Break | ASTStatement::Continue => {}
+
+			ASTStatement::FunctionCall {start_name, var_queries, args} => {
+				match &**var_queries {
+					[] => self.resolve_function_name(start_name),
+					[VarQuery::Feild(key)] if !self.is_var(start_name) => self.resolve_qualified_call(start_name, key, None),
+					_ => {
+						if !self.is_var(start_name) {
+							self.push_undefined_name(start_name);
+						}
+						self.resolve_var_queries(var_queries);
+					}
+				}
+				for arg in args {
+					self.resolve_formula(arg);
+				}
+			}
+
+			ASTStatement::Return {value} => {
+				if let Some(value) = value {
+					self.resolve_formula(value);
+				}
+			}
+
+		}
+	}
+
+	fn resolve_var_queries (&mut self, var_queries: &'a [VarQuery<'a>]) {
+		for var_query in var_queries {
+			if let VarQuery::Index(key) = var_query {
+				self.resolve_formula(key);
+			}
+		}
+	}
+
+	fn resolve_pattern (&mut self, pattern: &'a ASTPattern<'a>) {
+		match pattern {
+			ASTPattern::Wildcard | ASTPattern::Literal (_) | ASTPattern::Range {..} => {}
+			ASTPattern::Binding (name) => self.scopes.last_mut().unwrap().push(name),
+			ASTPattern::Variant {choice, variant, payload} => {
+				self.resolve_variant_name(*choice, variant);
+				match payload {
+					ASTPatternPayload::None => {}
+					ASTPatternPayload::Tuple (items) => items.iter().for_each(|item| self.resolve_pattern(item)),
+					ASTPatternPayload::Feilds (feilds) => feilds.iter().for_each(|(_, feild)| self.resolve_pattern(feild)),
+				}
+			}
+			ASTPattern::Tuple (items) => items.iter().for_each(|item| self.resolve_pattern(item)),
+		}
+	}
+
+
+
+	fn resolve_formula (&mut self, formula: &'a ASTFormula<'a>) {
+		match formula {
+
+			ASTFormula::Name (name) => {
+				let is_defined =
+					self.is_var(name)
+					|| self.symbol_table.get_value(name, self.file_index).is_some()
+					|| self.symbol_table.has_function(name, self.file_index)
+					|| self.symbol_table.get_all_variants().contains(name);
+				if !is_defined {
+					self.push_undefined_name(name);
+				} else if !self.is_var(name) && self.symbol_table.is_const_rejected(name, self.file_index) {
+					self.reject_current();
+				}
+			}
+
+			ASTFormula::Int (_) | ASTFormula::UInt (_) | ASTFormula::Float (_) | ASTFormula::TypedNumber {..} | ASTFormula::Bool (_) | ASTFormula::String (_) | ASTFormula::Char (_) => {}
+
+			ASTFormula::Tuple (items) => items.iter().for_each(|item| self.resolve_formula(item)),
+
+			ASTFormula::Operation {left, right, ..} => {
+				self.resolve_formula(left);
+				self.resolve_formula(right);
+			}
+
+			ASTFormula::New {name, feilds} => {
+				match self.symbol_table.get_type(name).map(|symbol| symbol.definition) {
+					Some(ASTDefinition::Object {feilds: object_feilds, ..}) => {
+						for (feild_name, _) in feilds {
+							if !object_feilds.iter().any(|feild| feild.name == *feild_name) {
+								self.push_error(RawCompileError::UnknownFeild {location: self.locations.get_token(feild_name), type_name: name.to_string(), feild: feild_name.to_string()}.into());
+							}
+						}
+					}
+					_ => self.push_error(RawCompileError::UnknownType {location: self.locations.get_token(name), name: name.to_string()}.into()),
+				}
+				feilds.iter().for_each(|(_, value)| self.resolve_formula(value));
+			}
+
+			ASTFormula::ChoiceVariant {choice, variant, payload} => {
+				self.resolve_variant_name(*choice, variant);
+				match payload {
+					ASTVariantPayload::None => {}
+					ASTVariantPayload::Tuple (values) => values.iter().for_each(|value| self.resolve_formula(value)),
+					ASTVariantPayload::Feilds (feilds) => feilds.iter().for_each(|(_, value)| self.resolve_formula(value)),
+				}
+			}
+
+			ASTFormula::Prefix {base, ..} | ASTFormula::As {base, ..} | ASTFormula::ReturnTest {base} => self.resolve_formula(base),
+
+			ASTFormula::IndexQuery {base, key} => {
+				self.resolve_formula(base);
+				self.resolve_formula(key);
+			}
+
+			// `module.CONST`, otherwise the feild can only be checked once types are known
+			ASTFormula::PropertyQuery {base, key} => {
+				if let ASTFormula::Name(name) = &**base {
+					if !self.is_var(name) {
+						if let Some(module_file_index) = self.get_module_file_index(name) {
+							if self.symbol_table.get_value(key, module_file_index).is_none() {
+								self.push_undefined_name_with(key, format!("{name}.{key}"), None);
+							} else if self.symbol_table.is_const_rejected(key, module_file_index) {
+								self.reject_current();
+							}
+							return;
+						}
+					}
+				}
+				self.resolve_formula(base);
+			}
+
+			ASTFormula::FunctionCall {base, args, ..} => {
+				match &**base {
+					ASTFormula::Name (name) => self.resolve_function_name(name),
+					ASTFormula::PropertyQuery {base: receiver, key} => match &**receiver {
+						ASTFormula::Name (name) if !self.is_var(name) => self.resolve_qualified_call(name, key, Some(receiver)),
+						_ => self.resolve_formula(receiver),
+					},
+					_ => self.resolve_formula(base),
+				}
+				args.iter().for_each(|arg| self.resolve_formula(arg));
+			}
+
+			ASTFormula::Function {args, statements, ..} => {
+				self.scopes.push(args.iter().map(|(name, _)| *name).collect());
+				self.resolve_block(statements);
+				self.scopes.pop();
+			}
+
+		}
+	}
+
+
+
+	fn resolve_function_name (&mut self, name: &'a str) {
+		if self.is_var(name) || self.symbol_table.has_function(name, self.file_index) {return;}
+		let candidates: Vec<&str> = self.symbol_table.get_functions(self.file_index).chain(NATIVE_FUNCTIONS.iter().copied()).collect();
+		let suggestion = fns::get_closest_name(name, candidates);
+		self.push_undefined_name_with(name, name.to_string(), suggestion);
+	}
+
+	// `module.function()`, `Type.function()`, or a method call on a value
+	fn resolve_qualified_call (&mut self, name: &'a str, key: &'a str, receiver: Option<&'a ASTFormula<'a>>) {
+		if let Some(module_file_index) = self.get_module_file_index(name) {
+			if !self.symbol_table.has_function(key, module_file_index) {
+				let suggestion = fns::get_closest_name(key, self.symbol_table.get_functions(module_file_index));
+				self.push_undefined_name_with(key, format!("{name}.{key}"), suggestion);
+			}
+			return;
+		}
+		if self.symbol_table.get_type(name).is_some() {
+			if !self.symbol_table.has_associated_function(name, key) {
+				self.push_undefined_name_with(key, format!("{name}.{key}"), None);
+			}
+			return;
+		}
+		match receiver {
+			Some(receiver) => self.resolve_formula(receiver),
+			None => self.push_undefined_name(name),
+		}
+	}
+
+	fn resolve_variant_name (&mut self, choice: Option<&'a str>, variant: &'a str) {
+		let Some(choice) = choice else {
+			if !self.symbol_table.get_all_variants().contains(&variant) {
+				let suggestion = fns::get_closest_name(variant, self.symbol_table.get_all_variants());
+				self.push_undefined_name_with(variant, variant.to_string(), suggestion);
+			}
+			return;
+		};
+		let Some(variants) = self.symbol_table.get_variants(choice) else {
+			self.push_error(RawCompileError::UnknownType {location: self.locations.get_token(choice), name: choice.to_string()}.into());
+			return;
+		};
+		if !variants.contains(&variant) {
+			let suggestion = fns::get_closest_name(variant, variants);
+			self.push_undefined_name_with(variant, format!("{choice}.{variant}"), suggestion);
+		}
+	}
+
+
+
+	fn is_var (&self, name: &str) -> bool {
+		self.scopes.iter().any(|scope| scope.contains(&name))
+	}
+
+	fn get_module_file_index (&self, name: &str) -> Option<usize> {
+		self.module_graph.imports.get(self.file_index)?.iter()
+			.find(|import| import.name == name)
+			.map(|import| import.file_index)
+	}
+
+	fn push_undefined_name (&mut self, name: &'a str) {
+		let mut candidates: Vec<&str> = self.scopes.iter().flatten().copied().collect();
+		candidates.extend(self.symbol_table.get_functions(self.file_index));
+		candidates.extend(
+			self.symbol_table.symbols.iter()
+				.filter(|symbol| matches!(symbol.kind, SymbolKind::Const | SymbolKind::Static) && (symbol.file_index == self.file_index || symbol.is_global))
+				.map(|symbol| symbol.name)
+		);
+		let suggestion = fns::get_closest_name(name, candidates);
+		self.push_undefined_name_with(name, name.to_string(), suggestion);
+	}
+
+	fn push_undefined_name_with (&mut self, location_text: &str, name: String, suggestion: Option<&str>) {
+		self.push_error(RawCompileError::UndefinedName {
+			location: self.locations.get_token(location_text),
+			name,
+			suggestion: suggestion.map(str::to_string),
+		}.into());
+	}
+
+	fn push_error (&mut self, error: CompileError) {
+		self.errors.push(error);
+		self.reject_current();
+	}
+
+	fn reject_current (&mut self) {
+		match self.current_statement {
+			Some(statement) => if !self.rejected_statements.iter().any(|rejected| std::ptr::eq(*rejected, statement)) {
+				self.rejected_statements.push(statement);
+			},
+			None => self.rejects_definition = true,
+		}
+	}
+
+
+
+}
@@ -1,5 +1,5 @@
 use crate::prelude::*;
-use std::{rc::Rc, cell::RefCell};
+use std::{rc::Rc, cell::RefCell, collections::HashMap};
 
 
 
@@ -26,11 +26,120 @@ pub struct Environment {
     pub bools: VarStack<bool>,
 
     pub strings: VarStack<Rc<RefCell<String>>>,
-    pub arrays: VarStack<Rc<RefCell<Vec<usize>>>>,
+    pub arrays: VarStack<Rc<RefCell<Vec<Value>>>>,
+    pub objects: VarStack<Rc<RefCell<ObjectValue>>>,
+    pub functions: VarStack<Rc<ClosureValue>>,
+    pub tuples: VarStack<Rc<Vec<Value>>>,
     //pub hashmaps: Values<UnsafeRc<HashMap<usize, usize>>>,
 
 }
 
+impl Environment {
+
+    pub fn new() -> Self {
+        Self {
+            int_8s: VarStack::new(),
+            int_16s: VarStack::new(),
+            int_32s: VarStack::new(),
+            int_64s: VarStack::new(),
+            uint_8s: VarStack::new(),
+            uint_16s: VarStack::new(),
+            uint_32s: VarStack::new(),
+            uint_64s: VarStack::new(),
+            float_32s: VarStack::new(),
+            float_64s: VarStack::new(),
+            bools: VarStack::new(),
+            strings: VarStack::new(),
+            arrays: VarStack::new(),
+            objects: VarStack::new(),
+            functions: VarStack::new(),
+            tuples: VarStack::new(),
+        }
+    }
+
+    pub fn push_frame (&mut self, frame_sizes: &[usize; StackType::COUNT]) {
+        self.int_8s.push_frame(frame_sizes[StackType::Int8 as usize]);
+        self.int_16s.push_frame(frame_sizes[StackType::Int16 as usize]);
+        self.int_32s.push_frame(frame_sizes[StackType::Int32 as usize]);
+        self.int_64s.push_frame(frame_sizes[StackType::Int64 as usize]);
+        self.uint_8s.push_frame(frame_sizes[StackType::UInt8 as usize]);
+        self.uint_16s.push_frame(frame_sizes[StackType::UInt16 as usize]);
+        self.uint_32s.push_frame(frame_sizes[StackType::UInt32 as usize]);
+        self.uint_64s.push_frame(frame_sizes[StackType::UInt64 as usize]);
+        self.float_32s.push_frame(frame_sizes[StackType::Float32 as usize]);
+        self.float_64s.push_frame(frame_sizes[StackType::Float64 as usize]);
+        self.bools.push_frame(frame_sizes[StackType::Bool as usize]);
+        self.strings.push_frame(frame_sizes[StackType::String as usize]);
+        self.arrays.push_frame(frame_sizes[StackType::Array as usize]);
+        self.objects.push_frame(frame_sizes[StackType::Object as usize]);
+        self.functions.push_frame(frame_sizes[StackType::Function as usize]);
+        self.tuples.push_frame(frame_sizes[StackType::Tuple as usize]);
+    }
+
+    pub fn pop_frame (&mut self) {
+        self.int_8s.pop_frame();
+        self.int_16s.pop_frame();
+        self.int_32s.pop_frame();
+        self.int_64s.pop_frame();
+        self.uint_8s.pop_frame();
+        self.uint_16s.pop_frame();
+        self.uint_32s.pop_frame();
+        self.uint_64s.pop_frame();
+        self.float_32s.pop_frame();
+        self.float_64s.pop_frame();
+        self.bools.pop_frame();
+        self.strings.pop_frame();
+        self.arrays.pop_frame();
+        self.objects.pop_frame();
+        self.functions.pop_frame();
+        self.tuples.pop_frame();
+    }
+
+    pub fn get_value (&self, slot: Slot) -> Value {
+        match slot.stack {
+            StackType::Int8    => Value::Int8    (*self.int_8s.get(slot.index)),
+            StackType::Int16   => Value::Int16   (*self.int_16s.get(slot.index)),
+            StackType::Int32   => Value::Int32   (*self.int_32s.get(slot.index)),
+            StackType::Int64   => Value::Int64   (*self.int_64s.get(slot.index)),
+            StackType::UInt8   => Value::UInt8   (*self.uint_8s.get(slot.index)),
+            StackType::UInt16  => Value::UInt16  (*self.uint_16s.get(slot.index)),
+            StackType::UInt32  => Value::UInt32  (*self.uint_32s.get(slot.index)),
+            StackType::UInt64  => Value::UInt64  (*self.uint_64s.get(slot.index)),
+            StackType::Float32 => Value::Float32 (*self.float_32s.get(slot.index)),
+            StackType::Float64 => Value::Float64 (*self.float_64s.get(slot.index)),
+            StackType::Bool    => Value::Bool    (*self.bools.get(slot.index)),
+            StackType::String  => Value::String  (self.strings.get(slot.index).clone()),
+            StackType::Array   => Value::Array   (self.arrays.get(slot.index).clone()),
+            StackType::Object  => Value::Object  (self.objects.get(slot.index).clone()),
+            StackType::Function => Value::Function (self.functions.get(slot.index).clone()),
+            StackType::Tuple   => Value::Tuple   (self.tuples.get(slot.index).clone()),
+        }
+    }
+
+    pub fn set_value (&mut self, slot: Slot, value: Value) {
+        match value {
+            Value::Int8    (value) => self.int_8s.set(slot.index, value),
+            Value::Int16   (value) => self.int_16s.set(slot.index, value),
+            Value::Int32   (value) => self.int_32s.set(slot.index, value),
+            Value::Int64   (value) => self.int_64s.set(slot.index, value),
+            Value::UInt8   (value) => self.uint_8s.set(slot.index, value),
+            Value::UInt16  (value) => self.uint_16s.set(slot.index, value),
+            Value::UInt32  (value) => self.uint_32s.set(slot.index, value),
+            Value::UInt64  (value) => self.uint_64s.set(slot.index, value),
+            Value::Float32 (value) => self.float_32s.set(slot.index, value),
+            Value::Float64 (value) => self.float_64s.set(slot.index, value),
+            Value::Bool    (value) => self.bools.set(slot.index, value),
+            Value::String  (value) => self.strings.set(slot.index, value),
+            Value::Array   (value) => self.arrays.set(slot.index, value),
+            Value::Object  (value) => self.objects.set(slot.index, value),
+            Value::Function (value) => self.functions.set(slot.index, value),
+            Value::Tuple   (value) => self.tuples.set(slot.index, value),
+            Value::None => {}
+        }
+    }
+
+}
+
 
 
 #[derive(Debug)]
@@ -39,6 +148,234 @@ pub struct VarStack<T> {
     stack_starts: Vec<usize>,
 }
 
+impl<T: Clone + Default> VarStack<T> {
+
+    pub fn new() -> Self {
+        Self {
+            values: vec!(),
+            stack_starts: vec!(),
+        }
+    }
+
+    pub fn push_frame (&mut self, size: usize) {
+        let stack_start = self.values.len();
+        self.stack_starts.push(stack_start);
+        self.values.resize(stack_start + size, T::default());
+    }
+
+    pub fn pop_frame (&mut self) {
+        let stack_start = self.stack_starts.pop().expect("attempted to pop a frame from an empty VarStack");
+        self.values.truncate(stack_start);
+    }
+
+    pub fn get (&self, index: usize) -> &T {
+        &self.values[self.stack_starts.last().unwrap() + index]
+    }
+
+    pub fn get_mut (&mut self, index: usize) -> &mut T {
+        &mut self.values[self.stack_starts.last().unwrap() + index]
+    }
+
+    pub fn set (&mut self, index: usize, value: T) {
+        *self.get_mut(index) = value;
+    }
+
+}
+
+
+
+// values that are stored outside of the typed stacks (array items, object feilds, function args, etc)
+#[derive(Debug, Clone, Default)]
+pub enum Value {
+    Int8 (i8),
+    Int16 (i16),
+    Int32 (i32),
+    Int64 (i64),
+    UInt8 (u8),
+    UInt16 (u16),
+    UInt32 (u32),
+    UInt64 (u64),
+    Float32 (f32),
+    Float64 (f64),
+    Bool (bool),
+    String (Rc<RefCell<String>>),
+    Array (Rc<RefCell<Vec<Value>>>),
+    Object (Rc<RefCell<ObjectValue>>),
+    Function (Rc<ClosureValue>),
+    Tuple (Rc<Vec<Value>>),
+    #[default]
+    None,
+}
+
+impl PartialEq for Value {
+    fn eq (&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Int8    (a), Self::Int8    (b)) => a == b,
+            (Self::Int16   (a), Self::Int16   (b)) => a == b,
+            (Self::Int32   (a), Self::Int32   (b)) => a == b,
+            (Self::Int64   (a), Self::Int64   (b)) => a == b,
+            (Self::UInt8   (a), Self::UInt8   (b)) => a == b,
+            (Self::UInt16  (a), Self::UInt16  (b)) => a == b,
+            (Self::UInt32  (a), Self::UInt32  (b)) => a == b,
+            (Self::UInt64  (a), Self::UInt64  (b)) => a == b,
+            (Self::Float32 (a), Self::Float32 (b)) => a == b,
+            (Self::Float64 (a), Self::Float64 (b)) => a == b,
+            (Self::Bool    (a), Self::Bool    (b)) => a == b,
+            (Self::String  (a), Self::String  (b)) => *a.borrow() == *b.borrow(),
+            (Self::Array   (a), Self::Array   (b)) => Rc::ptr_eq(a, b) || *a.borrow() == *b.borrow(),
+            (Self::Object  (a), Self::Object  (b)) => Rc::ptr_eq(a, b) || *a.borrow() == *b.borrow(),
+            (Self::Function (a), Self::Function (b)) => Rc::ptr_eq(a, b),
+            (Self::Tuple   (a), Self::Tuple   (b)) => a == b,
+            (Self::None, Self::None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Value {
+    pub fn new_string (string: impl Into<String>) -> Self {
+        Self::String(Rc::new(RefCell::new(string.into())))
+    }
+}
+
+// objects, choices, and Optional values (objects always have variant 0)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjectValue {
+    pub type_id: usize,
+    pub variant: usize,
+    pub feilds: Vec<Value>,
+}
+
+// an anonymous function and the values it captured when it was created, which are given to it before its args
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClosureValue {
+    pub function: usize,
+    pub captures: Vec<Value>,
+}
+
+
+
+
+
+#[derive(Debug)]
+pub struct Program {
+    pub functions: Vec<BytecodeFunction>,
+    pub types: Vec<RuntimeTypeData>,
+    pub entries: Vec<usize>,
+}
+
+#[derive(Debug)]
+pub struct BytecodeFunction {
+    pub name: String,
+    pub arg_types: Vec<TuaType>,
+    pub arg_slots: Vec<Slot>,
+    pub return_type: TuaType,
+    pub frame_sizes: [usize; StackType::COUNT],
+    pub instructions: Vec<Instruction>,
+}
+
+#[derive(Debug)]
+pub struct RuntimeTypeData {
+    pub name: String,
+    pub feild_names: Vec<String>,
+    pub variant_names: Vec<String>,
+    // payload feild names for each variant of a choice, tuple payloads use their indices as names
+    pub variant_feild_names: Vec<Vec<String>>,
+}
+
+// which functions to compile to bytecode
+#[derive(Debug)]
+pub enum EntryPoints {
+    None,
+    Function (String),
+    Tests,
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slot {
+    pub stack: StackType,
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StackType {
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    Float32,
+    Float64,
+    Bool,
+    String,
+    Array,
+    Object,
+    Function,
+    Tuple,
+}
+
+impl StackType {
+    pub const COUNT: usize = 16;
+}
+
+
+
+#[derive(Debug)]
+pub enum Instruction {
+
+    LoadConst {value: Value, output: Slot},
+    Copy {from: Slot, to: Slot},
+    Operation {operator: Operator, left: Slot, right: Slot, output: Slot},
+    Not {base: Slot, output: Slot},
+    Negate {base: Slot, output: Slot},
+    Cast {base: Slot, output: Slot},
+    ToString {base: Slot, output: Slot},
+
+    Jump {target: usize},
+    JumpIfFalse {condition: Slot, target: usize},
+    JumpIfTrue {condition: Slot, target: usize},
+    Call {function: usize, args: Vec<Slot>, output: Option<Slot>},
+    CallClosure {closure: Slot, args: Vec<Slot>, output: Option<Slot>},
+    CallNative {native: NativeFunction, args: Vec<Slot>, output: Option<Slot>},
+    Return {value: Option<Slot>},
+
+    Print {value: Slot},
+    Crash {message: Slot},
+    Assert {condition: Slot},
+    Todo {message: Slot},
+
+    NewObject {type_id: usize, variant: usize, feilds: Vec<Slot>, output: Slot},
+    NewClosure {function: usize, captures: Vec<Slot>, output: Slot},
+    NewTuple {items: Vec<Slot>, output: Slot},
+    IsVariant {base: Slot, variant: usize, output: Slot},
+    GetFeild {base: Slot, feild: usize, output: Slot},
+    SetFeild {base: Slot, feild: usize, value: Slot},
+    GetIndex {base: Slot, key: Slot, output: Slot},
+    SetIndex {base: Slot, key: Slot, value: Slot},
+
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NativeFunction {
+    ReadLine,
+    Abs,
+    StringLength,
+    StringTrim,
+    StringToUpper,
+    StringToLower,
+    StringContains,
+    StringSplit,
+    StringToUInt,
+    ArrayLength,
+    ArrayAdd,
+    ArrayContains,
+}
+
 
 
 
@@ -146,6 +483,8 @@ pub enum RawBasicToken {
     FormattedString {start: String, items: Vec<(Vec<BasicToken>, String)>},
     Char (char),
     Special (String),
+    // numbers are scanned by the lexer, so they're already finished tokens
+    Number (RawToken),
 }
 
 
@@ -222,11 +561,14 @@ pub enum RawToken {
     Int (i64),
     UInt (u64),
     Float (f64),
+    // a number with a type suffix, like `255_uint_8`
+    TypedNumber (Box<RawToken>, TuaType),
     Bool (bool),
     String (String),
     FormattedString {start: String, items: Vec<(Vec<Token>, String)>},
     Char (char),
     Operator (Operator),
+    PrefixOperator (PrefixOperator),
     AssignmentOperator (AssignmentOperator),
     OpenParen,
     CloseParen,
@@ -263,34 +605,82 @@ pub enum Operator {
     And,
     Or,
     Xor,
-    Not,
     As,
 }
 
 impl Operator {
-    pub const MAX_EVAL_LEVEL: usize = 7;
-    pub fn get_eval_level(&self) -> usize {
+    // higher binds tighter, `as` is parsed with the formula item it's after so it doesn't have one
+    pub fn get_precedence (&self) -> Option<usize> {
+        Some(match self {
+            Self::Or             => 1,
+            Self::Xor            => 2,
+            Self::And            => 3,
+            Self::Equal          => 4,
+            Self::NotEqual       => 4,
+            Self::GreaterThan    => 4,
+            Self::LessThan       => 4,
+            Self::GreaterOrEqual => 4,
+            Self::LessOrEqual    => 4,
+            Self::ShiftLeft      => 5,
+            Self::ShiftRight     => 5,
+            Self::Concat         => 6,
+            Self::Plus           => 7,
+            Self::Minus          => 7,
+            Self::Times          => 8,
+            Self::Divide         => 8,
+            Self::Modulo         => 9,
+            Self::Power          => 10,
+            Self::As             => return None,
+        })
+    }
+
+    // `2^3^2` is `2^(3^2)`, everything else goes left to right
+    pub fn is_right_associative (&self) -> bool {
+        *self == Self::Power
+    }
+
+    // comparisons can't be chained, since `a < b < c` would compare a bool to `c`
+    pub fn is_comparison (&self) -> bool {
+        matches!(self, Self::Equal | Self::NotEqual | Self::GreaterThan | Self::LessThan | Self::GreaterOrEqual | Self::LessOrEqual)
+    }
+
+    // objects and choices use these operators by defining associated functions, like `function Vec2.add (self, other: Vec2) returns Vec2`
+    // `equals` returns a bool and also gives `!=`, and `compare` returns an int that's negative, zero, or positive for `<`, `==`, or `>`
+    pub fn get_function_name (&self) -> Option<&'static str> {
+        Some(match self {
+            Self::Plus           => "add",
+            Self::Minus          => "sub",
+            Self::Times          => "mul",
+            Self::Divide         => "div",
+            Self::Power          => "pow",
+            Self::Modulo         => "rem",
+            Self::Concat         => "concat",
+            Self::Equal          => "equals",
+            Self::NotEqual       => "equals",
+            Self::GreaterThan    => "compare",
+            Self::LessThan       => "compare",
+            Self::GreaterOrEqual => "compare",
+            Self::LessOrEqual    => "compare",
+            _ => return None,
+        })
+    }
+}
+
+
+
+// operators that go before a single value, which they're applied to before any other operators
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrefixOperator {
+    Negate,
+    Not,
+}
+
+impl PrefixOperator {
+    // objects and choices can be negated by defining `function Vec2.neg (self) returns Vec2`
+    pub fn get_function_name (&self) -> Option<&'static str> {
         match self {
-            Self::Plus           => 3,
-            Self::Minus          => 3,
-            Self::Times          => 4,
-            Self::Divide         => 4,
-            Self::Power          => 6,
-            Self::Modulo         => 5,
-            Self::Concat         => 2,
-            Self::Equal          => 1,
-            Self::NotEqual       => 1,
-            Self::GreaterThan    => 1,
-            Self::LessThan       => 1,
-            Self::GreaterOrEqual => 1,
-            Self::LessOrEqual    => 1,
-            Self::ShiftLeft      => 1,
-            Self::ShiftRight     => 1,
-            Self::And            => 0,
-            Self::Or             => 0,
-            Self::Xor            => 0,
-            Self::Not            => 0,
-            Self::As             => 7,
+            Self::Negate => Some("neg"),
+            Self::Not    => None,
         }
     }
 }
@@ -318,12 +708,106 @@ pub enum AssignmentOperator {
 
 
 impl RawToken {
+
     pub fn as_name (&self) -> Option<&str> {
         match self {
             Self::Name(name) => Some(name),
             _ => None,
         }
     }
+
+    // roughly how many chars the token took up in the source, used to underline it in errors
+    pub fn get_source_len (&self) -> usize {
+        match self {
+            Self::FormattedString {..} => 2,
+            _ => self.to_string().chars().count(),
+        }
+    }
+
+}
+
+impl Display for RawToken {
+    fn fmt (&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name (name) => write!(fmt, "{name}"),
+            Self::Int (value) => write!(fmt, "{value}"),
+            Self::UInt (value) => write!(fmt, "{value}"),
+            Self::Float (value) => write!(fmt, "{value}"),
+            Self::TypedNumber (value, tua_type) => write!(fmt, "{value}_{tua_type}"),
+            Self::Bool (value) => write!(fmt, "{value}"),
+            Self::String (value) => write!(fmt, "{value:?}"),
+            Self::FormattedString {..} => write!(fmt, "#\"...\""),
+            Self::Char (value) => write!(fmt, "{value:?}"),
+            Self::Operator (operator) => write!(fmt, "{operator}"),
+            Self::PrefixOperator (operator) => write!(fmt, "{operator}"),
+            Self::AssignmentOperator (operator) => write!(fmt, "{operator}"),
+            Self::OpenParen => write!(fmt, "("),
+            Self::CloseParen => write!(fmt, ")"),
+            Self::OpenSquareBracket => write!(fmt, "["),
+            Self::CloseSquareBracket => write!(fmt, "]"),
+            Self::OpenCurlyBracket => write!(fmt, "{{"),
+            Self::CloseCurlyBracket => write!(fmt, "}}"),
+            Self::Period => write!(fmt, "."),
+            Self::Comma => write!(fmt, ","),
+            Self::QuestionMark => write!(fmt, "?"),
+            Self::Colon => write!(fmt, ":"),
+            Self::Octothorp => write!(fmt, "#"),
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt (&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}", match self {
+            Self::Plus           => "+",
+            Self::Minus          => "-",
+            Self::Times          => "*",
+            Self::Divide         => "/",
+            Self::Power          => "^",
+            Self::Modulo         => "%",
+            Self::Concat         => "..",
+            Self::Equal          => "==",
+            Self::NotEqual       => "!=",
+            Self::GreaterThan    => ">",
+            Self::LessThan       => "<",
+            Self::GreaterOrEqual => ">=",
+            Self::LessOrEqual    => "<=",
+            Self::ShiftLeft      => "<<",
+            Self::ShiftRight     => ">>",
+            Self::And            => "and",
+            Self::Or             => "or",
+            Self::Xor            => "xor",
+            Self::As             => "as",
+        })
+    }
+}
+
+impl Display for PrefixOperator {
+    fn fmt (&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}", match self {
+            Self::Negate => "-",
+            Self::Not    => "not",
+        })
+    }
+}
+
+impl Display for AssignmentOperator {
+    fn fmt (&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}", match self {
+            Self::Equals     => "=",
+            Self::Plus       => "+=",
+            Self::Minus      => "-=",
+            Self::Times      => "*=",
+            Self::Divide     => "/=",
+            Self::Modulo     => "%=",
+            Self::Concat     => "..=",
+            Self::ShiftLeft  => "<<=",
+            Self::ShiftRight => ">>=",
+            Self::Call       => ".=",
+            Self::PlusPlus   => "++",
+            Self::MinusMinus => "--",
+        })
+    }
 }
 
 
@@ -338,11 +822,90 @@ impl RawToken {
 #[derive(Debug, Default)]
 pub struct ParsedTuaFile<'a> {
     pub definitions: Vec<ASTDefinition<'a>>,
+    // what the macros used on each definition set, filled in once macros are expanded
+    pub properties: Vec<DefinitionProperties<'a>>,
+    // whether each definition had syntax errors, the statements that were skipped would cause errors of their own if it was checked
+    pub poisoned: Vec<bool>,
+    pub locations: TokenLocations<'a>,
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedMacroFile<'a> {
+    pub macros: Vec<ASTMacro<'a>>,
+    pub locations: TokenLocations<'a>,
+}
+
+
+
+// every name and string in the AST is borrowed from a token, so the address of a `&str` can be used to find where it came from
+#[derive(Debug, Default)]
+pub struct TokenLocations<'a> {
+    tokens_by_address: HashMap<usize, &'a Token>,
+}
+
+impl<'a> TokenLocations<'a> {
+
+    pub fn from_tokens (tokens: &'a [Token]) -> Self {
+        let mut output = Self::default();
+        output.add_tokens(tokens);
+        output
+    }
+
+    fn add_tokens (&mut self, tokens: &'a [Token]) {
+        for token in tokens {
+            match &token.token {
+                RawToken::Name(text) | RawToken::String(text) => self.add_text(text, token),
+                RawToken::FormattedString {start, items} => {
+                    self.add_text(start, token);
+                    for (item_tokens, text) in items {
+                        self.add_tokens(item_tokens);
+                        self.add_text(text, token);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_text (&mut self, text: &str, token: &'a Token) {
+        if text.is_empty() {return;}
+        self.tokens_by_address.insert(text.as_ptr() as usize, token);
+    }
+
+    pub fn get (&self, text: &str) -> Option<&'a Token> {
+        if text.is_empty() {return None;}
+        self.tokens_by_address.get(&(text.as_ptr() as usize)).copied()
+    }
+
+    // names that weren't borrowed from a token (like "self" or "Array") get a placeholder location
+    pub fn get_token (&self, text: &str) -> Token {
+        self.get(text).cloned().unwrap_or_else(|| Token {
+            token: RawToken::Name(text.to_string()),
+            line_num: 0,
+            char_num: 0,
+        })
+    }
+
+}
+
+
+
+#[derive(Debug, Default)]
+pub struct ModuleGraph<'a> {
+    pub imports: Vec<Vec<ModuleImport<'a>>>,
+}
+
+#[derive(Debug)]
+pub struct ModuleImport<'a> {
+    pub name: &'a str,
+    pub path: Vec<&'a str>,
+    pub file_index: usize,
 }
 
 #[derive(Debug)]
 pub enum ASTDefinition<'a> {
     Function {
+        attributes: Vec<ASTAttribute<'a>>,
         name: &'a str,
         associated_type: Option<ASTType<'a>>,
         args: Vec<ASTFunctionArg<'a>>,
@@ -350,25 +913,95 @@ pub enum ASTDefinition<'a> {
         statements: ASTBlock<'a>,
     },
     Object {
+        attributes: Vec<ASTAttribute<'a>>,
         name: &'a str,
         feilds: Vec<ASTObjectFeild<'a>>
     },
     Choice {
+        attributes: Vec<ASTAttribute<'a>>,
         name: &'a str,
-        choices: Vec<&'a str>
+        choices: Vec<ASTChoiceVariant<'a>>
     },
     Type {
+        attributes: Vec<ASTAttribute<'a>>,
         name: &'a str,
         ast_type: ASTType<'a>,
     },
     Const {
+        attributes: Vec<ASTAttribute<'a>>,
         name: &'a str,
         value: ASTFormula<'a>,
     },
     Static {
+        attributes: Vec<ASTAttribute<'a>>,
         name: &'a str,
         value: ASTFormula<'a>,
     },
+    Use {
+        paths: Vec<Vec<&'a str>>,
+    },
+    GlobalUse {
+        names: Vec<&'a str>,
+    },
+}
+
+impl<'a> ASTDefinition<'a> {
+
+    pub fn attributes (&self) -> &[ASTAttribute<'a>] {
+        match self {
+            Self::Function {attributes, ..} |
+            Self::Object   {attributes, ..} |
+            Self::Choice   {attributes, ..} |
+            Self::Type     {attributes, ..} |
+            Self::Const    {attributes, ..} |
+            Self::Static   {attributes, ..} => attributes,
+            Self::Use {..} | Self::GlobalUse {..} => &[],
+        }
+    }
+
+    pub fn attributes_mut (&mut self) -> Option<&mut Vec<ASTAttribute<'a>>> {
+        match self {
+            Self::Function {attributes, ..} |
+            Self::Object   {attributes, ..} |
+            Self::Choice   {attributes, ..} |
+            Self::Type     {attributes, ..} |
+            Self::Const    {attributes, ..} |
+            Self::Static   {attributes, ..} => Some(attributes),
+            Self::Use {..} | Self::GlobalUse {..} => None,
+        }
+    }
+
+    pub fn type_name (&self) -> &'static str {
+        match self {
+            Self::Function  {..} => "function",
+            Self::Object    {..} => "object",
+            Self::Choice    {..} => "choice",
+            Self::Type      {..} => "type",
+            Self::Const     {..} => "const",
+            Self::Static    {..} => "static",
+            Self::Use       {..} => "use",
+            Self::GlobalUse {..} => "global_use",
+        }
+    }
+
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ASTAttribute<'a> {
+    pub name: &'a str,
+    pub args: Vec<ASTFormula<'a>>,
+}
+
+// a macro from a `.tuam` file, which is used on definitions like an attribute
+#[derive(Debug)]
+pub struct ASTMacro<'a> {
+    pub name: &'a str,
+    pub args: Vec<&'a str>,
+    // None means the macro can be used on any definition
+    pub allowed_definition_types: Option<Vec<&'a str>>,
+    // sets `is_{name}` to true on the definition, like `is_global` for `#global`
+    pub set_is_this: bool,
+    pub code: ASTBlock<'a>,
 }
 
 #[derive(Debug)]
@@ -380,16 +1013,95 @@ pub struct ASTFunctionArg<'a> {
 
 #[derive(Debug)]
 pub struct ASTObjectFeild<'a> {
+    pub attributes: Vec<ASTAttribute<'a>>,
     pub name: &'a str,
     pub ast_type: ASTType<'a>,
     pub default_value: Option<ASTFormula<'a>>,
 }
 
+#[derive(Debug)]
+pub struct ASTChoiceVariant<'a> {
+    pub name: &'a str,
+    pub payload: ASTChoicePayload<'a>,
+}
+
+// `Nothing`, `Filled (any_inner)`, or `Circle (radius: float)`
+#[derive(Debug)]
+pub enum ASTChoicePayload<'a> {
+    None,
+    Tuple (Vec<ASTType<'a>>),
+    Feilds (Vec<(&'a str, ASTType<'a>)>),
+}
+
 
 
 pub type ASTBlock<'a> = Vec<ASTStatement<'a>>;
 
-#[derive(Debug)]
+
+
+
+
+// a value that macro code works with, like `args.name` or `input.unlock_keys`
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroValue<'a> {
+    Int (i64),
+    Bool (bool),
+    String (&'a str),
+    Name (&'a str),
+    List (Vec<MacroValue<'a>>),
+}
+
+impl<'a> Display for MacroValue<'a> {
+    fn fmt (&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int    (value) => write!(fmt, "{value}"),
+            Self::Bool   (value) => write!(fmt, "{value}"),
+            Self::String (value) => write!(fmt, "\"{value}\""),
+            Self::Name   (value) => write!(fmt, "{value}"),
+            Self::List   (items) => write!(fmt, "[{}]", items.iter().map(MacroValue::to_string).collect::<Vec<_>>().join(", ")),
+        }
+    }
+}
+
+// the definition that macro code sees as `input`, which is read by the compiler once every macro has run
+#[derive(Debug, Default)]
+pub struct DefinitionProperties<'a> {
+    pub values: Vec<(String, MacroValue<'a>)>,
+}
+
+impl<'a> DefinitionProperties<'a> {
+
+    pub fn get (&self, name: &str) -> Option<&MacroValue<'a>> {
+        self.values.iter().find(|(value_name, _)| value_name == name).map(|(_, value)| value)
+    }
+
+    pub fn get_mut (&mut self, name: &str) -> Option<&mut MacroValue<'a>> {
+        self.values.iter_mut().find(|(value_name, _)| value_name == name).map(|(_, value)| value)
+    }
+
+    pub fn set (&mut self, name: &str, new_value: MacroValue<'a>) {
+        match self.get_mut(name) {
+            Some(value) => *value = new_value,
+            None => self.values.push((name.to_string(), new_value)),
+        }
+    }
+
+    pub fn get_bool (&self, name: &str) -> bool {
+        self.get(name) == Some(&MacroValue::Bool(true))
+    }
+
+    // a name, or every name in a list of them
+    pub fn get_names (&self, name: &str) -> Vec<&'a str> {
+        match self.get(name) {
+            Some(MacroValue::Name(name)) => vec!(*name),
+            Some(MacroValue::List(items)) => items.iter().filter_map(|item| if let MacroValue::Name(name) = item {Some(*name)} else {None}).collect(),
+            _ => vec!(),
+        }
+    }
+
+}
+
+#[derive(Debug, PartialEq)]
 pub enum ASTStatement<'a> {
 
     Print {value: ASTFormula<'a>},
@@ -398,11 +1110,11 @@ pub enum ASTStatement<'a> {
     Assert {condition: ASTFormula<'a>},
     Todo {message: ASTFormula<'a>},
 
-    VarInit {var_names: Vec<&'a str>, value: ASTFormula<'a>},
+    VarInit {var_names: Vec<&'a str>, var_type: Option<ASTType<'a>>, value: ASTFormula<'a>},
     VarAssignment {start_name: &'a str, var_queries: Vec<VarQuery<'a>>, operator: AssignmentOperator, value: ASTFormula<'a>},
 
     If {condition: ASTFormula<'a>, true_block: ASTBlock<'a>, false_block: ASTBlock<'a>},
-    Switch {switch_value: ASTFormula<'a>, cases: Vec<(ASTFormula<'a>, ASTBlock<'a>)>, default_case: Option<ASTBlock<'a>>},
+    Switch {switch_value: ASTFormula<'a>, cases: Vec<ASTSwitchCase<'a>>},
     For {var_names: Vec<&'a str>, iter: ASTFormula<'a>, block: ASTBlock<'a>},
     While {condition: ASTFormula<'a>, block: ASTBlock<'a>},
     Loop {block: ASTBlock<'a>},
@@ -414,7 +1126,31 @@ pub enum ASTStatement<'a> {
 
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
+pub struct ASTSwitchCase<'a> {
+    pub patterns: Vec<ASTPattern<'a>>,
+    pub block: ASTBlock<'a>,
+}
+
+// `default` and `_` match anything, and lowercase names match anything and bind it to a new variable
+#[derive(Debug, PartialEq)]
+pub enum ASTPattern<'a> {
+    Wildcard,
+    Binding (&'a str),
+    Literal (ASTFormula<'a>),
+    Range {start: ASTFormula<'a>, end: ASTFormula<'a>, is_inclusive: bool},
+    Variant {choice: Option<&'a str>, variant: &'a str, payload: ASTPatternPayload<'a>},
+    Tuple (Vec<ASTPattern<'a>>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ASTPatternPayload<'a> {
+    None,
+    Tuple (Vec<ASTPattern<'a>>),
+    Feilds (Vec<(&'a str, ASTPattern<'a>)>),
+}
+
+#[derive(Debug, PartialEq)]
 pub enum VarQuery<'a> {
     Feild (&'a str),
     Index (ASTFormula<'a>),
@@ -428,6 +1164,7 @@ pub enum ASTFormula<'a> {
     Int (i64),
     UInt (u64),
     Float (f64),
+    TypedNumber {value: Box<ASTFormula<'a>>, tua_type: TuaType},
     Bool (bool),
     String (&'a str),
     Char (char),
@@ -436,15 +1173,26 @@ pub enum ASTFormula<'a> {
 
     Operation {operator: Operator, left: Box<ASTFormula<'a>>, right: Box<ASTFormula<'a>>},
     New {name: &'a str, feilds: Vec<(&'a str, ASTFormula<'a>)>},
-    Not {base: Box<ASTFormula<'a>>},
+    ChoiceVariant {choice: Option<&'a str>, variant: &'a str, payload: ASTVariantPayload<'a>},
+    Prefix {operator: PrefixOperator, base: Box<ASTFormula<'a>>},
     As {base: Box<ASTFormula<'a>>, ast_type: ASTType<'a>},
     IndexQuery {base: Box<ASTFormula<'a>>, key: Box<ASTFormula<'a>>},
     PropertyQuery {base: Box<ASTFormula<'a>>, key: &'a str},
     ReturnTest {base: Box<ASTFormula<'a>>},
     FunctionCall {base: Box<ASTFormula<'a>>, args: Vec<ASTFormula<'a>>, type_args: ASTTypeArgs<'a>},
+    // `function (a, b) return a + b end`, `captures` is every name it uses that isn't defined inside of it
+    Function {args: Vec<(&'a str, Option<ASTType<'a>>)>, return_type: Option<ASTType<'a>>, statements: ASTBlock<'a>, captures: Vec<&'a str>},
 
 }
 
+// `Job.Programmer`, `Filled(x)`, or `Shape.Circle(radius: 1.5)`, also used as patterns
+#[derive(Debug, PartialEq)]
+pub enum ASTVariantPayload<'a> {
+    None,
+    Tuple (Vec<ASTFormula<'a>>),
+    Feilds (Vec<(&'a str, ASTFormula<'a>)>),
+}
+
 #[derive(Debug, PartialEq)]
 pub struct ASTTypeArgs<'a> {
     pub unnamed_arg: Option<ASTType<'a>>,
@@ -456,6 +1204,8 @@ pub struct ASTType<'a> {
     pub name: &'a str,
     pub unnamed_type_arg: Option<Box<ASTType<'a>>>,
     pub named_type_args: Vec<(&'a str, ASTType<'a>)>,
+    // the items of `(int, String)`, which is named `Tuple`
+    pub tuple_items: Vec<ASTType<'a>>,
 }
 
 impl<'a> Default for ASTType<'a> {
@@ -464,10 +1214,36 @@ impl<'a> Default for ASTType<'a> {
             name: "none",
             unnamed_type_arg: None,
             named_type_args: vec!(),
+            tuple_items: vec!(),
         }
     }
 }
 
+impl<'a> ASTType<'a> {
+
+    // any type named `any` or starting with `any_` is a generic type
+    pub fn is_generic_name (name: &str) -> bool {
+        name == "any" || name.starts_with("any_")
+    }
+
+    // the generic types used anywhere in this type, in the order they first appear
+    pub fn add_generic_names (&self, output: &mut Vec<&'a str>) {
+        if Self::is_generic_name(self.name) && !output.contains(&self.name) {
+            output.push(self.name);
+        }
+        if let Some(unnamed_type_arg) = &self.unnamed_type_arg {
+            unnamed_type_arg.add_generic_names(output);
+        }
+        for (_, named_type_arg) in &self.named_type_args {
+            named_type_arg.add_generic_names(output);
+        }
+        for tuple_item in &self.tuple_items {
+            tuple_item.add_generic_names(output);
+        }
+    }
+
+}
+
 
 
 impl<'a> ASTStatement<'a> {
@@ -475,3 +1251,131 @@ impl<'a> ASTStatement<'a> {
         Self::Print{value: ASTFormula::Int(0)}
     }
 }
+
+
+
+
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TuaType {
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    Float32,
+    Float64,
+    Bool,
+    String,
+    Array (Box<TuaType>),
+    Optional (Box<TuaType>),
+    // (value type, error type)
+    MaybeError (Box<TuaType>, Box<TuaType>),
+    // (pass type, return type), what a `test` function gives the `?` operator
+    ControlFlow (Box<TuaType>, Box<TuaType>),
+    Object (String),
+    Choice (String),
+    // (arg types, return type)
+    Function (Vec<TuaType>, Box<TuaType>),
+    Tuple (Vec<TuaType>),
+    // the inferred error type of a `MaybeError<T>` that can return more than one type of error
+    Union (Vec<TuaType>),
+    None,
+}
+
+impl TuaType {
+
+    pub fn get_stack_type (&self) -> Option<StackType> {
+        Some(match self {
+            Self::Int8     => StackType::Int8,
+            Self::Int16    => StackType::Int16,
+            Self::Int32    => StackType::Int32,
+            Self::Int64    => StackType::Int64,
+            Self::UInt8    => StackType::UInt8,
+            Self::UInt16   => StackType::UInt16,
+            Self::UInt32   => StackType::UInt32,
+            Self::UInt64   => StackType::UInt64,
+            Self::Float32  => StackType::Float32,
+            Self::Float64  => StackType::Float64,
+            Self::Bool     => StackType::Bool,
+            Self::String   => StackType::String,
+            Self::Array    (_) => StackType::Array,
+            Self::Optional (_) => StackType::Object,
+            Self::MaybeError  (..) => StackType::Object,
+            Self::ControlFlow (..) => StackType::Object,
+            Self::Object   (_) => StackType::Object,
+            Self::Choice   (_) => StackType::Object,
+            Self::Function (..) => StackType::Function,
+            Self::Tuple    (_) => StackType::Tuple,
+            Self::Union    (members) => return members.first().map(|member| member.get_stack_type()).unwrap_or(Some(StackType::Object)),
+            Self::None => return None,
+        })
+    }
+
+    pub fn is_int (&self) -> bool {
+        matches!(self, Self::Int8 | Self::Int16 | Self::Int32 | Self::Int64 | Self::UInt8 | Self::UInt16 | Self::UInt32 | Self::UInt64)
+    }
+
+    pub fn is_signed (&self) -> bool {
+        matches!(self, Self::Int8 | Self::Int16 | Self::Int32 | Self::Int64 | Self::Float32 | Self::Float64)
+    }
+
+    pub fn is_float (&self) -> bool {
+        matches!(self, Self::Float32 | Self::Float64)
+    }
+
+    pub fn is_number (&self) -> bool {
+        self.is_int() || self.is_float()
+    }
+
+    pub fn is_user_type (&self) -> bool {
+        matches!(self, Self::Object(_) | Self::Choice(_))
+    }
+
+    // the name used for associated functions (`function int.double (self)`)
+    pub fn get_type_name (&self) -> String {
+        match self {
+            Self::Array    (_) => String::from("Array"),
+            Self::Optional (_) => String::from("Optional"),
+            Self::MaybeError  (..) => String::from("MaybeError"),
+            Self::ControlFlow (..) => String::from("ControlFlow"),
+            Self::Function (..) => String::from("Function"),
+            Self::Tuple    (_) => String::from("Tuple"),
+            _ => self.to_string(),
+        }
+    }
+
+}
+
+impl Display for TuaType {
+    fn fmt (&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int8     => write!(fmt, "int_8"),
+            Self::Int16    => write!(fmt, "int_16"),
+            Self::Int32    => write!(fmt, "int_32"),
+            Self::Int64    => write!(fmt, "int"),
+            Self::UInt8    => write!(fmt, "uint_8"),
+            Self::UInt16   => write!(fmt, "uint_16"),
+            Self::UInt32   => write!(fmt, "uint_32"),
+            Self::UInt64   => write!(fmt, "uint"),
+            Self::Float32  => write!(fmt, "float"),
+            Self::Float64  => write!(fmt, "float_64"),
+            Self::Bool     => write!(fmt, "bool"),
+            Self::String   => write!(fmt, "String"),
+            Self::Array    (inner) => write!(fmt, "{inner}[]"),
+            Self::Optional (inner) => write!(fmt, "Optional<{inner}>"),
+            Self::MaybeError  (value, error) => write!(fmt, "MaybeError<{value}, error: {error}>"),
+            Self::ControlFlow (pass, output) => write!(fmt, "ControlFlow<pass: {pass}, return: {output}>"),
+            Self::Object   (name) => write!(fmt, "{name}"),
+            Self::Choice   (name) => write!(fmt, "{name}"),
+            Self::Function (args, output) => write!(fmt, "Function<({}), returns: {output}>", args.iter().map(TuaType::to_string).collect::<Vec<_>>().join(", ")),
+            Self::Tuple    (items) => write!(fmt, "({})", items.iter().map(TuaType::to_string).collect::<Vec<_>>().join(", ")),
+            Self::Union    (members) if members.is_empty() => write!(fmt, "never"),
+            Self::Union    (members) => write!(fmt, "({})", members.iter().map(TuaType::to_string).collect::<Vec<_>>().join(" or ")),
+            Self::None => write!(fmt, "none"),
+        }
+    }
+}
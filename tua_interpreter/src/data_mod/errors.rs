@@ -1,6 +1,6 @@
 use crate::prelude::*;
 
-use std::{io, ops::FromResidual, convert::Infallible, backtrace::Backtrace};
+use std::{io, ops::FromResidual, convert::Infallible, backtrace::{Backtrace, BacktraceStatus}, sync::atomic::{AtomicBool, Ordering}};
 
 
 
@@ -9,14 +9,71 @@ use std::{io, ops::FromResidual, convert::Infallible, backtrace::Backtrace};
 //#[derive(Debug)]
 pub struct CompileError {
     raw_error: RawCompileError,
+    path: Option<PathBuf>,
     backtrace: Backtrace,
+    // errors that are found again after being reported where they came from, like an unknown type in a function's signature at each call to it
+    is_reported: bool,
+}
+
+
+
+impl CompileError {
+
+    pub fn raw_error (&self) -> &RawCompileError {
+        &self.raw_error
+    }
+
+    pub fn path (&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    // errors are created without knowing which file they're in, so the path is added by whatever is going through the files
+    pub fn set_path (&mut self, path: &Path) {
+        if self.path.is_none() {
+            self.path = Some(path.to_path_buf());
+        }
+    }
+
+    pub fn with_path (mut self, path: &Path) -> Self {
+        self.set_path(path);
+        self
+    }
+
+    pub fn into_reported (mut self) -> Self {
+        self.is_reported = true;
+        self
+    }
+
+    pub fn is_reported (&self) -> bool {
+        self.is_reported
+    }
+
+    // only the frames inside of this crate (and their "at file:line" lines) other than the one capturing it, or nothing if backtraces aren't enabled
+    pub fn get_backtrace_frames (&self) -> Vec<String> {
+        if self.backtrace.status() != BacktraceStatus::Captured {return vec!();}
+        let backtrace = self.backtrace.to_string();
+        let mut output = vec!();
+        let mut lines = backtrace.lines().map(str::trim).peekable();
+        while let Some(line) = lines.next() {
+            if !line.contains("tua_interpreter::") || line.contains("capture_backtrace") {continue;}
+            output.push(line.to_string());
+            if let Some(location_line) = lines.next_if(|line| line.starts_with("at ")) {
+                output.push(String::from("    ") + location_line);
+            }
+        }
+        output
+    }
+
 }
 
 
 
 impl std::fmt::Debug for CompileError {
     fn fmt (&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        std::fmt::Display::fmt(self, fmt)
+        fmt.debug_struct("CompileError")
+            .field("raw_error", &self.raw_error)
+            .field("path", &self.path)
+            .finish()
     }
 }
 
@@ -24,32 +81,33 @@ impl std::fmt::Debug for CompileError {
 
 impl Display for CompileError {
     fn fmt (&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-
-        // main error
-        writeln!(fmt, "Initial error: {:#?}", self.raw_error)?;
-        writeln!(fmt, "Error context:")?;
-        
-        // backtrace
-        let frames: Vec<String> = self.backtrace.frames().iter()
-            .map(strip_frame_text)
-            .collect();
-        let (first, last) = fns::find_first_and_last(&frames, |frame| frame.starts_with("\"tua_interpreter::")).unwrap_or_else(|| {
-            let _ = write!(fmt, "[no backtrace]");
-            (0, 0)
-        });
-        for frame in frames.iter().take(last + 1).skip(first + 2) {
-            let frame = String::from("\"") + &frame[18..frame.len()];
-            let mut frame_items: Vec<&str> = frame.split(' ').collect();
-            writeln!(fmt, "{} {} {}", frame_items[0], frame_items[3], frame_items[4])?;
+        write!(fmt, "{}", self.raw_error)?;
+        if let Some(path) = &self.path {
+            write!(fmt, " ({}", path.display())?;
+            if let Some(span) = self.raw_error.get_span() {
+                write!(fmt, ":{}:{}", span.line_num + 1, span.char_num + 1)?;
+            }
+            write!(fmt, ")")?;
         }
-
         Ok(())
     }
 }
 
-fn strip_frame_text (frame: &std::backtrace::BacktraceFrame) -> String {
-    let frame = format!("{frame:?}");
-    frame[7..(frame.len() - 3)].to_string()
+
+
+// `--debug` turns this on so that backtraces are captured without needing `RUST_BACKTRACE`
+static FORCE_BACKTRACES: AtomicBool = AtomicBool::new(false);
+
+pub fn force_backtraces () {
+    FORCE_BACKTRACES.store(true, Ordering::Relaxed);
+}
+
+fn capture_backtrace () -> Backtrace {
+    if FORCE_BACKTRACES.load(Ordering::Relaxed) {
+        Backtrace::force_capture()
+    } else {
+        Backtrace::capture()
+    }
 }
 
 
@@ -58,7 +116,9 @@ impl From<RawCompileError> for CompileError {
     fn from(input: RawCompileError) -> Self {
         Self {
             raw_error: input,
-            backtrace: Backtrace::capture(),
+            path: None,
+            backtrace: capture_backtrace(),
+            is_reported: false,
         }
     }
 }
@@ -69,7 +129,9 @@ impl From<IoError> for CompileError {
             raw_error: RawCompileError::Io {
                 source: io_error
             },
-            backtrace: Backtrace::capture(),
+            path: None,
+            backtrace: capture_backtrace(),
+            is_reported: false,
         }
     }
 }
@@ -142,7 +204,249 @@ pub enum RawCompileError {
         location: Token,
     },
 
+    ChainedComparison {
+        location: Token,
+        first: String,
+        second: String,
+    },
+
+    UnknownAttribute {
+        location: Token,
+        name: String,
+    },
+
+    InvalidAttributeTarget {
+        location: Token,
+        name: String,
+        target: String,
+    },
+
+    InvalidAttributeArgCount {
+        location: Token,
+        name: String,
+        expected: String,
+        found: usize,
+    },
+
+    UnresolvedImport {
+        location: Token,
+        path: String,
+    },
+
+    CircularImport {
+        location: Token,
+        cycle: Vec<PathBuf>,
+    },
+
+    UndefinedName {
+        location: Token,
+        name: String,
+        suggestion: Option<String>,
+    },
+
+    DuplicateName {
+        location: Token,
+        name: String,
+        kind: String,
+    },
+
+    UnknownDefinition {
+        location: Token,
+        name: String,
+        suggestion: Option<String>,
+    },
+
+    UnknownType {
+        location: Token,
+        name: String,
+    },
+
+    UnknownFeild {
+        location: Token,
+        type_name: String,
+        feild: String,
+    },
+
+    MissingObjectFeild {
+        location: Token,
+        object: String,
+        feild: String,
+    },
+
+    CircularTypeAlias {
+        location: Token,
+        name: String,
+    },
+
+    CircularDefaultValue {
+        location: Token,
+        object: String,
+        feild: String,
+    },
+
+    MismatchedTypes {
+        location: Token,
+        expected: String,
+        found: String,
+    },
+
+    InvalidCast {
+        location: Token,
+        from: String,
+        to: String,
+    },
+
+    InvalidVariantPayload {
+        location: Token,
+        variant: String,
+        expected: String,
+    },
+
+    InvalidOperation {
+        location: Token,
+        operator: String,
+        left: String,
+        right: String,
+    },
+
+    MissingOperatorFunction {
+        location: Token,
+        operator: String,
+        type_name: String,
+        function: String,
+    },
+
+    NoMatchingFunction {
+        location: Token,
+        name: String,
+        arg_count: usize,
+    },
+
+    LiteralOutOfRange {
+        location: Token,
+        value: String,
+        tua_type: String,
+    },
+
+    InvalidNumberLiteral {
+        location: Token,
+        details: String,
+    },
+
+    InvalidPrefixOperation {
+        location: Token,
+        operator: String,
+        found: String,
+    },
+
+    CannotInferType {
+        location: Token,
+    },
+
+    UntypedFunctionArg {
+        location: Token,
+        name: String,
+    },
+
+    ChangedTupleItem {
+        location: Token,
+        tuple_type: String,
+    },
+
+    AssignedCapture {
+        location: Token,
+        name: String,
+    },
+
+    UnboundGenericType {
+        location: Token,
+        name: String,
+    },
+
+    ConflictingGenericTypes {
+        location: Token,
+        name: String,
+        first: String,
+        second: String,
+    },
+
+    IncompatibleErrorTypes {
+        location: Token,
+        function: String,
+        first: String,
+        second: String,
+    },
+
+    InvalidReturnTest {
+        location: Token,
+        found: String,
+        return_type: String,
+    },
+
+    MissingGenericRequirement {
+        location: Token,
+        type_name: String,
+        kind: String,
+        name: String,
+        used_on: String,
+        required_by: String,
+    },
+
+    NonExhaustiveSwitch {
+        location: Token,
+        missing: String,
+    },
+
+    InconsistentPatternBindings {
+        location: Token,
+        name: String,
+    },
+
+    MissingReturn {
+        location: Token,
+        function: String,
+        return_type: String,
+    },
+
+    BreakOutsideLoop {
+        location: Token,
+    },
+
+    ThrowOutsideMaybeError {
+        location: Token,
+        return_type: String,
+    },
+
+    UnknownMacroProperty {
+        location: Token,
+        name: String,
+    },
+
+    MacroFailed {
+        location: Token,
+        name: String,
+        details: String,
+    },
+
+    MissingConstructorDefault {
+        location: Token,
+        feild_name: String,
+        object_name: String,
+    },
+
+    MissingPermission {
+        location: Token,
+        permission: String,
+        action: String,
+        type_name: String,
+    },
+
+    EntryFunctionNotFound {
+        name: String,
+    },
+
     UnfinishedFeature {
+        location: Token,
         details: String,
     },
 
@@ -184,10 +488,269 @@ impl RawCompileError {
 
 //impl std::error::Error for RawCompileError {}
 
+impl RawCompileError {
+
+    pub fn get_span (&self) -> Option<SourceSpan> {
+        match self {
+            Self::InvalidCharacter {location} |
+            Self::NoEndQuote {location} |
+            Self::NoBlockCommentEnd {location} |
+            Self::InvalidCharacterDefinition {location} => Some(SourceSpan::from_char(location)),
+            Self::UnexpectedToken {found_token: location, ..} |
+            Self::UnexpectedEndOfFile {proceeding_token: location, ..} |
+            Self::BlockNotClosed {location} |
+            Self::InvalidFunctionName {location} |
+            Self::DuplicateFunctionArg {location} |
+            Self::InvalidTypeName {location} |
+            Self::InvalidTokenType {found_token: location, ..} |
+            Self::MultipleUnnamedTypes {location} |
+            Self::MultipleDefaultCases {location} |
+            Self::ChainedComparison {location, ..} |
+            Self::UnknownAttribute {location, ..} |
+            Self::InvalidAttributeTarget {location, ..} |
+            Self::InvalidAttributeArgCount {location, ..} |
+            Self::UnresolvedImport {location, ..} |
+            Self::CircularImport {location, ..} |
+            Self::UndefinedName {location, ..} |
+            Self::DuplicateName {location, ..} |
+            Self::UnknownDefinition {location, ..} |
+            Self::UnknownType {location, ..} |
+            Self::UnknownFeild {location, ..} |
+            Self::MissingObjectFeild {location, ..} |
+            Self::CircularTypeAlias {location, ..} |
+            Self::CircularDefaultValue {location, ..} |
+            Self::MismatchedTypes {location, ..} |
+            Self::InvalidCast {location, ..} |
+            Self::InvalidVariantPayload {location, ..} |
+            Self::NonExhaustiveSwitch {location, ..} |
+            Self::InconsistentPatternBindings {location, ..} |
+            Self::MissingReturn {location, ..} |
+            Self::InvalidOperation {location, ..} |
+            Self::MissingOperatorFunction {location, ..} |
+            Self::NoMatchingFunction {location, ..} |
+            Self::LiteralOutOfRange {location, ..} |
+            Self::InvalidNumberLiteral {location, ..} |
+            Self::InvalidPrefixOperation {location, ..} |
+            Self::CannotInferType {location} |
+            Self::UntypedFunctionArg {location, ..} |
+            Self::ChangedTupleItem {location, ..} |
+            Self::AssignedCapture {location, ..} |
+            Self::UnboundGenericType {location, ..} |
+            Self::ConflictingGenericTypes {location, ..} |
+            Self::MissingGenericRequirement {location, ..} |
+            Self::IncompatibleErrorTypes {location, ..} |
+            Self::InvalidReturnTest {location, ..} |
+            Self::ThrowOutsideMaybeError {location, ..} |
+            Self::UnknownMacroProperty {location, ..} |
+            Self::MacroFailed {location, ..} |
+            Self::MissingPermission {location, ..} |
+            Self::MissingConstructorDefault {location, ..} |
+            Self::UnfinishedFeature {location, ..} |
+            Self::BreakOutsideLoop {location} => Some(SourceSpan::from_token(location)),
+            Self::EntryFunctionNotFound {..} |
+            Self::Io {..} => None,
+        }
+    }
+
+    pub fn get_variant_name (&self) -> &'static str {
+        match self {
+            Self::InvalidCharacter {..} => "InvalidCharacter",
+            Self::NoEndQuote {..} => "NoEndQuote",
+            Self::NoBlockCommentEnd {..} => "NoBlockCommentEnd",
+            Self::InvalidCharacterDefinition {..} => "InvalidCharacterDefinition",
+            Self::UnexpectedToken {..} => "UnexpectedToken",
+            Self::UnexpectedEndOfFile {..} => "UnexpectedEndOfFile",
+            Self::BlockNotClosed {..} => "BlockNotClosed",
+            Self::InvalidFunctionName {..} => "InvalidFunctionName",
+            Self::DuplicateFunctionArg {..} => "DuplicateFunctionArg",
+            Self::InvalidTypeName {..} => "InvalidTypeName",
+            Self::InvalidTokenType {..} => "InvalidTokenType",
+            Self::MultipleUnnamedTypes {..} => "MultipleUnnamedTypes",
+            Self::MultipleDefaultCases {..} => "MultipleDefaultCases",
+            Self::ChainedComparison {..} => "ChainedComparison",
+            Self::UnknownAttribute {..} => "UnknownAttribute",
+            Self::InvalidAttributeTarget {..} => "InvalidAttributeTarget",
+            Self::InvalidAttributeArgCount {..} => "InvalidAttributeArgCount",
+            Self::UnresolvedImport {..} => "UnresolvedImport",
+            Self::CircularImport {..} => "CircularImport",
+            Self::UndefinedName {..} => "UndefinedName",
+            Self::DuplicateName {..} => "DuplicateName",
+            Self::UnknownDefinition {..} => "UnknownDefinition",
+            Self::UnknownType {..} => "UnknownType",
+            Self::UnknownFeild {..} => "UnknownFeild",
+            Self::MissingObjectFeild {..} => "MissingObjectFeild",
+            Self::CircularTypeAlias {..} => "CircularTypeAlias",
+            Self::CircularDefaultValue {..} => "CircularDefaultValue",
+            Self::MismatchedTypes {..} => "MismatchedTypes",
+            Self::InvalidCast {..} => "InvalidCast",
+            Self::InvalidVariantPayload {..} => "InvalidVariantPayload",
+            Self::InvalidOperation {..} => "InvalidOperation",
+            Self::MissingOperatorFunction {..} => "MissingOperatorFunction",
+            Self::NoMatchingFunction {..} => "NoMatchingFunction",
+            Self::LiteralOutOfRange {..} => "LiteralOutOfRange",
+            Self::InvalidNumberLiteral {..} => "InvalidNumberLiteral",
+            Self::InvalidPrefixOperation {..} => "InvalidPrefixOperation",
+            Self::CannotInferType {..} => "CannotInferType",
+            Self::UntypedFunctionArg {..} => "UntypedFunctionArg",
+            Self::ChangedTupleItem {..} => "ChangedTupleItem",
+            Self::AssignedCapture {..} => "AssignedCapture",
+            Self::UnboundGenericType {..} => "UnboundGenericType",
+            Self::ConflictingGenericTypes {..} => "ConflictingGenericTypes",
+            Self::MissingGenericRequirement {..} => "MissingGenericRequirement",
+            Self::IncompatibleErrorTypes {..} => "IncompatibleErrorTypes",
+            Self::InvalidReturnTest {..} => "InvalidReturnTest",
+            Self::NonExhaustiveSwitch {..} => "NonExhaustiveSwitch",
+            Self::InconsistentPatternBindings {..} => "InconsistentPatternBindings",
+            Self::MissingReturn {..} => "MissingReturn",
+            Self::BreakOutsideLoop {..} => "BreakOutsideLoop",
+            Self::ThrowOutsideMaybeError {..} => "ThrowOutsideMaybeError",
+            Self::UnknownMacroProperty {..} => "UnknownMacroProperty",
+            Self::MacroFailed {..} => "MacroFailed",
+            Self::MissingPermission {..} => "MissingPermission",
+            Self::MissingConstructorDefault {..} => "MissingConstructorDefault",
+            Self::EntryFunctionNotFound {..} => "EntryFunctionNotFound",
+            Self::UnfinishedFeature {..} => "UnfinishedFeature",
+            Self::Io {..} => "Io",
+        }
+    }
+
+    pub fn get_expected_and_found (&self) -> Option<(String, String)> {
+        match self {
+            Self::UnexpectedToken {found_token, expected, ..} => Some((expected.to_string(), found_token.token.to_string())),
+            Self::UnexpectedEndOfFile {expected, ..} => Some((expected.to_string(), String::from("[end of file]"))),
+            Self::InvalidTokenType {found_token, expected_type} => Some((expected_type.to_string(), found_token.token.to_string())),
+            Self::MismatchedTypes {expected, found, ..} => Some((expected.to_string(), found.to_string())),
+            Self::InvalidCast {from, to, ..} => Some((to.to_string(), from.to_string())),
+            _ => None,
+        }
+    }
+
+    // extra info that's shown under the source snippet
+    pub fn get_note (&self) -> Option<String> {
+        match self {
+            Self::UnexpectedToken {context, ..} => Some(context.to_string()),
+            Self::CircularImport {cycle, ..} => Some(format!("import cycle: {}", cycle.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(" -> "))),
+            Self::UndefinedName {suggestion: Some(suggestion), ..} |
+            Self::UnknownDefinition {suggestion: Some(suggestion), ..} => Some(format!("did you mean `{suggestion}`?")),
+            Self::UnknownDefinition {suggestion: None, ..} => Some(String::from("expected 'function', 'object', 'choice', 'type', 'const', 'static', 'use', 'global_use', or '#'")),
+            Self::NonExhaustiveSwitch {..} => Some(String::from("add a case for it, or a `default` case")),
+            Self::CircularTypeAlias {..} => Some(String::from("an alias can't contain itself, use an object or a choice for recursive types")),
+            Self::CircularDefaultValue {feild, ..} => Some(format!("the default value of `{feild}` creates the same type again, so creating it would never finish")),
+            Self::InvalidCast {..} => Some(String::from("only numbers and bools can be cast to numbers, and anything can be cast to `String`")),
+            Self::IncompatibleErrorTypes {..} => Some(String::from("only objects and choices can be combined into one error type, so give it with `MaybeError<T, error: E>` instead")),
+            Self::InvalidReturnTest {..} => Some(String::from("`?` can be used on `Optional`s in functions that return an `Optional`, on `MaybeError`s in functions that return a `MaybeError`, and on types with a `test` function")),
+            Self::MissingOperatorFunction {type_name, function, ..} => Some(format!("it can be used once `function {type_name}.{function}` is defined")),
+            Self::UnboundGenericType {..} => Some(String::from("generic types are decided by the arguments a function is called with")),
+            Self::UntypedFunctionArg {name, ..} => Some(format!("give it a type (like `{name}: int`), or pass the function where a `Function<...>` is expected")),
+            Self::ChangedTupleItem {..} => Some(String::from("tuples can't be changed once they're made, assign a whole new tuple instead")),
+            Self::AssignedCapture {..} => Some(String::from("anonymous functions get their own copy of the variables they use, so put the value in an object to share changes to it")),
+            Self::ConflictingGenericTypes {name, ..} => Some(format!("every use of `{name}` in a function call has to be the same type")),
+            Self::MissingGenericRequirement {kind, name, used_on, required_by, ..} => Some(format!("`{required_by}` uses the {kind} `{name}` on {used_on}")),
+            Self::MissingReturn {..} => Some(String::from("add a `return` at the end of the function, or `crash` if it should never get there")),
+            Self::MissingPermission {permission, ..} => Some(format!("only functions with `#get_permission({permission})` can do this, so use one of them or add it to this function")),
+            Self::MissingConstructorDefault {feild_name, ..} => Some(format!("give it a default value (like `{feild_name}: int = 0`) or remove `#notInConstructor`")),
+            Self::UnknownMacroProperty {..} => Some(String::from("expected 'args', 'allowed_definition_types', 'set_is_this', or 'code'")),
+            Self::ChainedComparison {..} => Some(String::from("use `and` to combine comparisons, like `a < b and b < c`")),
+            Self::ThrowOutsideMaybeError {..} => Some(String::from("`throw` returns the error from the function, so the function has to return a `MaybeError` (use `crash` to stop the program instead)")),
+            _ => None,
+        }
+    }
+
+}
+
+
+
 impl Display for RawCompileError {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        write!(fmt, "{self:?}")
+        match self {
+            Self::InvalidCharacter {location} => write!(fmt, "invalid character {:?}", location.char),
+            Self::NoEndQuote {..} => write!(fmt, "no end quote was found for this string"),
+            Self::NoBlockCommentEnd {..} => write!(fmt, "no end was found for this block comment"),
+            Self::InvalidCharacterDefinition {..} => write!(fmt, "invalid character literal"),
+            Self::UnexpectedToken {found_token, expected, ..} => write!(fmt, "expected {expected}, found `{}`", found_token.token),
+            Self::UnexpectedEndOfFile {expected, ..} => write!(fmt, "expected {expected}, found the end of the file"),
+            Self::BlockNotClosed {..} => write!(fmt, "this block is never closed with `end`"),
+            Self::InvalidFunctionName {location} => write!(fmt, "invalid function name `{}`", location.token),
+            Self::DuplicateFunctionArg {location} => write!(fmt, "the argument `{}` is defined more than once", location.token),
+            Self::InvalidTypeName {location} => write!(fmt, "invalid type name `{}`", location.token),
+            Self::InvalidTokenType {found_token, expected_type} => write!(fmt, "expected a {expected_type}, found `{}`", found_token.token),
+            Self::MultipleUnnamedTypes {..} => write!(fmt, "only one unnamed type argument can be given"),
+            Self::MultipleDefaultCases {..} => write!(fmt, "this switch statement has more than one default case"),
+            Self::ChainedComparison {first, second, ..} => write!(fmt, "comparison operators cannot be chained, found `{first}` and then `{second}`"),
+            Self::UnknownAttribute {name, ..} => write!(fmt, "unknown attribute `#{name}`"),
+            Self::InvalidAttributeTarget {name, target, ..} => write!(fmt, "the attribute `#{name}` cannot be used on a {target}"),
+            Self::InvalidAttributeArgCount {name, expected, found, ..} => write!(fmt, "the attribute `#{name}` takes {expected} argument(s), but {found} were given"),
+            Self::UnresolvedImport {path, ..} => write!(fmt, "could not find the module `{path}`"),
+            Self::CircularImport {..} => write!(fmt, "circular import"),
+            Self::UndefinedName {name, ..} => write!(fmt, "cannot find `{name}` in this scope"),
+            Self::DuplicateName {name, kind, ..} => write!(fmt, "the {kind} `{name}` is defined more than once"),
+            Self::UnknownDefinition {name, ..} => write!(fmt, "unknown definition `{name}`"),
+            Self::UnknownType {name, ..} => write!(fmt, "unknown type `{name}`"),
+            Self::UnknownFeild {type_name, feild, ..} => write!(fmt, "`{type_name}` has no feild named `{feild}`"),
+            Self::MissingObjectFeild {object, feild, ..} => write!(fmt, "missing a value for the feild `{feild}` of `{object}`"),
+            Self::CircularTypeAlias {name, ..} => write!(fmt, "the type alias `{name}` refers to itself"),
+            Self::CircularDefaultValue {object, feild, ..} => write!(fmt, "the default value of `{object}.{feild}` depends on itself"),
+            Self::MismatchedTypes {expected, found, ..} => write!(fmt, "mismatched types, expected `{expected}`, found `{found}`"),
+            Self::InvalidCast {from, to, ..} => write!(fmt, "cannot cast `{from}` to `{to}`"),
+            Self::InvalidVariantPayload {variant, expected, ..} => write!(fmt, "invalid payload for `{variant}`, expected {expected}"),
+            Self::InvalidOperation {operator, left, right, ..} => write!(fmt, "the operator `{operator}` cannot be used on `{left}` and `{right}`"),
+            Self::MissingOperatorFunction {operator, type_name, ..} => write!(fmt, "the operator `{operator}` cannot be used on `{type_name}`"),
+            Self::NoMatchingFunction {name, arg_count, ..} => write!(fmt, "no version of `{name}` takes {arg_count} argument(s)"),
+            Self::LiteralOutOfRange {value, tua_type, ..} => write!(fmt, "the literal `{value}` does not fit in the type `{tua_type}`"),
+            Self::InvalidNumberLiteral {details, ..} => write!(fmt, "invalid number literal: {details}"),
+            Self::InvalidPrefixOperation {operator, found, ..} => write!(fmt, "the operator `{operator}` cannot be used on `{found}`"),
+            Self::CannotInferType {..} => write!(fmt, "cannot infer the type of this value"),
+            Self::UntypedFunctionArg {name, ..} => write!(fmt, "cannot infer the type of the argument `{name}`"),
+            Self::ChangedTupleItem {tuple_type, ..} => write!(fmt, "cannot change an item of the tuple `{tuple_type}`"),
+            Self::AssignedCapture {name, ..} => write!(fmt, "cannot assign to `{name}` because this anonymous function captured it"),
+            Self::UnboundGenericType {name, ..} => write!(fmt, "cannot infer the generic type `{name}`"),
+            Self::ConflictingGenericTypes {name, first, second, ..} => write!(fmt, "the generic type `{name}` is used as both `{first}` and `{second}`"),
+            Self::IncompatibleErrorTypes {function, first, second, ..} => write!(fmt, "the errors that `{function}` returns can't be combined, found `{first}` and `{second}`"),
+            Self::InvalidReturnTest {found, return_type, ..} => write!(fmt, "the `?` operator can't be used on `{found}` in a function that returns `{return_type}`"),
+            Self::MissingGenericRequirement {type_name, kind, name, required_by, ..} => write!(fmt, "{type_name} has no {kind} `{name}` required by `{required_by}`"),
+            Self::NonExhaustiveSwitch {missing, ..} => write!(fmt, "this switch doesn't handle `{missing}`"),
+            Self::InconsistentPatternBindings {name, ..} => write!(fmt, "`{name}` isn't bound by every pattern in this case"),
+            Self::MissingReturn {function, return_type, ..} => write!(fmt, "the function `{function}` can end without returning a `{return_type}`"),
+            Self::BreakOutsideLoop {..} => write!(fmt, "`break` and `continue` can only be used inside of loops"),
+            Self::MissingPermission {permission, action, type_name, ..} => write!(fmt, "this function needs the permission `{permission}` to {action} `{type_name}`"),
+            Self::MissingConstructorDefault {feild_name, object_name, ..} => write!(fmt, "the feild `{feild_name}` isn't given to `{object_name}.new`, so it needs a default value"),
+            Self::UnknownMacroProperty {name, ..} => write!(fmt, "unknown macro property `{name}`"),
+            Self::MacroFailed {name, details, ..} => write!(fmt, "the macro `{name}` failed: {details}"),
+            Self::ThrowOutsideMaybeError {return_type, ..} => write!(fmt, "`throw` can't be used in a function that returns `{return_type}`"),
+            Self::EntryFunctionNotFound {name} => write!(fmt, "could not find the entry function `{name}`"),
+            Self::UnfinishedFeature {details, ..} => write!(fmt, "unfinished feature: {details}"),
+            Self::Io {source} => write!(fmt, "{source}"),
+        }
+    }
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceSpan {
+    pub line_num: usize,
+    pub char_num: usize,
+    pub len: usize,
+}
+
+impl SourceSpan {
+
+    pub fn from_char (char_data: &CharData) -> Self {
+        Self {
+            line_num: char_data.line_num,
+            char_num: char_data.char_num,
+            len: 1,
+        }
     }
+
+    pub fn from_token (token: &Token) -> Self {
+        Self {
+            line_num: token.line_num,
+            char_num: token.char_num,
+            len: token.token.get_source_len(),
+        }
+    }
+
 }
 
 
@@ -197,3 +760,60 @@ impl From<RawCompileError> for String {
 		format!("TEMPORARY CODE!!! {tua_compile_error:?}")
 	}
 }
+
+
+
+
+
+
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub raw_error: RawRuntimeError,
+    pub function_name: String,
+}
+
+impl RuntimeError {
+    pub fn new (raw_error: RawRuntimeError, function_name: impl Into<String>) -> Self {
+        Self {
+            raw_error,
+            function_name: function_name.into(),
+        }
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt (&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{} (in function `{}`)", self.raw_error, self.function_name)
+    }
+}
+
+
+
+#[derive(Debug)]
+pub enum RawRuntimeError {
+    DivideByZero,
+    IntegerOverflow {operation: &'static str},
+    IndexOutOfBounds {index: String, length: usize},
+    InvalidOperation {operator: String},
+    StackOverflow,
+    Crash {message: String},
+    AssertionFailed,
+    Todo {message: String},
+    Io {source: io::Error},
+}
+
+impl Display for RawRuntimeError {
+    fn fmt (&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DivideByZero => write!(fmt, "attempted to divide by zero"),
+            Self::IntegerOverflow {operation} => write!(fmt, "integer overflow during {operation}"),
+            Self::IndexOutOfBounds {index, length} => write!(fmt, "index {index} is out of bounds for an array of length {length}"),
+            Self::InvalidOperation {operator} => write!(fmt, "invalid operands for `{operator}`"),
+            Self::StackOverflow => write!(fmt, "stack overflow"),
+            Self::Crash {message} => write!(fmt, "crashed: {message}"),
+            Self::AssertionFailed => write!(fmt, "assertion failed"),
+            Self::Todo {message} => write!(fmt, "not yet implemented: {message}"),
+            Self::Io {source} => write!(fmt, "{source}"),
+        }
+    }
+}
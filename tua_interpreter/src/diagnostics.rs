@@ -0,0 +1,174 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+
+
+
+// source files are only loaded when an error in them is shown
+#[derive(Debug, Default)]
+pub struct SourceCache {
+    files: HashMap<PathBuf, Option<Vec<String>>>,
+}
+
+impl SourceCache {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_line (&mut self, path: &Path, line_num: usize) -> Option<&str> {
+        let lines = self.files.entry(path.to_path_buf()).or_insert_with(|| {
+            let contents = match init::get_bundled_std_file(path) {
+                Some(contents) => contents.to_string(),
+                None => fs::read_to_string(path).ok()?,
+            };
+            Some(contents.lines().map(str::to_string).collect())
+        });
+        lines.as_ref()?.get(line_num).map(String::as_str)
+    }
+
+}
+
+
+
+
+
+// rustc-style errors:
+//
+// error: expected '(', found `:`
+//   --> testing_2/Examples.tua:73:27
+//    |
+// 73 |     var persons = new HashMap:<key: String, value: Person>()
+//    |                              ^
+//    = note: while parsing new object definition
+pub fn render_error (error: &CompileError, label: &str, source_cache: &mut SourceCache, show_backtrace: bool) -> String {
+    let raw_error = error.raw_error();
+    let mut output = format!("{label}: {raw_error}\n");
+
+    // location and snippet
+    let span = raw_error.get_span();
+    let line_num_text = span.map(|span| (span.line_num + 1).to_string()).unwrap_or_default();
+    let gutter = " ".repeat(line_num_text.len());
+    match (error.path(), span) {
+        (Some(path), Some(span)) => {
+            output += &format!("{gutter}--> {}:{}:{}\n", path.display(), span.line_num + 1, span.char_num + 1);
+            if let Some(line) = source_cache.get_line(path, span.line_num) {
+                // tabs are kept so the caret lines up with the source line
+                let caret_padding: String = line.chars().take(span.char_num).map(|char| if char == '\t' {'\t'} else {' '}).collect();
+                output += &format!("{gutter} |\n");
+                output += &format!("{line_num_text} | {line}\n");
+                output += &format!("{gutter} | {caret_padding}{}\n", "^".repeat(span.len.max(1)));
+            }
+        }
+        (Some(path), None) => output += &format!("{gutter}--> {}\n", path.display()),
+        (None, _) => {}
+    }
+
+    // note
+    if let Some(note) = raw_error.get_note() {
+        output += &format!("{gutter} = note: {note}\n");
+    }
+
+    // backtrace
+    if show_backtrace {
+        let frames = error.get_backtrace_frames();
+        if frames.is_empty() {
+            output += &format!("{gutter} = backtrace: [not captured]\n");
+        } else {
+            output += &format!("{gutter} = backtrace:\n");
+            for frame in frames {
+                output += &format!("        {frame}\n");
+            }
+        }
+    }
+
+    output
+}
+
+
+
+
+// one json object per line:
+// {"severity":"error","variant":"UnexpectedToken","message":"expected '(', found `:`","path":"main.tua","start_line":73,"start_column":27,"end_line":73,"end_column":28,"expected":"'('","found":":","note":"while parsing new object definition"}
+// lines and columns start at 1, the end column is exclusive, and missing values are `null`
+pub fn render_error_json (error: &CompileError, severity: &str) -> String {
+    let raw_error = error.raw_error();
+    let span = raw_error.get_span();
+    let (expected, found) = match raw_error.get_expected_and_found() {
+        Some((expected, found)) => (Some(expected), Some(found)),
+        None => (None, None),
+    };
+    let feilds = [
+        ("severity", json_string(severity)),
+        ("variant", json_string(raw_error.get_variant_name())),
+        ("message", json_string(&raw_error.to_string())),
+        ("path", json_option(error.path().map(|path| json_string(&path.display().to_string())))),
+        ("start_line", json_option(span.map(|span| (span.line_num + 1).to_string()))),
+        ("start_column", json_option(span.map(|span| (span.char_num + 1).to_string()))),
+        ("end_line", json_option(span.map(|span| (span.line_num + 1).to_string()))),
+        ("end_column", json_option(span.map(|span| (span.char_num + 1 + span.len).to_string()))),
+        ("expected", json_option(expected.map(|expected| json_string(&expected)))),
+        ("found", json_option(found.map(|found| json_string(&found)))),
+        ("note", json_option(raw_error.get_note().map(|note| json_string(&note)))),
+    ];
+    let feilds: Vec<String> = feilds.iter().map(|(name, value)| format!("\"{name}\":{value}")).collect();
+    format!("{{{}}}", feilds.join(","))
+}
+
+fn json_option (value: Option<String>) -> String {
+    value.unwrap_or_else(|| String::from("null"))
+}
+
+pub fn json_string (input: &str) -> String {
+    let mut output = String::from("\"");
+    for char in input.chars() {
+        match char {
+            '"' => output += "\\\"",
+            '\\' => output += "\\\\",
+            '\n' => output += "\\n",
+            '\r' => output += "\\r",
+            '\t' => output += "\\t",
+            char if (char as u32) < 0x20 => output += &format!("\\u{:04x}", char as u32),
+            char => output.push(char),
+        }
+    }
+    output.push('"');
+    output
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use super::SourceCache;
+
+    // an error at the `:` in `new Point:`, in a file that's already in the cache
+    fn unexpected_colon () -> (CompileError, SourceCache) {
+        let location = Token {token: RawToken::Colon, line_num: 0, char_num: 18};
+        let error = CompileError::from(RawCompileError::new_unexpected_token(&location, "'('", "while parsing new object definition")).with_path(Path::new("main.tua"));
+        let source_cache = SourceCache {files: [(PathBuf::from("main.tua"), Some(vec!(String::from("\tvar x = new Point:"))))].into_iter().collect()};
+        (error, source_cache)
+    }
+
+
+
+    #[test]
+    fn errors_show_their_source_line () {
+        let (error, mut source_cache) = unexpected_colon();
+        let expected = "error: expected '(', found `:`\n --> main.tua:1:19\n  |\n1 | \tvar x = new Point:\n  | \t                 ^\n  = note: while parsing new object definition\n";
+        assert_eq!(diagnostics::render_error(&error, "error", &mut source_cache, false), expected);
+    }
+
+    #[test]
+    fn json_errors_have_every_feild () {
+        let (error, _) = unexpected_colon();
+        let expected = r#"{"severity":"error","variant":"UnexpectedToken","message":"expected '(', found `:`","path":"main.tua","start_line":1,"start_column":19,"end_line":1,"end_column":20,"expected":"'('","found":":","note":"while parsing new object definition"}"#;
+        assert_eq!(diagnostics::render_error_json(&error, "error"), expected);
+        let error = CompileError::from(RawCompileError::EntryFunctionNotFound {name: String::from("main")});
+        let expected = r#"{"severity":"error","variant":"EntryFunctionNotFound","message":"could not find the entry function `main`","path":null,"start_line":null,"start_column":null,"end_line":null,"end_column":null,"expected":null,"found":null,"note":null}"#;
+        assert_eq!(diagnostics::render_error_json(&error, "error"), expected);
+    }
+
+}
@@ -133,6 +133,16 @@ pub fn get_all_files_in_dir<P: Into<PathBuf>> (path: P) -> Result<Vec<PathBuf>,
 
 
 
+pub fn is_same_file (a: &Path, b: &Path) -> bool {
+    if a == b {return true;}
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+
+
 pub fn some_if<T> (condition: bool, some_fn: impl FnOnce() -> T) -> Option<T> {
     if condition {
         Some(some_fn())
@@ -140,3 +150,36 @@ pub fn some_if<T> (condition: bool, some_fn: impl FnOnce() -> T) -> Option<T> {
         None
     }
 }
+
+
+
+// used for "did you mean ...?" notes, only returns names that are a few typos away
+pub fn get_closest_name<'a> (name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    // short names would match almost anything otherwise
+    let max_distance = (name.chars().count() / 3).max(1).min(name.chars().count().saturating_sub(1));
+    candidates.into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (get_edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// levenshtein distance, where swapping two letters only counts as one edit
+pub fn get_edit_distance (a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut distances = vec!(vec!(0; b.len() + 1); a.len() + 1);
+    for i in 0..=a.len() {distances[i][0] = i;}
+    for j in 0..=b.len() {distances[0][j] = j;}
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] {0} else {1};
+            distances[i][j] = (distances[i - 1][j] + 1).min(distances[i][j - 1] + 1).min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distances[i][j] = distances[i][j].min(distances[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    distances[a.len()][b.len()]
+}
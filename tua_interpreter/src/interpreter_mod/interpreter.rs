@@ -0,0 +1,526 @@
+use crate::prelude::*;
+use std::{rc::Rc, cell::RefCell};
+
+
+
+pub const MAX_CALL_DEPTH: usize = 10_000;
+
+struct CallFrame {
+	function: usize,
+	next_instruction: usize,
+	output: Option<Slot>,
+}
+
+
+
+
+
+pub fn run_function (program: &Program, function_id: usize, args: Vec<Value>) -> Result<Value, RuntimeError> {
+	let mut env = Environment::new();
+	let function = &program.functions[function_id];
+	env.push_frame(&function.frame_sizes);
+	for (slot, value) in function.arg_slots.iter().zip(args) {
+		env.set_value(*slot, value);
+	}
+	let mut call_stack = vec!(CallFrame {function: function_id, next_instruction: 0, output: None});
+
+	loop {
+		let frame = call_stack.last_mut().unwrap();
+		let function = &program.functions[frame.function];
+		let instruction = &function.instructions[frame.next_instruction];
+		frame.next_instruction += 1;
+		match run_instruction(instruction, program, &mut env, &mut call_stack) {
+			Ok(None) => {}
+			Ok(Some(return_value)) => return Ok(return_value),
+			Err(error) => return Err(RuntimeError::new(error, &function.name)),
+		}
+	}
+}
+
+
+
+// returns the return value of the first function once it returns
+fn run_instruction (instruction: &Instruction, program: &Program, env: &mut Environment, call_stack: &mut Vec<CallFrame>) -> Result<Option<Value>, RawRuntimeError> {
+	match instruction {
+
+		Instruction::LoadConst {value, output} => env.set_value(*output, value.clone()),
+
+		Instruction::Copy {from, to} => {
+			let value = env.get_value(*from);
+			env.set_value(*to, value);
+		}
+
+		Instruction::Operation {operator, left, right, output} => {
+			let value = natives::run_operation(operator, env.get_value(*left), env.get_value(*right))?;
+			env.set_value(*output, value);
+		}
+
+		Instruction::Not {base, output} => {
+			let value = match env.get_value(*base) {
+				Value::Bool   (value) => Value::Bool(!value),
+				Value::Int8   (value) => Value::Int8(!value),
+				Value::Int16  (value) => Value::Int16(!value),
+				Value::Int32  (value) => Value::Int32(!value),
+				Value::Int64  (value) => Value::Int64(!value),
+				Value::UInt8  (value) => Value::UInt8(!value),
+				Value::UInt16 (value) => Value::UInt16(!value),
+				Value::UInt32 (value) => Value::UInt32(!value),
+				Value::UInt64 (value) => Value::UInt64(!value),
+				_ => return Err(RawRuntimeError::InvalidOperation {operator: String::from("not")}),
+			};
+			env.set_value(*output, value);
+		}
+
+		Instruction::Negate {base, output} => {
+			let value = match env.get_value(*base) {
+				Value::Int8    (value) => value.checked_neg().map(Value::Int8),
+				Value::Int16   (value) => value.checked_neg().map(Value::Int16),
+				Value::Int32   (value) => value.checked_neg().map(Value::Int32),
+				Value::Int64   (value) => value.checked_neg().map(Value::Int64),
+				Value::Float32 (value) => Some(Value::Float32(-value)),
+				Value::Float64 (value) => Some(Value::Float64(-value)),
+				_ => return Err(RawRuntimeError::InvalidOperation {operator: String::from("-")}),
+			};
+			let Some(value) = value else {
+				return Err(RawRuntimeError::IntegerOverflow {operation: "negation"});
+			};
+			env.set_value(*output, value);
+		}
+
+		Instruction::Cast {base, output} => {
+			let value = natives::cast_value(env.get_value(*base), output.stack);
+			env.set_value(*output, value);
+		}
+
+		Instruction::ToString {base, output} => {
+			let value = Value::new_string(format_value(program, &env.get_value(*base)));
+			env.set_value(*output, value);
+		}
+
+		Instruction::Jump {target} => call_stack.last_mut().unwrap().next_instruction = *target,
+
+		Instruction::JumpIfFalse {condition, target} => {
+			if !get_bool(env, *condition) {
+				call_stack.last_mut().unwrap().next_instruction = *target;
+			}
+		}
+
+		Instruction::JumpIfTrue {condition, target} => {
+			if get_bool(env, *condition) {
+				call_stack.last_mut().unwrap().next_instruction = *target;
+			}
+		}
+
+		Instruction::Call {function, args, output} => {
+			if call_stack.len() >= MAX_CALL_DEPTH {
+				return Err(RawRuntimeError::StackOverflow);
+			}
+			let args: Vec<Value> = args.iter().map(|slot| env.get_value(*slot)).collect();
+			let callee = &program.functions[*function];
+			env.push_frame(&callee.frame_sizes);
+			for (slot, value) in callee.arg_slots.iter().zip(args) {
+				env.set_value(*slot, value);
+			}
+			call_stack.push(CallFrame {function: *function, next_instruction: 0, output: *output});
+		}
+
+		// the closure's captured values come before the args it's called with
+		Instruction::CallClosure {closure, args, output} => {
+			if call_stack.len() >= MAX_CALL_DEPTH {
+				return Err(RawRuntimeError::StackOverflow);
+			}
+			let Value::Function(closure) = env.get_value(*closure) else {unreachable!()};
+			let args: Vec<Value> = closure.captures.iter().cloned().chain(args.iter().map(|slot| env.get_value(*slot))).collect();
+			let callee = &program.functions[closure.function];
+			env.push_frame(&callee.frame_sizes);
+			for (slot, value) in callee.arg_slots.iter().zip(args) {
+				env.set_value(*slot, value);
+			}
+			call_stack.push(CallFrame {function: closure.function, next_instruction: 0, output: *output});
+		}
+
+		Instruction::CallNative {native, args, output} => {
+			let args: Vec<Value> = args.iter().map(|slot| env.get_value(*slot)).collect();
+			let value = natives::run_native(*native, args)?;
+			if let Some(output) = output {
+				env.set_value(*output, value);
+			}
+		}
+
+		Instruction::Return {value} => {
+			let value = value.map(|slot| env.get_value(slot)).unwrap_or_default();
+			env.pop_frame();
+			let frame = call_stack.pop().unwrap();
+			if call_stack.is_empty() {
+				return Ok(Some(value));
+			}
+			if let Some(output) = frame.output {
+				env.set_value(output, value);
+			}
+		}
+
+		Instruction::Print {value} => println!("{}", format_value(program, &env.get_value(*value))),
+
+		Instruction::Crash {message} => return Err(RawRuntimeError::Crash {message: format_value(program, &env.get_value(*message))}),
+
+		Instruction::Assert {condition} => {
+			if !get_bool(env, *condition) {
+				return Err(RawRuntimeError::AssertionFailed);
+			}
+		}
+
+		Instruction::Todo {message} => return Err(RawRuntimeError::Todo {message: format_value(program, &env.get_value(*message))}),
+
+		Instruction::NewObject {type_id, variant, feilds, output} => {
+			let object = ObjectValue {
+				type_id: *type_id,
+				variant: *variant,
+				feilds: feilds.iter().map(|slot| env.get_value(*slot)).collect(),
+			};
+			env.set_value(*output, Value::Object(Rc::new(RefCell::new(object))));
+		}
+
+		Instruction::NewClosure {function, captures, output} => {
+			let closure = ClosureValue {
+				function: *function,
+				captures: captures.iter().map(|slot| env.get_value(*slot)).collect(),
+			};
+			env.set_value(*output, Value::Function(Rc::new(closure)));
+		}
+
+		Instruction::NewTuple {items, output} => {
+			let items = items.iter().map(|slot| env.get_value(*slot)).collect();
+			env.set_value(*output, Value::Tuple(Rc::new(items)));
+		}
+
+		Instruction::IsVariant {base, variant, output} => {
+			let Value::Object(object) = env.get_value(*base) else {unreachable!()};
+			let is_variant = object.borrow().variant == *variant;
+			env.set_value(*output, Value::Bool(is_variant));
+		}
+
+		// tuple items are read the same way as feilds
+		Instruction::GetFeild {base, feild, output} => {
+			let value = match env.get_value(*base) {
+				Value::Object (object) => object.borrow().feilds[*feild].clone(),
+				Value::Tuple (items) => items[*feild].clone(),
+				_ => unreachable!(),
+			};
+			env.set_value(*output, value);
+		}
+
+		Instruction::SetFeild {base, feild, value} => {
+			let Value::Object(object) = env.get_value(*base) else {unreachable!()};
+			object.borrow_mut().feilds[*feild] = env.get_value(*value);
+		}
+
+		Instruction::GetIndex {base, key, output} => {
+			let Value::Array(array) = env.get_value(*base) else {unreachable!()};
+			let index = get_array_index(&env.get_value(*key), array.borrow().len())?;
+			let value = array.borrow()[index].clone();
+			env.set_value(*output, value);
+		}
+
+		Instruction::SetIndex {base, key, value} => {
+			let Value::Array(array) = env.get_value(*base) else {unreachable!()};
+			let index = get_array_index(&env.get_value(*key), array.borrow().len())?;
+			array.borrow_mut()[index] = env.get_value(*value);
+		}
+
+	}
+	Ok(None)
+}
+
+
+
+fn get_bool (env: &Environment, slot: Slot) -> bool {
+	*env.bools.get(slot.index)
+}
+
+fn get_array_index (key: &Value, length: usize) -> Result<usize, RawRuntimeError> {
+	let index = natives::get_int_value(key).unwrap();
+	usize::try_from(index).ok()
+		.filter(|index| *index < length)
+		.ok_or_else(|| RawRuntimeError::IndexOutOfBounds {index: index.to_string(), length})
+}
+
+
+
+
+
+pub fn format_value (program: &Program, value: &Value) -> String {
+	match value {
+		Value::String (value) => value.borrow().to_string(),
+		_ => format_inner_value(program, value),
+	}
+}
+
+// strings are quoted when they're inside of other values
+fn format_inner_value (program: &Program, value: &Value) -> String {
+	match value {
+		Value::Int8    (value) => value.to_string(),
+		Value::Int16   (value) => value.to_string(),
+		Value::Int32   (value) => value.to_string(),
+		Value::Int64   (value) => value.to_string(),
+		Value::UInt8   (value) => value.to_string(),
+		Value::UInt16  (value) => value.to_string(),
+		Value::UInt32  (value) => value.to_string(),
+		Value::UInt64  (value) => value.to_string(),
+		Value::Float32 (value) => value.to_string(),
+		Value::Float64 (value) => value.to_string(),
+		Value::Bool    (value) => value.to_string(),
+		Value::String  (value) => format!("{:?}", value.borrow()),
+		Value::Array   (items) => {
+			let items: Vec<String> = items.borrow().iter().map(|item| format_inner_value(program, item)).collect();
+			format!("[{}]", items.join(", "))
+		}
+		Value::Object  (object) => {
+			let object = object.borrow();
+			let type_data = &program.types[object.type_id];
+			if object.type_id == bytecode::OPTIONAL_TYPE_ID {
+				return match object.feilds.first() {
+					Some(value) => format_inner_value(program, value),
+					None => String::from("Nothing"),
+				};
+			}
+			if !type_data.variant_names.is_empty() {
+				let variant_name = format!("{}.{}", type_data.name, type_data.variant_names[object.variant]);
+				if object.feilds.is_empty() {
+					return variant_name;
+				}
+				let feild_names = &type_data.variant_feild_names[object.variant];
+				let is_tuple = feild_names.first().map(|name| name == "0").unwrap_or(false);
+				let feilds: Vec<String> = feild_names.iter().zip(object.feilds.iter())
+					.map(|(name, value)| if is_tuple {format_inner_value(program, value)} else {format!("{name}: {}", format_inner_value(program, value))})
+					.collect();
+				return format!("{variant_name} ({})", feilds.join(", "));
+			}
+			let feilds: Vec<String> = type_data.feild_names.iter().zip(object.feilds.iter())
+				.map(|(name, value)| format!("{name}: {}", format_inner_value(program, value)))
+				.collect();
+			format!("{} ({})", type_data.name, feilds.join(", "))
+		}
+		Value::Function (closure) => format!("Function ({})", program.functions[closure.function].name),
+		Value::Tuple   (items) => {
+			let items: Vec<String> = items.iter().map(|item| format_inner_value(program, item)).collect();
+			format!("({})", items.join(", "))
+		}
+		Value::None => String::from("none"),
+	}
+}
+
+
+
+pub fn parse_arg_value (arg: &str, tua_type: &TuaType) -> Result<Value, String> {
+	let output = match tua_type {
+		TuaType::Int8    => arg.parse().map(Value::Int8).ok(),
+		TuaType::Int16   => arg.parse().map(Value::Int16).ok(),
+		TuaType::Int32   => arg.parse().map(Value::Int32).ok(),
+		TuaType::Int64   => arg.parse().map(Value::Int64).ok(),
+		TuaType::UInt8   => arg.parse().map(Value::UInt8).ok(),
+		TuaType::UInt16  => arg.parse().map(Value::UInt16).ok(),
+		TuaType::UInt32  => arg.parse().map(Value::UInt32).ok(),
+		TuaType::UInt64  => arg.parse().map(Value::UInt64).ok(),
+		TuaType::Float32 => arg.parse().map(Value::Float32).ok(),
+		TuaType::Float64 => arg.parse().map(Value::Float64).ok(),
+		TuaType::Bool    => arg.parse().map(Value::Bool).ok(),
+		TuaType::String  => Some(Value::new_string(arg)),
+		_ => return Err(format!("arguments of type `{tua_type}` cannot be given from the command line")),
+	};
+	output.ok_or_else(|| format!("could not parse `{arg}` as `{tua_type}`"))
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+	use crate::prelude::*;
+
+	// compiles a single-file project and runs its `main`, giving back the formatted return value or the compile errors
+	pub fn run_source (name: &str, source: &str) -> Result<String, Vec<String>> {
+		let path = std::env::temp_dir().join(format!("tua_test_{name}.tua"));
+		fs::write(&path, source).unwrap();
+		let (program, errors) = compiler::compile_from_path(&path, &EntryPoints::Function(String::from("main")), &mut Logger::new("test")).unwrap();
+		fs::remove_file(&path).unwrap();
+		let Some(program) = program.filter(|_| errors.is_empty()) else {
+			return Err(errors.iter().map(|error| error.raw_error().to_string()).collect());
+		};
+		let output = interpreter::run_function(&program, program.entries[0], vec!()).unwrap();
+		Ok(interpreter::format_value(&program, &output))
+	}
+
+
+
+	#[test]
+	fn while_loop () {
+		let source = "function main returns int\n\tvar total = 0\n\tvar i = 1\n\twhile i <= 10 do\n\t\ttotal += i\n\t\ti += 1\n\tend\n\treturn total\nend\n";
+		assert_eq!(run_source("while_loop", source), Ok(String::from("55")));
+	}
+
+	#[test]
+	fn recursion () {
+		let source = "function fib (n: int) returns int\n\tif n < 2 then return n end\n\treturn fib(n - 1) + fib(n - 2)\nend\n\nfunction main returns int\n\treturn fib(15)\nend\n";
+		assert_eq!(run_source("recursion", source), Ok(String::from("610")));
+	}
+
+	#[test]
+	fn object_feilds () {
+		let source = "object Point (\n\tx: int,\n\ty: int = 2,\n)\n\nfunction main returns Point\n\tvar point = new Point (x: 1)\n\tpoint.x += 5\n\treturn point\nend\n";
+		assert_eq!(run_source("object_feilds", source), Ok(String::from("Point (x: 6, y: 2)")));
+	}
+
+	#[test]
+	fn tuple_patterns () {
+		let source = "function main returns String\n\tvar pair = (false, 3)\n\tswitch pair do\n\t\t(true, _):\n\t\t\treturn \"yes\"\n\t\tend\n\t\t(false, n):\n\t\t\treturn \"no \" .. n\n\t\tend\n\tend\nend\n";
+		assert_eq!(run_source("tuple_patterns", source), Ok(String::from("no 3")));
+	}
+
+	#[test]
+	fn unknown_attributes_are_reported () {
+		let source = "#fast\nfunction double (x: int) returns int\n\treturn x * 2\nend\n\n#notInConstructor\nfunction main returns int\n\treturn double(2)\nend\n";
+		assert_eq!(run_source("unknown_attributes_are_reported", source), Err(vec!(String::from("unknown attribute `#fast`"), String::from("the attribute `#notInConstructor` cannot be used on a function"))));
+	}
+
+	#[test]
+	fn choice_variant_payloads () {
+		let source = "choice Shape (\n\tCircle (radius: int),\n\tRect (int, int),\n\tEmpty,\n)\n\nfunction main returns (Shape, Shape, Shape)\n\treturn (Shape.Circle(radius: 3), Rect(2, 5), Shape.Empty)\nend\n";
+		assert_eq!(run_source("choice_variant_payloads", source), Ok(String::from("(Shape.Circle (radius: 3), Shape.Rect (2, 5), Shape.Empty)")));
+		let source = "choice Shape (\n\tCircle (radius: int),\n\tRect (int, int),\n)\n\nfunction main returns Shape\n\treturn Shape.Rect(2)\nend\n";
+		assert_eq!(run_source("wrong_choice_variant_payload", source), Err(vec!(String::from("invalid payload for `Shape.Rect`, expected `(int, int)`"))));
+	}
+
+	#[test]
+	fn type_mismatches_are_reported () {
+		let source = "function half (x: float) returns float\n\treturn x / 2\nend\n\nfunction main returns int\n\tvar count: uint_8 = true\n\tvar name = \"a\"\n\tname = 5\n\tprint half(\"b\")\n\tprint 1 + \"c\"\n\tprint name as bool\n\treturn 0\nend\n";
+		assert_eq!(run_source("type_mismatches", source), Err(vec!(
+			String::from("mismatched types, expected `uint_8`, found `bool`"),
+			String::from("mismatched types, expected `String`, found `int`"),
+			String::from("mismatched types, expected `float`, found `String`"),
+			String::from("mismatched types, expected `int`, found `String`"),
+			String::from("cannot cast `String` to `bool`"),
+		)));
+	}
+
+	#[test]
+	fn generic_functions_are_made_per_type () {
+		let source = "function pick (first: any_item, second: any_item, take_first: bool) returns any_item\n\tif take_first then return first end\n\treturn second\nend\n\nfunction main returns (int, String)\n\treturn (pick(1, 2, false), pick(\"a\", \"b\", true))\nend\n";
+		assert_eq!(run_source("generic_functions", source), Ok(String::from("(2, \"a\")")));
+		let source = "function pick (first: any_item, second: any_item) returns any_item\n\treturn first\nend\n\nfunction main returns int\n\treturn pick(1, \"b\")\nend\n";
+		assert_eq!(run_source("conflicting_generic_types", source), Err(vec!(String::from("the generic type `any_item` is used as both `String` and `int`"))));
+	}
+
+	#[test]
+	fn throw_returns_the_error () {
+		let source = "function check (n: int) returns MaybeError<int, error: String>\n\tif n < 0 then\n\t\tthrow \"negative\"\n\tend\n\treturn n\nend\n\nfunction main returns (MaybeError<int, error: String>, MaybeError<int, error: String>)\n\treturn (check(1), check(-1))\nend\n";
+		assert_eq!(run_source("throw_returns_the_error", source), Ok(String::from("(MaybeError.Value (1), MaybeError.Error (\"negative\"))")));
+		let source = "function check (n: int) returns int\n\tthrow \"negative\"\nend\n\nfunction check_2 (n: int) returns MaybeError<int, error: String>\n\tthrow 5\nend\n\nfunction main\n\tprint check(1)\n\tprint check_2(1)\nend\n";
+		assert_eq!(run_source("wrong_throws", source), Err(vec!(String::from("mismatched types, expected `String`, found `int`"), String::from("`throw` can't be used in a function that returns `int`"))));
+	}
+
+	const EVEN_INT: &str = "#set_permission(UnlockEvenInt)\nobject EvenInt (\n\tnum: int,\n)\n\n#get_permission(UnlockEvenInt)\nfunction EvenInt.make (num: int) returns EvenInt\n\treturn new EvenInt (num: num - num % 2)\nend\n\n#get_permission(UnlockEvenInt)\nfunction EvenInt.double (self)\n\tself.num *= 2\nend\n\n";
+
+	#[test]
+	fn permissions_allow_locked_changes () {
+		let source = EVEN_INT.to_string() + "function main returns int\n\tvar value = EvenInt.make(5)\n\tvalue.double()\n\treturn value.num\nend\n";
+		assert_eq!(run_source("permissions_allow_locked_changes", &source), Ok(String::from("8")));
+	}
+
+	#[test]
+	fn locked_changes_need_permissions () {
+		let source = EVEN_INT.to_string() + "function increment (value: EvenInt)\n\tvalue.num += 1\nend\n\nfunction main returns int\n\tvar value = new EvenInt (num: 3)\n\tincrement(value)\n\treturn value.num\nend\n";
+		assert_eq!(run_source("locked_changes_need_permissions", &source), Err(vec!(
+			String::from("this function needs the permission `UnlockEvenInt` to create `EvenInt`"),
+			String::from("this function needs the permission `UnlockEvenInt` to change the feilds of `EvenInt`"),
+		)));
+	}
+
+	#[test]
+	fn default_constructors () {
+		let source = "#makeDefaultConstructor\nobject PersonData (\n\tname: String,\n\t#notInConstructor\n\tvisits: int = 0,\n\tage: uint,\n)\n\nfunction main returns PersonData\n\treturn PersonData.new(\"Ann\", 30)\nend\n";
+		assert_eq!(run_source("default_constructors", source), Ok(String::from("PersonData (name: \"Ann\", visits: 0, age: 30)")));
+		let source = "#makeDefaultConstructor\nobject PersonData (\n\tname: String,\n\t#notInConstructor\n\tvisits: int,\n)\n\nfunction main returns PersonData\n\treturn PersonData.new(\"Ann\")\nend\n";
+		assert_eq!(run_source("default_constructor_without_default", source), Err(vec!(String::from("the feild `visits` isn't given to `PersonData.new`, so it needs a default value"))));
+	}
+
+	#[test]
+	fn tuple_destructuring () {
+		let source = "function split (pair: (String, int)) returns (int, String)\n\tvar name, count = pair\n\treturn (count + pair.1, name .. pair.0)\nend\n\nfunction main returns (int, String)\n\treturn split((\"ab\", 2))\nend\n";
+		assert_eq!(run_source("tuple_destructuring", source), Ok(String::from("(4, \"abab\")")));
+		let source = "function main returns int\n\tvar a, b = (1, 2, 3)\n\treturn a\nend\n";
+		assert_eq!(run_source("wrong_tuple_destructuring", source), Err(vec!(String::from("mismatched types, expected `a tuple with 2 items`, found `(int, int, int)`"))));
+	}
+
+	const COUNTDOWN: &str = "object Countdown (\n\tstart: int,\n)\n\nobject CountdownIter (\n\tleft: int,\n)\n\nfunction Countdown.to_iter (self) returns CountdownIter\n\treturn new CountdownIter (left: self.start)\nend\n\n";
+
+	#[test]
+	fn for_loop_with_to_iter () {
+		let source = COUNTDOWN.to_string() + "function CountdownIter.next_item (self) returns Optional<int>\n\tif self.left == 0 then return Optional.Nothing end\n\tself.left -= 1\n\treturn Optional.Filled(self.left + 1)\nend\n\nfunction sum (items: any) returns int\n\tvar total = 0\n\tfor item in items do\n\t\ttotal += item\n\tend\n\treturn total\nend\n\nfunction main returns int\n\treturn sum(new Countdown (start: 4))\nend\n";
+		assert_eq!(run_source("for_loop_with_to_iter", &source), Ok(String::from("10")));
+	}
+
+	#[test]
+	fn for_loop_with_multiple_variables () {
+		let source = COUNTDOWN.to_string() + "function CountdownIter.next_item (self) returns Optional<(int, int)>\n\tif self.left == 0 then return Optional.Nothing end\n\tself.left -= 1\n\treturn Optional.Filled((self.left + 1, (self.left + 1) * (self.left + 1)))\nend\n\nfunction main returns int\n\tvar total = 0\n\tfor n, square in new Countdown (start: 3) do\n\t\ttotal += n * square\n\tend\n\treturn total\nend\n";
+		assert_eq!(run_source("for_loop_with_multiple_variables", &source), Ok(String::from("36")));
+	}
+
+	#[test]
+	fn generic_requirements_through_to_iter () {
+		let source = COUNTDOWN.to_string() + "function sum (items: any) returns int\n\tvar total = 0\n\tfor item in items do\n\t\ttotal += item\n\tend\n\treturn total\nend\n\nfunction main returns int\n\treturn sum(new Countdown (start: 4))\nend\n";
+		assert_eq!(run_source("generic_requirements_through_to_iter", &source), Err(vec!(String::from("CountdownIter has no function `next_item` required by `sum`"))));
+	}
+
+	#[test]
+	fn unknown_names_are_reported_once () {
+		let source = "choice Shape (\n\tCircle,\n)\n\nconst LIMIT = missing\n\nfunction main returns int\n\tvar shape = Shape.Nope\n\tprint shape\n\treturn LIMIT\nend\n";
+		assert_eq!(run_source("unknown_names", source), Err(vec!(String::from("cannot find `missing` in this scope"), String::from("cannot find `Shape.Nope` in this scope"))));
+	}
+
+	#[test]
+	fn operators_call_associated_functions () {
+		let source = "object Vec2 (\n\tx: int,\n\ty: int,\n)\n\nfunction Vec2.add (self, other: Vec2) returns Vec2\n\treturn new Vec2 (x: self.x + other.x, y: self.y + other.y)\nend\n\nfunction Vec2.index (self, i: int) returns int\n\tif i == 0 then return self.x end\n\treturn self.y\nend\n\nfunction Vec2.equals (self, other: Vec2) returns bool\n\treturn self.x == other.x and self.y == other.y\nend\n\nfunction main returns (Vec2, int, bool, bool)\n\tvar a = new Vec2 (x: 1, y: 2)\n\tvar b = new Vec2 (x: 3, y: 4)\n\treturn (a + b, b[1], a == b, a != b)\nend\n";
+		assert_eq!(run_source("operators_call_associated_functions", source), Ok(String::from("(Vec2 (x: 4, y: 6), 4, false, true)")));
+		let source = "object Person (\n\tname: String,\n)\n\nfunction main returns Person\n\tvar person = new Person (name: \"a\")\n\treturn person + person\nend\n";
+		assert_eq!(run_source("operators_without_functions", source), Err(vec!(String::from("the operator `+` cannot be used on `Person`"))));
+	}
+
+	#[test]
+	fn concat_needs_a_concat_function () {
+		let source = "object Name (\n\ttext: String,\n)\n\nfunction main returns String\n\tvar name = new Name (text: \"a\")\n\tvar text = name .. \"!\"\n\tname ..= \"?\"\n\treturn text\nend\n";
+		let error = String::from("the operator `..` cannot be used on `Name`");
+		assert_eq!(run_source("concat_without_function", source), Err(vec!(error.clone(), error)));
+	}
+
+	#[test]
+	fn definition_errors_are_reported_once () {
+		let source = "object Counter (\n\tcount: int = \"zero\",\n)\n\nfunction helper (x: Missing) returns int\n\treturn 1\nend\n\nfunction other returns int\n\tvar counter = new Counter ()\n\treturn helper(2)\nend\n\nfunction main returns int\n\tvar counter = new Counter ()\n\treturn helper(1) + other()\nend\n";
+		assert_eq!(run_source("definition_errors", source), Err(vec!(String::from("unknown type `Missing`"), String::from("mismatched types, expected `int`, found `String`"))));
+	}
+
+	#[test]
+	fn overloads_that_dont_fit_are_undone () {
+		let source = "function twice (x: any) returns any\n\treturn x .. x\nend\n\nfunction apply (f: Function<(int), returns: int>) returns int\n\treturn f(1)\nend\n\nfunction apply (f: Function<(String), returns: String>) returns String\n\treturn f(\"a\")\nend\n\nfunction main returns String\n\treturn apply(function (v)\n\t\tvar first: String = v\n\t\tvar second: String = v\n\t\treturn twice(v)\n\tend)\nend\n";
+		assert_eq!(run_source("overloads_undone", source), Ok(String::from("aa")));
+	}
+
+	#[test]
+	fn captured_variables_cant_be_assigned () {
+		let source = "function main returns int\n\tvar count = 0\n\tvar counter = function() returns int\n\t\tcount += 1\n\t\treturn count\n\tend\n\treturn counter()\nend\n";
+		assert_eq!(run_source("captured_variables", source), Err(vec!(String::from("cannot assign to `count` because this anonymous function captured it"))));
+	}
+
+	#[test]
+	fn string_to_uint () {
+		let source = "function main returns (Optional<uint>, Optional<uint>)\n\treturn (\" 42\".to_uint(), \"-1\".to_uint())\nend\n";
+		assert_eq!(run_source("string_to_uint", source), Ok(String::from("(42, Nothing)")));
+	}
+
+	#[test]
+	fn type_errors_stop_the_program () {
+		let source = "function main returns int\n\treturn \"one\"\nend\n";
+		assert_eq!(run_source("type_errors", source), Err(vec!(String::from("mismatched types, expected `int`, found `String`"))));
+	}
+
+}
@@ -0,0 +1,185 @@
+use crate::prelude::*;
+use std::{io, rc::Rc, cell::RefCell, cmp::Ordering};
+
+
+
+macro_rules! int_operation {
+	($operator:expr, $left:expr, $right:expr, $variant:ident) => {
+		match $operator {
+			Operator::Plus => $left.checked_add($right).map(Value::$variant).ok_or(RawRuntimeError::IntegerOverflow {operation: "addition"}),
+			Operator::Minus => $left.checked_sub($right).map(Value::$variant).ok_or(RawRuntimeError::IntegerOverflow {operation: "subtraction"}),
+			Operator::Times => $left.checked_mul($right).map(Value::$variant).ok_or(RawRuntimeError::IntegerOverflow {operation: "multiplication"}),
+			Operator::Divide if $right == 0 => Err(RawRuntimeError::DivideByZero),
+			Operator::Divide => $left.checked_div($right).map(Value::$variant).ok_or(RawRuntimeError::IntegerOverflow {operation: "division"}),
+			Operator::Modulo if $right == 0 => Err(RawRuntimeError::DivideByZero),
+			Operator::Modulo => $left.checked_rem_euclid($right).map(Value::$variant).ok_or(RawRuntimeError::IntegerOverflow {operation: "modulo"}),
+			Operator::Power => u32::try_from($right).ok().and_then(|right| $left.checked_pow(right)).map(Value::$variant).ok_or(RawRuntimeError::IntegerOverflow {operation: "exponentiation"}),
+			Operator::ShiftLeft => u32::try_from($right).ok().and_then(|right| $left.checked_shl(right)).map(Value::$variant).ok_or(RawRuntimeError::IntegerOverflow {operation: "left shift"}),
+			Operator::ShiftRight => u32::try_from($right).ok().and_then(|right| $left.checked_shr(right)).map(Value::$variant).ok_or(RawRuntimeError::IntegerOverflow {operation: "right shift"}),
+			operator => compare_values(operator, $left.partial_cmp(&$right)),
+		}
+	};
+}
+
+macro_rules! float_operation {
+	($operator:expr, $left:expr, $right:expr, $variant:ident) => {
+		match $operator {
+			Operator::Plus => Ok(Value::$variant($left + $right)),
+			Operator::Minus => Ok(Value::$variant($left - $right)),
+			Operator::Times => Ok(Value::$variant($left * $right)),
+			Operator::Divide => Ok(Value::$variant($left / $right)),
+			Operator::Modulo => Ok(Value::$variant($left.rem_euclid($right))),
+			Operator::Power => Ok(Value::$variant($left.powf($right))),
+			operator => compare_values(operator, $left.partial_cmp(&$right)),
+		}
+	};
+}
+
+
+
+pub fn run_operation (operator: &Operator, left: Value, right: Value) -> Result<Value, RawRuntimeError> {
+	match operator {
+		Operator::Equal => return Ok(Value::Bool(left == right)),
+		Operator::NotEqual => return Ok(Value::Bool(left != right)),
+		_ => {}
+	}
+	match (left, right) {
+		(Value::Int8    (left), Value::Int8    (right)) => int_operation!(operator, left, right, Int8),
+		(Value::Int16   (left), Value::Int16   (right)) => int_operation!(operator, left, right, Int16),
+		(Value::Int32   (left), Value::Int32   (right)) => int_operation!(operator, left, right, Int32),
+		(Value::Int64   (left), Value::Int64   (right)) => int_operation!(operator, left, right, Int64),
+		(Value::UInt8   (left), Value::UInt8   (right)) => int_operation!(operator, left, right, UInt8),
+		(Value::UInt16  (left), Value::UInt16  (right)) => int_operation!(operator, left, right, UInt16),
+		(Value::UInt32  (left), Value::UInt32  (right)) => int_operation!(operator, left, right, UInt32),
+		(Value::UInt64  (left), Value::UInt64  (right)) => int_operation!(operator, left, right, UInt64),
+		(Value::Float32 (left), Value::Float32 (right)) => float_operation!(operator, left, right, Float32),
+		(Value::Float64 (left), Value::Float64 (right)) => float_operation!(operator, left, right, Float64),
+		(Value::String  (left), Value::String  (right)) => match operator {
+			Operator::Concat => Ok(Value::new_string(left.borrow().to_string() + &right.borrow())),
+			operator => compare_values(operator, left.borrow().partial_cmp(&*right.borrow())),
+		},
+		_ => Err(RawRuntimeError::InvalidOperation {operator: operator.to_string()}),
+	}
+}
+
+fn compare_values (operator: &Operator, ordering: Option<Ordering>) -> Result<Value, RawRuntimeError> {
+	let Some(ordering) = ordering else {
+		return Ok(Value::Bool(false));
+	};
+	Ok(Value::Bool(match operator {
+		Operator::GreaterThan => ordering.is_gt(),
+		Operator::LessThan => ordering.is_lt(),
+		Operator::GreaterOrEqual => ordering.is_ge(),
+		Operator::LessOrEqual => ordering.is_le(),
+		_ => return Err(RawRuntimeError::InvalidOperation {operator: operator.to_string()}),
+	}))
+}
+
+
+
+pub fn get_int_value (value: &Value) -> Option<i128> {
+	Some(match value {
+		Value::Int8   (value) => *value as i128,
+		Value::Int16  (value) => *value as i128,
+		Value::Int32  (value) => *value as i128,
+		Value::Int64  (value) => *value as i128,
+		Value::UInt8  (value) => *value as i128,
+		Value::UInt16 (value) => *value as i128,
+		Value::UInt32 (value) => *value as i128,
+		Value::UInt64 (value) => *value as i128,
+		Value::Bool   (value) => *value as i128,
+		_ => return None,
+	})
+}
+
+// casts follow rust's `as` rules (floats saturate, ints wrap)
+pub fn cast_value (value: Value, stack: StackType) -> Value {
+	let float_value = match value {
+		Value::Float32 (value) => Some(value as f64),
+		Value::Float64 (value) => Some(value),
+		_ => None,
+	};
+	let int_value = match float_value {
+		Some(float_value) => float_value as i128,
+		None => get_int_value(&value).unwrap(),
+	};
+	let float_value = float_value.unwrap_or(int_value as f64);
+	match stack {
+		StackType::Int8    => Value::Int8(int_value as i8),
+		StackType::Int16   => Value::Int16(int_value as i16),
+		StackType::Int32   => Value::Int32(int_value as i32),
+		StackType::Int64   => Value::Int64(int_value as i64),
+		StackType::UInt8   => Value::UInt8(int_value as u8),
+		StackType::UInt16  => Value::UInt16(int_value as u16),
+		StackType::UInt32  => Value::UInt32(int_value as u32),
+		StackType::UInt64  => Value::UInt64(int_value as u64),
+		StackType::Float32 => Value::Float32(float_value as f32),
+		StackType::Float64 => Value::Float64(float_value),
+		StackType::Bool    => Value::Bool(int_value != 0),
+		_ => value,
+	}
+}
+
+
+
+
+
+pub fn run_native (native: NativeFunction, mut args: Vec<Value>) -> Result<Value, RawRuntimeError> {
+	Ok(match native {
+
+		NativeFunction::ReadLine => {
+			let mut line = String::new();
+			io::stdin().read_line(&mut line).map_err(|source| RawRuntimeError::Io {source})?;
+			let line_len = line.trim_end_matches(['\n', '\r']).len();
+			line.truncate(line_len);
+			Value::new_string(line)
+		}
+
+		NativeFunction::Abs => match args.remove(0) {
+			Value::Int8    (value) => Value::Int8(value.checked_abs().ok_or(RawRuntimeError::IntegerOverflow {operation: "abs"})?),
+			Value::Int16   (value) => Value::Int16(value.checked_abs().ok_or(RawRuntimeError::IntegerOverflow {operation: "abs"})?),
+			Value::Int32   (value) => Value::Int32(value.checked_abs().ok_or(RawRuntimeError::IntegerOverflow {operation: "abs"})?),
+			Value::Int64   (value) => Value::Int64(value.checked_abs().ok_or(RawRuntimeError::IntegerOverflow {operation: "abs"})?),
+			Value::Float32 (value) => Value::Float32(value.abs()),
+			Value::Float64 (value) => Value::Float64(value.abs()),
+			unsigned_value => unsigned_value,
+		},
+
+		NativeFunction::StringLength => Value::UInt64(get_string(&args[0]).chars().count() as u64),
+		NativeFunction::StringTrim => Value::new_string(get_string(&args[0]).trim()),
+		NativeFunction::StringToUpper => Value::new_string(get_string(&args[0]).to_uppercase()),
+		NativeFunction::StringToLower => Value::new_string(get_string(&args[0]).to_lowercase()),
+		NativeFunction::StringContains => Value::Bool(get_string(&args[0]).contains(&get_string(&args[1]))),
+		NativeFunction::StringSplit => {
+			let items = get_string(&args[0]).split(&get_string(&args[1])).map(Value::new_string).collect();
+			Value::Array(Rc::new(RefCell::new(items)))
+		}
+		// spaces around the number are ignored, and anything else that isn't a whole number that fits in a `uint` gives `Nothing`
+		NativeFunction::StringToUInt => {
+			let object = match get_string(&args[0]).trim().parse::<u64>() {
+				Ok(value) => ObjectValue {type_id: bytecode::OPTIONAL_TYPE_ID, variant: bytecode::FILLED_VARIANT, feilds: vec!(Value::UInt64(value))},
+				Err(_) => ObjectValue {type_id: bytecode::OPTIONAL_TYPE_ID, variant: bytecode::NOTHING_VARIANT, feilds: vec!()},
+			};
+			Value::Object(Rc::new(RefCell::new(object)))
+		}
+
+		NativeFunction::ArrayLength => Value::UInt64(get_array(&args[0]).borrow().len() as u64),
+		NativeFunction::ArrayAdd => {
+			let item = args.pop().unwrap();
+			get_array(&args[0]).borrow_mut().push(item);
+			Value::None
+		}
+		NativeFunction::ArrayContains => Value::Bool(get_array(&args[0]).borrow().contains(&args[1])),
+
+	})
+}
+
+fn get_string (value: &Value) -> String {
+	let Value::String(value) = value else {unreachable!()};
+	value.borrow().to_string()
+}
+
+fn get_array (value: &Value) -> &Rc<RefCell<Vec<Value>>> {
+	let Value::Array(value) = value else {unreachable!()};
+	value
+}
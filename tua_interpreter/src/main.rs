@@ -10,34 +10,33 @@
 // nightly features
 #![feature(box_syntax)]
 #![feature(try_trait_v2)]
-#![feature(backtrace_frames)]
 
 
 
 mod compiler_mod;
 mod data_mod;
+mod interpreter_mod;
 mod fns;
 mod logger;
 mod additions;
+mod cli;
+mod diagnostics;
 mod prelude;
 
 use prelude::*;
 
-use std::env;
+use std::{env, process::ExitCode};
 
 
 
-fn main() {
-    env::set_var("RUST_BACKTRACE", "1");
-    let mut path = fns::get_program_dir();
-    path.push("input");
-    let mut logger = Logger::new("Test compiling Tua code");
-    let (_, errors) = match compiler::compile_from_dir(path, &mut logger) {
+fn main() -> ExitCode {
+    let args = match cli::parse_args(env::args().skip(1)) {
         Ok(v) => v,
-        Err(error) => {
-            logger.print_all();
-            panic!("Fatal error:\n{error}")
+        Err(message) => {
+            eprintln!("error: {message}");
+            eprintln!("run `tua_interpreter help` for usage");
+            return ExitCode::from(cli::EXIT_USAGE_ERROR);
         }
     };
-    logger.print_all();
+    cli::run_cli(args)
 }
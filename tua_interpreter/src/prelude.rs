@@ -1,4 +1,4 @@
-pub use crate::{*, compiler_mod::*, logger::*, additions::*, //fns::*,
+pub use crate::{*, compiler_mod::*, interpreter_mod::*, logger::*, additions::*, //fns::*,
     data_mod::{data::*, errors::*}
 };
 